@@ -0,0 +1,96 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Manage aliases stored in the release JSON.
+//!
+//! Stored aliases are used by every command that takes `--alias`, so they don't need to be passed
+//! in each time. An alias passed in on the command line takes precedence over a stored alias with
+//! the same name.
+
+use crate::{
+    command::Alias,
+    release_json::{single_project, single_project_mut},
+};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use mukti_metadata::{MuktiProject, MuktiReleasesJson, ProjectAlias};
+use std::collections::BTreeMap;
+
+/// Store `aliases`, replacing any existing aliases with the same names.
+pub(crate) fn add_aliases(release_json: &mut MuktiReleasesJson, aliases: &[Alias]) -> Result<()> {
+    for alias in aliases {
+        let project = project_mut(release_json, alias.project.as_deref())?;
+        let new = ProjectAlias {
+            target: alias.target_format.target.clone(),
+            format: alias.target_format.format.clone(),
+            version_req: alias.version_req.clone(),
+            extra: BTreeMap::new(),
+        };
+        let new_desc = describe(&new);
+        match project.aliases.insert(alias.alias.clone(), new) {
+            Some(old) => eprintln!("alias {}: {} -> {new_desc}", alias.alias, describe(&old)),
+            None => eprintln!("alias {}: (new) -> {new_desc}", alias.alias),
+        }
+    }
+    Ok(())
+}
+
+/// Remove the stored aliases `names`, each of which can be scoped to a project as `PROJECT:NAME`.
+pub(crate) fn remove_aliases(release_json: &mut MuktiReleasesJson, names: &[String]) -> Result<()> {
+    for name in names {
+        let (project_name, name) = match name.split_once(':') {
+            Some((project_name, name)) => (Some(project_name), name),
+            None => (None, name.as_str()),
+        };
+        let project = project_mut(release_json, project_name)?;
+        match project.aliases.remove(name) {
+            Some(old) => eprintln!("alias {name}: {} -> (removed)", describe(&old)),
+            None => bail!("alias {name} not found"),
+        }
+    }
+    Ok(())
+}
+
+/// Print stored aliases to stdout, in the same syntax as `--alias`.
+///
+/// Aliases are only scoped with a `PROJECT:` prefix if the release JSON has several projects.
+pub(crate) fn list_aliases(release_json: &MuktiReleasesJson) -> Result<()> {
+    let scoped = single_project(release_json).is_err();
+    for (project_name, project) in &release_json.projects {
+        for (name, alias) in &project.aliases {
+            if scoped {
+                print!("{project_name}:");
+            }
+            print!("{name}");
+            if let Some(req) = &alias.version_req {
+                print!("@{req}");
+            }
+            println!("={}:{}", alias.target, alias.format);
+        }
+    }
+    Ok(())
+}
+
+fn project_mut<'a>(
+    release_json: &'a mut MuktiReleasesJson,
+    project_name: Option<&str>,
+) -> Result<&'a mut MuktiProject> {
+    match project_name {
+        Some(project_name) => release_json
+            .projects
+            .get_mut(project_name)
+            .ok_or_else(|| eyre!("project {project_name} not found in the release JSON")),
+        None => {
+            let (_, project) = single_project_mut(release_json).wrap_err(
+                "in a release JSON with several projects, scope aliases as PROJECT:ALIAS",
+            )?;
+            Ok(project)
+        }
+    }
+}
+
+fn describe(alias: &ProjectAlias) -> String {
+    match &alias.version_req {
+        Some(req) => format!("{}:{} (for {req})", alias.target, alias.format),
+        None => format!("{}:{}", alias.target, alias.format),
+    }
+}
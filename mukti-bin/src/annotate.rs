@@ -0,0 +1,59 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Attach release notes to versions.
+
+use crate::release_json::single_project_mut;
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, VersionRange};
+use semver::Version;
+
+/// Set the release notes and changelog URL for `version`.
+///
+/// Fields that aren't specified are left unchanged, unless `clear` is set, in which case they're
+/// removed.
+pub(crate) fn annotate(
+    release_json: &mut MuktiReleasesJson,
+    version: &Version,
+    notes_file: Option<&Utf8Path>,
+    changelog_url: Option<&str>,
+    clear: bool,
+) -> Result<()> {
+    if notes_file.is_none() && changelog_url.is_none() && !clear {
+        bail!("at least one of --notes-file, --changelog-url or --clear is required");
+    }
+
+    let notes = notes_file.map(read_notes).transpose()?;
+
+    let (_, project) = single_project_mut(release_json)?;
+    let version_data = project
+        .ranges
+        .get_mut(&VersionRange::from_version(version))
+        .and_then(|range| range.versions.get_mut(version));
+    let Some(version_data) = version_data else {
+        bail!("version {version} not found in releases JSON");
+    };
+
+    if clear {
+        version_data.notes = None;
+        version_data.changelog_url = None;
+    }
+    if let Some(notes) = notes {
+        version_data.notes = Some(notes);
+    }
+    if let Some(changelog_url) = changelog_url {
+        version_data.changelog_url = Some(changelog_url.to_owned());
+    }
+
+    eprintln!("annotated version {version}");
+    Ok(())
+}
+
+/// Read release notes from a file.
+pub(crate) fn read_notes(path: &Utf8Path) -> Result<String> {
+    let notes = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read release notes at {}", path))?;
+    // Trailing newlines from the notes file aren't meaningful.
+    Ok(notes.trim_end().to_owned())
+}
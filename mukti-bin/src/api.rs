@@ -0,0 +1,143 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate a static JSON API from the release JSON.
+//!
+//! Documents are written to `v1/{project}/latest.json`, `v1/{project}/{range}.json` and
+//! `v1/{project}/{version}.json`, so clients can fetch just the part they need.
+
+use crate::output::OutputOpts;
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, ReleaseStatus, ReleaseVersionData, VersionRange};
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const API_VERSION: &str = "v1";
+
+/// A single version, as served at `{version}.json` and `latest.json`.
+#[derive(Debug, Serialize)]
+struct VersionDocument<'a> {
+    project: &'a str,
+    version: &'a Version,
+    #[serde(flatten)]
+    data: &'a ReleaseVersionData,
+}
+
+/// A version range, as served at `{range}.json`.
+#[derive(Debug, Serialize)]
+struct RangeDocument<'a> {
+    project: &'a str,
+    range: VersionRange,
+    latest: &'a Version,
+    is_prerelease: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eol_date: Option<&'a str>,
+    /// Versions in this range, newest first.
+    versions: Vec<VersionSummary<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionSummary<'a> {
+    version: &'a Version,
+    status: ReleaseStatus,
+}
+
+/// Write API documents for every project to `out_dir`, returning the files that changed relative
+/// to it.
+///
+/// Documents left over from versions that have since been removed are deleted.
+pub(crate) fn generate_api(
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+
+    for (project_name, project) in &release_json.projects {
+        // Map of file name to contents.
+        let mut documents = BTreeMap::new();
+
+        if let Some(range) = &project.latest {
+            let range_data = &project.ranges[range];
+            let document = VersionDocument {
+                project: project_name,
+                version: &range_data.latest,
+                data: &range_data.versions[&range_data.latest],
+            };
+            documents.insert("latest.json".to_owned(), to_json(&document)?);
+        }
+
+        for (range, range_data) in &project.ranges {
+            let document = RangeDocument {
+                project: project_name,
+                range: *range,
+                latest: &range_data.latest,
+                is_prerelease: range_data.is_prerelease,
+                eol_date: range_data.eol_date.as_deref(),
+                versions: range_data
+                    .versions
+                    .iter()
+                    .rev()
+                    .map(|(version, version_data)| VersionSummary {
+                        version,
+                        status: version_data.status.clone(),
+                    })
+                    .collect(),
+            };
+            documents.insert(format!("{range}.json"), to_json(&document)?);
+
+            for (version, version_data) in &range_data.versions {
+                let document = VersionDocument {
+                    project: project_name,
+                    version,
+                    data: version_data,
+                };
+                documents.insert(format!("{version}.json"), to_json(&document)?);
+            }
+        }
+
+        let rel_dir = Utf8Path::new(API_VERSION).join(project_name);
+        let dir = out_dir.join(&rel_dir);
+        std::fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("failed to create directory {dir}"))?;
+        remove_stale(&dir, &documents.keys().map(String::as_str).collect())?;
+        let changed_before = changed.len();
+        for (file_name, contents) in &documents {
+            if output_opts.write_file(&dir.join(file_name), contents)? {
+                changed.push(rel_dir.join(file_name));
+            }
+        }
+        eprintln!(
+            "wrote {} documents for {project_name} to {dir} ({} changed)",
+            documents.len(),
+            changed.len() - changed_before
+        );
+    }
+
+    Ok(changed)
+}
+
+fn to_json(document: &impl Serialize) -> Result<String> {
+    let mut out = serde_json::to_string(document)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// Remove JSON files in `dir` that aren't in `keep`.
+fn remove_stale(dir: &Utf8Path, keep: &BTreeSet<&str>) -> Result<()> {
+    for entry in dir
+        .read_dir_utf8()
+        .wrap_err_with(|| format!("failed to read directory {dir}"))?
+    {
+        let entry = entry.wrap_err_with(|| format!("failed to read directory {dir}"))?;
+        let file_name = entry.file_name();
+        if file_name.ends_with(".json") && !keep.contains(file_name) {
+            std::fs::remove_file(entry.path())
+                .wrap_err_with(|| format!("failed to remove stale document {}", entry.path()))?;
+            eprintln!("removed stale document {}", entry.path());
+        }
+    }
+    Ok(())
+}
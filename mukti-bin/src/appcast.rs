@@ -0,0 +1,158 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate a Sparkle appcast, so macOS apps can update themselves from the release JSON.
+
+use crate::{output::OutputOpts, redirects::xml_escape, release_json::single_project};
+use camino::Utf8Path;
+use chrono::DateTime;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{MuktiReleasesJson, ReleaseLocation, ReleaseVersionData};
+use std::fmt::Write as _;
+
+const SPARKLE_NAMESPACE: &str = "http://www.andymatuschak.org/xml-namespaces/sparkle";
+
+/// Apple targets in order of preference, if --target isn't passed in.
+const APPLE_TARGETS: &[&str] = &[
+    "universal-apple-darwin",
+    "aarch64-apple-darwin",
+    "x86_64-apple-darwin",
+];
+
+/// Formats Sparkle can install, in order of preference.
+const SPARKLE_FORMATS: &[&str] = &["dmg", "zip", "tar.xz", "tar.bz2", "tar.gz"];
+
+/// Write an appcast with an item for every active version that has an artifact Sparkle can
+/// install.
+///
+/// Pre-releases are put on a Sparkle channel named after their first pre-release identifier, e.g.
+/// `beta` for 1.0.0-beta.2, so only apps that opt into that channel are offered them.
+pub(crate) fn generate_appcast(
+    release_json: &MuktiReleasesJson,
+    target: Option<&str>,
+    out: &Utf8Path,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+
+    let mut xml = String::with_capacity(4096);
+    writeln!(
+        xml,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:sparkle="{SPARKLE_NAMESPACE}">
+  <channel>
+    <title>{}</title>"#,
+        xml_escape(project.info.display_name.as_deref().unwrap_or(project_name))
+    )
+    .expect("writing to a string is infallible");
+    if let Some(homepage) = &project.info.homepage {
+        writeln!(xml, "    <link>{}</link>", xml_escape(homepage))
+            .expect("writing to a string is infallible");
+    }
+    if let Some(description) = &project.info.description {
+        writeln!(
+            xml,
+            "    <description>{}</description>",
+            xml_escape(description)
+        )
+        .expect("writing to a string is infallible");
+    }
+
+    let mut items = 0;
+    for (version, version_data) in project.all_versions() {
+        if !version_data.status.is_available() {
+            continue;
+        }
+        let Some(location) = best_location(version_data, target) else {
+            continue;
+        };
+
+        let version_str = version.to_string();
+        writeln!(
+            xml,
+            "    <item>
+      <title>{}</title>
+      <sparkle:version>{}</sparkle:version>
+      <sparkle:shortVersionString>{}</sparkle:shortVersionString>",
+            xml_escape(&version_str),
+            xml_escape(&version_str),
+            xml_escape(&version_str),
+        )
+        .expect("writing to a string is infallible");
+        if let Some(date) = version_data
+            .release_date
+            .as_deref()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        {
+            writeln!(xml, "      <pubDate>{}</pubDate>", date.to_rfc2822())
+                .expect("writing to a string is infallible");
+        }
+        // Sparkle prefers a release notes link to an inline description, so only fall back to
+        // the release URL if there are no notes.
+        match (&version_data.changelog_url, &version_data.notes) {
+            (None, Some(notes)) => writeln!(
+                xml,
+                r#"      <description sparkle:format="plain-text">{}</description>"#,
+                xml_escape(notes)
+            ),
+            (changelog_url, _) => writeln!(
+                xml,
+                "      <sparkle:releaseNotesLink>{}</sparkle:releaseNotesLink>",
+                xml_escape(
+                    changelog_url
+                        .as_deref()
+                        .unwrap_or(&version_data.release_url)
+                )
+            ),
+        }
+        .expect("writing to a string is infallible");
+        if let Some(channel) = version.pre.split('.').next().filter(|c| !c.is_empty()) {
+            writeln!(
+                xml,
+                "      <sparkle:channel>{}</sparkle:channel>",
+                xml_escape(channel)
+            )
+            .expect("writing to a string is infallible");
+        }
+        write!(
+            xml,
+            r#"      <enclosure url="{}" type="application/octet-stream""#,
+            xml_escape(&location.url)
+        )
+        .expect("writing to a string is infallible");
+        if let Some(size) = location.size {
+            write!(xml, r#" length="{size}""#).expect("writing to a string is infallible");
+        }
+        xml.push_str(" />\n    </item>\n");
+        items += 1;
+    }
+
+    xml.push_str("  </channel>\n</rss>\n");
+
+    if items == 0 {
+        bail!(
+            "no active versions have a {} artifact for {}",
+            SPARKLE_FORMATS.join(", "),
+            target.unwrap_or("an Apple target")
+        );
+    }
+    OutputOpts::default().write_file(out, &xml)?;
+    eprintln!("wrote {items} items for {project_name} to {out}");
+    Ok(())
+}
+
+/// Pick the artifact to offer for a version: the preferred format of `target`, or of the most
+/// preferred Apple target available.
+fn best_location<'a>(
+    version_data: &'a ReleaseVersionData,
+    target: Option<&str>,
+) -> Option<&'a ReleaseLocation> {
+    let targets: Vec<&str> = match target {
+        Some(target) => vec![target],
+        None => APPLE_TARGETS.to_vec(),
+    };
+    targets.iter().find_map(|target| {
+        SPARKLE_FORMATS
+            .iter()
+            .find_map(|format| version_data.location(target, format))
+    })
+}
@@ -0,0 +1,231 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate metadata for a flat APT repository from the `.deb` artifacts in the release JSON.
+//!
+//! Each `.deb` is downloaded so that `Packages` lists the fields from its control file, such as
+//! `Depends` and the full Debian version, rather than ones derived from the release JSON.
+//!
+//! The repository is used with a sources line like `deb https://example.com/repo/ ./`. APT fetches
+//! packages from the `Filename` in `Packages`, relative to the repository URL, so the `.deb` files
+//! need to be copied or redirected there unless they're already served from under `--base-url`.
+//!
+//! `Release` isn't signed: run e.g. `gpg --clearsign -o InRelease Release` afterwards.
+
+use crate::{
+    fetch::Fetcher,
+    output::{write_bytes, OutputOpts},
+    packages::fetch_packages,
+    publish::file_name_from_url,
+    release_json::single_project,
+    scheduler::Scheduler,
+};
+use camino::Utf8Path;
+use chrono::Utc;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use mukti_metadata::{Format, MuktiReleasesJson};
+use sha2::{Digest as _, Sha256};
+use std::{
+    fmt::Write as _,
+    io::{Read, Write as _},
+    path::Path,
+};
+use xz2::read::XzDecoder;
+
+pub(crate) async fn generate_apt(
+    release_json: &MuktiReleasesJson,
+    base_url: Option<&str>,
+    out_dir: &Utf8Path,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+    let debs = fetch_packages(project, Format::Deb, DebControl::read, scheduler, fetcher).await?;
+    if debs.is_empty() {
+        bail!("no active versions have .deb artifacts");
+    }
+
+    let mut packages = String::new();
+    let mut architectures = Vec::new();
+    for deb in &debs {
+        let file_name = match base_url.and_then(|base_url| {
+            deb.location
+                .url
+                .strip_prefix(base_url.trim_end_matches('/'))
+        }) {
+            Some(path) => path.trim_start_matches('/'),
+            None => file_name_from_url(&deb.location.url).unwrap_or(&deb.location.url),
+        };
+
+        // The control file is listed as is, followed by the fields describing the file itself.
+        writeln!(
+            packages,
+            "{}\nFilename: ./{file_name}\nSize: {}\nSHA256: {}\n",
+            deb.metadata.paragraph, deb.size, deb.sha256
+        )
+        .expect("writing to a string is infallible");
+        let architecture = deb.metadata.architecture();
+        // Packages for all architectures are listed without a Release entry of their own.
+        if architecture != "all" && !architectures.contains(&architecture) {
+            architectures.push(architecture);
+        }
+    }
+    let count = debs.len();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    let packages_gz = encoder
+        .write_all(packages.as_bytes())
+        .and_then(|()| encoder.finish())
+        .wrap_err("failed to compress Packages")?;
+
+    std::fs::create_dir_all(out_dir)
+        .wrap_err_with(|| format!("failed to create directory {out_dir}"))?;
+    let output_opts = OutputOpts::default();
+    let mut changed = output_opts.write_file(&out_dir.join("Packages"), &packages)?;
+    changed |= write_bytes(&out_dir.join("Packages.gz"), &packages_gz)?;
+
+    // The date changes on every run, so only rewrite Release if the index did.
+    let release_path = out_dir.join("Release");
+    if changed || !release_path.exists() {
+        architectures.sort_unstable();
+        let mut release = format!(
+            "Origin: {project_name}
+Label: {project_name}
+Date: {}
+Architectures: {}
+SHA256:
+",
+            Utc::now().format("%a, %d %b %Y %H:%M:%S UTC"),
+            architectures.join(" "),
+        );
+        for (name, contents) in [
+            ("Packages", packages.as_bytes()),
+            ("Packages.gz", &packages_gz),
+        ] {
+            writeln!(
+                release,
+                " {} {} {name}",
+                hex::encode(Sha256::digest(contents)),
+                contents.len()
+            )
+            .expect("writing to a string is infallible");
+        }
+        output_opts.write_file(&release_path, &release)?;
+    }
+
+    eprintln!(
+        "wrote {count} packages for {project_name} to {out_dir}{}",
+        if changed { "" } else { " (unchanged)" }
+    );
+    Ok(())
+}
+
+/// The control file of a `.deb`.
+#[derive(Debug)]
+pub(crate) struct DebControl {
+    /// The control paragraph, without trailing newlines.
+    paragraph: String,
+}
+
+impl DebControl {
+    /// Read the control file from the `control.tar` member of a `.deb`.
+    pub(crate) fn read(deb: &[u8]) -> Result<Self> {
+        let mut archive = ar::Archive::new(deb);
+        while let Some(entry) = archive.next_entry() {
+            let entry = entry.wrap_err("invalid .deb archive")?;
+            let name = String::from_utf8_lossy(entry.header().identifier())
+                .trim_end_matches('/')
+                .to_owned();
+            let reader: Box<dyn Read + '_> = match name.as_str() {
+                "control.tar" => Box::new(entry),
+                "control.tar.gz" => Box::new(GzDecoder::new(entry)),
+                "control.tar.xz" => Box::new(XzDecoder::new(entry)),
+                "control.tar.zst" => Box::new(
+                    zstd::Decoder::new(entry).wrap_err("failed to decompress control.tar.zst")?,
+                ),
+                _ => continue,
+            };
+
+            let mut control_tar = tar::Archive::new(reader);
+            for file in control_tar
+                .entries()
+                .wrap_err_with(|| format!("failed to read {name}"))?
+            {
+                let mut file = file.wrap_err_with(|| format!("failed to read {name}"))?;
+                let path = file.path()?;
+                if path.strip_prefix(".").unwrap_or(&path) == Path::new("control") {
+                    let mut text = String::new();
+                    file.read_to_string(&mut text)
+                        .wrap_err("failed to read control file")?;
+                    return Self::parse(&text);
+                }
+            }
+            bail!("{name} doesn't contain a control file");
+        }
+        bail!(".deb doesn't contain a control.tar member")
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let control = Self {
+            paragraph: text.trim_end().to_owned(),
+        };
+        for field in ["Package", "Version", "Architecture"] {
+            if control.field(field).is_none() {
+                bail!("control file is missing the {field} field");
+            }
+        }
+        Ok(control)
+    }
+
+    /// Returns the value of a field, without any continuation lines.
+    fn field(&self, name: &str) -> Option<&str> {
+        self.paragraph.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (!key.starts_with([' ', '\t']) && key.eq_ignore_ascii_case(name)).then(|| value.trim())
+        })
+    }
+
+    fn architecture(&self) -> &str {
+        self.field("Architecture")
+            .expect("Architecture was checked when parsing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FIXTURE_DEB: &[u8] = include_bytes!("../../fixtures/hello_1.2.3-1_amd64.deb");
+
+    #[test]
+    fn test_read_deb_control() {
+        let control = DebControl::read(FIXTURE_DEB).unwrap();
+        assert_eq!(control.field("Package"), Some("hello"));
+        assert_eq!(control.field("Version"), Some("1.2.3-1"));
+        assert_eq!(control.architecture(), "amd64");
+        assert_eq!(control.field("Depends"), Some("libc6 (>= 2.31)"));
+        assert_eq!(control.field("installed-size"), Some("4"));
+        // Continuation lines are kept in the paragraph, but not returned as part of the field.
+        assert_eq!(control.field("Description"), Some("Say hello"));
+        assert!(control
+            .paragraph
+            .ends_with("Description: Say hello\n A fixture package for testing generate-apt.\n .\n It prints hello."));
+    }
+
+    #[test]
+    fn test_read_deb_control_errors() {
+        assert!(DebControl::read(b"not a deb").is_err());
+
+        let err = DebControl::parse("Package: hello\nVersion: 1.2.3-1\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "control file is missing the Architecture field"
+        );
+        // A continuation line that looks like a field doesn't count.
+        assert!(DebControl::parse(
+            "Package: hello\nDescription: x\n Version: 1\nArchitecture: all\n"
+        )
+        .is_err());
+    }
+}
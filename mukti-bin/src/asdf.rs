@@ -0,0 +1,113 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate the version list and download mapping a generic asdf or mise plugin can install from.
+//!
+//! * `versions.txt` lists every active version, oldest first and space-separated, which is the
+//!   format `bin/list-all` prints.
+//! * `versions/{version}.tsv` has a line per artifact: `OS ARCH FORMAT URL SHA256`, tab-separated.
+//!   `OS` and `ARCH` match the lowercased output of `uname -s` and `uname -m`, so `bin/download`
+//!   can pick a line with e.g. `awk -v os=linux -v arch=x86_64 '$1 == os && $2 == arch'`. The
+//!   SHA-256 column is `-` if there's no checksum.
+
+use crate::{output::OutputOpts, release_json::single_project};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{DigestAlgorithm, MuktiReleasesJson};
+use std::fmt::Write as _;
+
+pub(crate) fn generate_asdf(
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let (project_name, project) = single_project(release_json)?;
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+    let mut write = |rel_path: Utf8PathBuf, contents: &str| -> Result<()> {
+        let path = out_dir.join(&rel_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+        }
+        if output_opts.write_file(&path, contents)? {
+            changed.push(rel_path);
+        }
+        Ok(())
+    };
+
+    let mut versions: Vec<_> = project
+        .all_versions()
+        .filter(|(_, version_data)| version_data.status.is_available())
+        .collect();
+    versions.reverse();
+
+    let version_list: Vec<_> = versions
+        .iter()
+        .map(|(version, _)| version.to_string())
+        .collect();
+    write(
+        "versions.txt".into(),
+        &format!("{}\n", version_list.join(" ")),
+    )?;
+
+    for (version, version_data) in &versions {
+        let mut tsv = String::new();
+        for location in version_data.installable_locations() {
+            let sha256 = location
+                .checksums
+                .get(&DigestAlgorithm::Sha256)
+                .map_or("-", |digest| digest.0.as_str());
+            for (os, arch) in uname_pairs(&location.target) {
+                writeln!(
+                    tsv,
+                    "{os}\t{arch}\t{}\t{}\t{sha256}",
+                    location.format.normalized(),
+                    location.url,
+                )
+                .expect("writing to a string is infallible");
+            }
+        }
+        write(Utf8PathBuf::from(format!("versions/{version}.tsv")), &tsv)?;
+    }
+
+    eprintln!(
+        "wrote {} versions for {project_name} to {out_dir} ({} files changed)",
+        versions.len(),
+        changed.len()
+    );
+    Ok(changed)
+}
+
+/// The `uname -s` and `uname -m` values, lowercased, that a target runs on.
+fn uname_pairs(target: &str) -> Vec<(&'static str, &'static str)> {
+    let mut components = target.split('-');
+    let arch = components.next().unwrap_or_default();
+    let os = if target.contains("-linux") {
+        "linux"
+    } else if target.contains("-apple-darwin") {
+        "darwin"
+    } else if target.contains("-freebsd") {
+        "freebsd"
+    } else if target.contains("-illumos") || target.contains("-solaris") {
+        "sunos"
+    } else {
+        return Vec::new();
+    };
+
+    let archs: &[&'static str] = match (os, arch) {
+        // macOS reports arm64 rather than aarch64.
+        ("darwin", "aarch64") => &["arm64"],
+        ("darwin", "universal") => &["x86_64", "arm64"],
+        ("freebsd", "x86_64") => &["amd64"],
+        ("sunos", "x86_64") => &["i86pc"],
+        (_, "x86_64") => &["x86_64"],
+        (_, "aarch64") => &["aarch64"],
+        (_, "i686") => &["i686"],
+        (_, "armv7") => &["armv7l"],
+        (_, "riscv64gc") => &["riscv64"],
+        (_, "powerpc64le") => &["ppc64le"],
+        (_, "s390x") => &["s390x"],
+        _ => &[],
+    };
+    archs.iter().map(|arch| (os, *arch)).collect()
+}
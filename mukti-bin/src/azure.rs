@@ -0,0 +1,92 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal Azure Blob Storage client, supporting just enough of the API (Put Blob) to upload
+//! files, e.g. to the `$web` container that backs a static website.
+//!
+//! Requests are authorized with a shared access signature, resolved through [`SecretResolver`]
+//! as `AZURE_STORAGE_SAS_TOKEN`. `AZURE_STORAGE_ENDPOINT` can be used to point at a different
+//! blob endpoint, such as Azurite.
+
+use crate::{destination::ObjectMetadata, s3::uri_encode, secrets::SecretResolver};
+use color_eyre::eyre::{Result, WrapErr};
+use reqwest::{header, Client};
+
+const AZURE_STORAGE_SAS_TOKEN: &str = "AZURE_STORAGE_SAS_TOKEN";
+
+/// The version of the Blob service REST API that requests are made against.
+const API_VERSION: &str = "2023-11-03";
+
+pub(crate) struct AzureBlobClient {
+    client: Client,
+    endpoint: String,
+    container: String,
+    sas_token: String,
+}
+
+impl AzureBlobClient {
+    pub(crate) fn new(account: &str, container: &str, secrets: &SecretResolver) -> Result<Self> {
+        let endpoint = std::env::var("AZURE_STORAGE_ENDPOINT")
+            .map(|endpoint| endpoint.trim_end_matches('/').to_owned())
+            .unwrap_or_else(|_| format!("https://{account}.blob.core.windows.net"));
+        let sas_token = secrets.require(AZURE_STORAGE_SAS_TOKEN)?;
+
+        Ok(Self {
+            client: Client::builder()
+                .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            endpoint,
+            container: container.to_owned(),
+            // The Azure portal shows tokens with a leading `?`, but the CLI doesn't.
+            sas_token: sas_token.trim_start_matches('?').to_owned(),
+        })
+    }
+
+    /// Upload a block blob, overwriting any existing blob at `key`.
+    ///
+    /// Blob storage serves blobs with the content type and cache control they were uploaded
+    /// with, so these must be set here for static websites (and any CDN in front of them) to
+    /// behave correctly.
+    pub(crate) async fn put_blob(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        metadata: &ObjectMetadata<'_>,
+    ) -> Result<()> {
+        let content_type = metadata.content_type.unwrap_or("application/octet-stream");
+        let mut request = self
+            .client
+            .put(format!(
+                "{}/{}/{}?{}",
+                self.endpoint,
+                self.container,
+                uri_encode(key),
+                self.sas_token
+            ))
+            .header("x-ms-version", API_VERSION)
+            .header("x-ms-blob-type", "BlockBlob")
+            .header("x-ms-blob-content-type", content_type)
+            .header(header::CONTENT_TYPE, content_type);
+        if let Some(cache_control) = metadata.cache_control {
+            request = request.header("x-ms-blob-cache-control", cache_control);
+        }
+
+        // The SAS token is part of the URL, so leave the URL out of errors.
+        let context = || {
+            format!(
+                "failed to upload {key} to Azure container {}",
+                self.container
+            )
+        };
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .wrap_err_with(context)?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)
+            .wrap_err_with(context)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Measure how long it takes to parse, serialize and resolve versions in a release JSON file.
+//!
+//! Allocations are only counted in builds with the `count-allocations` feature, which installs
+//! [`CountingAllocator`] as the global allocator.
+
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::MuktiReleasesJson;
+use semver::VersionReq;
+#[cfg(feature = "count-allocations")]
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// A global allocator that counts allocations, so `bench-parse` can report them.
+#[cfg(feature = "count-allocations")]
+#[doc(hidden)]
+pub struct CountingAllocator;
+
+#[cfg(feature = "count-allocations")]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+#[cfg(feature = "count-allocations")]
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "count-allocations")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+pub(crate) fn bench_parse(path: &Utf8Path, iterations: usize) -> Result<()> {
+    if iterations == 0 {
+        bail!("--iterations must be at least 1");
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read releases JSON file at {}", path))?;
+    let release_json: MuktiReleasesJson = serde_json::from_str(&contents)
+        .wrap_err_with(|| format!("failed to deserialize releases JSON at {}", path))?;
+
+    let (versions, locations) = count_versions(&release_json);
+    eprintln!(
+        "{}: {} bytes, {} projects, {} versions, {} locations",
+        path,
+        contents.len(),
+        release_json.projects.len(),
+        versions,
+        locations,
+    );
+    eprintln!("{iterations} iterations each");
+
+    let parse = measure(iterations, || {
+        serde_json::from_str::<MuktiReleasesJson>(black_box(&contents))
+            .expect("parsing succeeded above");
+    });
+    parse.report("parse");
+
+    let serialize = measure(iterations, || {
+        serde_json::to_string_pretty(black_box(&release_json)).expect("serialization succeeded");
+    });
+    serialize.report("serialize");
+
+    let resolve = measure(iterations, || resolve_all(black_box(&release_json)));
+    resolve.report("resolve");
+
+    Ok(())
+}
+
+fn count_versions(release_json: &MuktiReleasesJson) -> (usize, usize) {
+    let mut versions = 0;
+    let mut locations = 0;
+    for project in release_json.projects.values() {
+        for (_, version_data) in project.all_versions() {
+            versions += 1;
+            locations += version_data.locations.len();
+        }
+    }
+    (versions, locations)
+}
+
+/// Resolve every version in the file the way clients do: by exact version, and by the latest
+/// version matching its minor range.
+fn resolve_all(release_json: &MuktiReleasesJson) {
+    for project in release_json.projects.values() {
+        for (version, _) in project.all_versions() {
+            black_box(project.get_version_data(version));
+            let req = VersionReq::parse(&format!("~{}.{}", version.major, version.minor))
+                .expect("version requirement is valid");
+            black_box(project.get_latest_matching(&req));
+        }
+    }
+}
+
+struct Measurement {
+    times: Vec<Duration>,
+    /// The number of allocations and bytes allocated per iteration, if they're being counted.
+    allocations: Option<(u64, u64)>,
+}
+
+fn measure(iterations: usize, mut f: impl FnMut()) -> Measurement {
+    let mut times = Vec::with_capacity(iterations);
+    let before = allocation_counts();
+    for _ in 0..iterations {
+        let start = Instant::now();
+        f();
+        times.push(start.elapsed());
+    }
+    let iterations = iterations as u64;
+    Measurement {
+        times,
+        allocations: before.zip(allocation_counts()).map(|(before, after)| {
+            (
+                (after.0 - before.0) / iterations,
+                (after.1 - before.1) / iterations,
+            )
+        }),
+    }
+}
+
+/// Returns the total number of allocations and bytes allocated so far, if they're being counted.
+#[cfg(feature = "count-allocations")]
+fn allocation_counts() -> Option<(u64, u64)> {
+    Some((
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    ))
+}
+
+#[cfg(not(feature = "count-allocations"))]
+fn allocation_counts() -> Option<(u64, u64)> {
+    None
+}
+
+impl Measurement {
+    fn report(mut self, name: &str) {
+        self.times.sort_unstable();
+        let min = self.times[0];
+        let median = self.times[self.times.len() / 2];
+        let mean = self.times.iter().sum::<Duration>() / self.times.len() as u32;
+        let allocations = match self.allocations {
+            Some((allocations, bytes)) => {
+                format!("  {allocations} allocations ({bytes} bytes) per iteration")
+            }
+            None => String::new(),
+        };
+        println!(
+            "{name:<10} min {min:>10.2?}  median {median:>10.2?}  mean {mean:>10.2?}{allocations}"
+        );
+    }
+}
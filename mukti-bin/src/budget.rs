@@ -0,0 +1,195 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Check new artifacts against size budgets, to catch accidental debug builds or bundled bloat
+//! before a release is recorded.
+
+use crate::{
+    checksums::ArchiveWithChecksums,
+    errors::{NameValueParseError, SizeBudgetParseError},
+};
+use clap::Args;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::MuktiReleasesJson;
+use semver::Version;
+use std::str::FromStr;
+
+/// Matches any target in a size budget.
+const ANY_TARGET: &str = "*";
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct BudgetOpts {
+    /// Maximum size of a target's artifacts, e.g. x86_64-unknown-linux-gnu=15MB (can be
+    /// repeated; use * as the target to apply to all targets without their own budget)
+    #[clap(long = "size-budget", value_name = "TARGET=SIZE")]
+    size_budgets: Vec<SizeBudget>,
+
+    /// Maximum growth in size, as a percentage, over the previous version's artifact for the same
+    /// target and format
+    #[clap(long, value_name = "PERCENT")]
+    max_growth: Option<f64>,
+
+    /// Fail instead of warning if an artifact is over budget
+    #[clap(long)]
+    enforce_budgets: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SizeBudget {
+    target: String,
+    max_size: u64,
+}
+
+impl FromStr for SizeBudget {
+    type Err = SizeBudgetParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (target, size) = input.split_once('=').ok_or_else(|| NameValueParseError {
+            input: input.to_owned(),
+            delimiter: '=',
+        })?;
+        let max_size = parse_size(size).ok_or_else(|| SizeBudgetParseError::Size {
+            input: size.to_owned(),
+        })?;
+        Ok(Self {
+            target: target.to_owned(),
+            max_size,
+        })
+    }
+}
+
+/// Check the sizes of newly fetched archives against the budgets in `opts`.
+///
+/// Sizes are compared against the newest earlier version of the project with an artifact for the
+/// same target and format. Violations are printed as warnings, or with `--enforce-budgets`,
+/// turned into an error.
+pub(crate) fn check_size_budgets(
+    release_json: &MuktiReleasesJson,
+    version: &Version,
+    archives: &[ArchiveWithChecksums],
+    opts: &BudgetOpts,
+) -> Result<()> {
+    if opts.size_budgets.is_empty() && opts.max_growth.is_none() {
+        return Ok(());
+    }
+
+    let project = release_json.projects.values().next();
+    let mut violations = Vec::new();
+    for archive in archives {
+        let target_format = &archive.archive.target_format;
+        let Some(size) = archive
+            .checksums
+            .as_ref()
+            .ok()
+            .and_then(|checksums| checksums.size)
+        else {
+            eprintln!(
+                "skipping size budget check for {}: size not known",
+                archive.archive.name
+            );
+            continue;
+        };
+
+        let budget = opts
+            .size_budgets
+            .iter()
+            .find(|budget| budget.target == target_format.target)
+            .or_else(|| {
+                opts.size_budgets
+                    .iter()
+                    .find(|budget| budget.target == ANY_TARGET)
+            });
+        if let Some(budget) = budget {
+            if size > budget.max_size {
+                violations.push(format!(
+                    "{} is {}, over its budget of {}",
+                    archive.archive.name,
+                    format_size(size),
+                    format_size(budget.max_size)
+                ));
+            }
+        }
+
+        let Some(max_growth) = opts.max_growth else {
+            continue;
+        };
+        // all_versions is newest first.
+        let previous = project.and_then(|project| {
+            project
+                .all_versions()
+                .filter(|(other, _)| *other < version)
+                .find_map(|(other, version_data)| {
+                    version_data
+                        .locations
+                        .iter()
+                        .find(|location| {
+                            location.target == target_format.target
+                                && location.format.as_str() == target_format.format
+                        })
+                        .and_then(|location| Some((other, location.size?)))
+                })
+        });
+        if let Some((previous_version, previous_size)) = previous {
+            let growth = (size as f64 / previous_size.max(1) as f64 - 1.0) * 100.0;
+            if growth > max_growth {
+                violations.push(format!(
+                    "{} is {}, {growth:.1}% larger than {} in {previous_version} \
+                     (maximum growth {max_growth}%)",
+                    archive.archive.name,
+                    format_size(size),
+                    format_size(previous_size)
+                ));
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let level = if opts.enforce_budgets {
+        "error"
+    } else {
+        "warning"
+    };
+    for violation in &violations {
+        eprintln!("{level}: {violation}");
+    }
+    if opts.enforce_budgets {
+        bail!(
+            "{} size budget violations, not adding {version}",
+            violations.len()
+        );
+    }
+    Ok(())
+}
+
+/// Parse a size like `15MB`, `1.5 GiB` or `1048576` into a number of bytes.
+fn parse_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1000 * 1000,
+        "g" | "gb" => 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+fn format_size(size: u64) -> String {
+    if size >= 1000 * 1000 {
+        format!("{:.1} MB", size as f64 / (1000.0 * 1000.0))
+    } else if size >= 1000 {
+        format!("{:.1} KB", size as f64 / 1000.0)
+    } else {
+        format!("{size} bytes")
+    }
+}
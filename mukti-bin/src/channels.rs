@@ -0,0 +1,63 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Manage release channels.
+
+use crate::release_json::single_project_mut;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{MuktiReleasesJson, VersionRange};
+use semver::Version;
+
+/// Point the channel `to` at `version`.
+///
+/// If `from` is specified, `version` must be the version that channel currently points to.
+pub(crate) fn promote(
+    release_json: &mut MuktiReleasesJson,
+    from: Option<&str>,
+    to: &str,
+    version: &Version,
+) -> Result<()> {
+    check_channel_name(to)?;
+    let (_, project) = single_project_mut(release_json)?;
+
+    let Some((version, version_data)) = project.get_version_data(version) else {
+        bail!("version {version} not found in releases JSON");
+    };
+    if version_data.status.is_yanked() {
+        bail!("version {version} is yanked and can't be promoted");
+    }
+    let version = version.clone();
+
+    if let Some(from) = from {
+        match project.channels.get(from) {
+            Some(from_version) if from_version.cmp_precedence(&version).is_eq() => {}
+            Some(from_version) => bail!(
+                "channel {from} points to {from_version}, not {version} \
+                 (omit --from to point {to} at {version} anyway)"
+            ),
+            None => bail!("channel {from} not found"),
+        }
+    }
+
+    match project.channels.insert(to.to_owned(), version.clone()) {
+        Some(old) => eprintln!("channel {to}: {old} -> {version}"),
+        None => eprintln!("channel {to}: (new) -> {version}"),
+    }
+
+    Ok(())
+}
+
+/// Channels are published next to `latest`, ranges and versions, so a channel name must be a single
+/// path segment that can't be confused with any of them.
+fn check_channel_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        bail!("channel name '{name}' must be a single, non-empty path segment");
+    }
+    if name == "latest" {
+        bail!("channel name 'latest' is reserved for the latest version");
+    }
+    if name.parse::<VersionRange>().is_ok() || name.parse::<Version>().is_ok() {
+        bail!("channel name '{name}' would conflict with a version or version range");
+    }
+    Ok(())
+}
@@ -4,14 +4,32 @@
 use std::collections::BTreeMap;
 
 use blake2::Blake2b;
-use bytes::Bytes;
-use color_eyre::{eyre::eyre, Result};
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
 use futures_util::stream::StreamExt;
-use mukti_metadata::{Digest, DigestAlgorithm, MuktiReleasesJson, ReleaseLocation};
+use mukti_metadata::{ArtifactKind, Digest, DigestAlgorithm, MuktiReleasesJson, ReleaseLocation};
 use sha2::{Digest as _, Sha256};
 use tokio::task::JoinHandle;
 
-use crate::command::Archive;
+use crate::{command::Archive, fetch::Fetcher, scheduler::Scheduler, staging::StagingFile};
+
+/// Verify `bytes` against the size and checksums recorded for a location.
+///
+/// Returns the number of checksums verified. Algorithms mukti doesn't know about are skipped.
+pub(crate) fn verify_checksums(location: &ReleaseLocation, bytes: &[u8]) -> Result<usize> {
+    if let Some(size) = location.size {
+        if size != bytes.len() as u64 {
+            bail!(
+                "size mismatch for {}: expected {size} bytes, actual {}",
+                location.url,
+                bytes.len()
+            );
+        }
+    }
+    Checksums::compute(bytes).verify_against(&location.url, &location.checksums)
+}
 
 pub(crate) struct ArchiveWithChecksums {
     pub(crate) archive: Archive,
@@ -23,35 +41,65 @@ pub(crate) struct ArchiveWithChecksums {
 pub(crate) async fn fetch_release_checksums(
     archive_prefix: &str,
     archives: Vec<Archive>,
-    download_jobs: usize,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+    staging: &mut StagingFile,
 ) -> Vec<ArchiveWithChecksums> {
-    let fetch_tasks = archives.iter().map(|archive| {
-        let url = format!("{}/{}", archive_prefix, archive.name);
-        async move {
-            let result = spawn_fetch_and_checksum_task(url.clone()).await;
-            (archive, url, result)
-        }
-    });
+    // Collect checksums from the staging file up front, since it's written to as results come in.
+    // Checksums supplied with the archive take precedence.
+    let urls_and_staged: Vec<_> = archives
+        .iter()
+        .map(|archive| {
+            let url = format!("{}/{}", archive_prefix, archive.name);
+            let staged = archive
+                .precomputed_checksums()
+                .or_else(|| staging.get(&url).cloned());
+            (archive, url, staged)
+        })
+        .collect();
+
+    let fetch_tasks = urls_and_staged
+        .into_iter()
+        .map(|(archive, url, staged)| async move {
+            match staged {
+                Some(checksums) => (archive, url, true, Ok(Ok(checksums))),
+                None => {
+                    let result = spawn_fetch_and_checksum_task(fetcher.clone(), url.clone()).await;
+                    (archive, url, false, result)
+                }
+            }
+        });
 
-    // Note buffered rather than buffer_unordered, so results are obtained in
-    // order.
-    let mut stream = futures_util::stream::iter(fetch_tasks).buffered(download_jobs);
+    // Note ordered, so results are obtained in order.
+    let mut stream = scheduler.ordered().run(fetch_tasks);
 
     let mut succeeded = 0;
     let mut failed = 0;
 
     let mut archives_with_checksums = Vec::new();
 
-    while let Some((archive, url, result)) = stream.next().await {
+    while let Some((archive, url, staged, result)) = stream.next().await {
+        // Verify any partial set of checksums supplied with the archive.
+        let result = result.map(|result| {
+            result.and_then(|checksums| {
+                checksums.verify_against(&url, &archive.expected_checksums)?;
+                Ok(checksums)
+            })
+        });
         let checksums = match result {
             Ok(Ok(checksums)) => {
                 succeeded += 1;
+                if !staged {
+                    if let Err(e) = staging.record(&url, &checksums) {
+                        eprintln!("for {url}, error recording checksum in staging file: {e}");
+                    }
+                }
                 Ok(checksums)
             }
             Ok(Err(e)) => {
                 failed += 1;
                 eprintln!("for {url}, error fetching checksum: {e}");
-                Err(eyre!(e))
+                Err(e)
             }
             Err(e) => {
                 failed += 1;
@@ -76,23 +124,26 @@ pub(crate) async fn fetch_release_checksums(
     archives_with_checksums
 }
 
-pub(crate) async fn backfill_checksums(release_json: &mut MuktiReleasesJson, download_jobs: usize) {
+pub(crate) async fn backfill_checksums(
+    release_json: &mut MuktiReleasesJson,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+) {
     let location_count = all_locations_without_checksums(release_json).count();
 
     let results = {
-        let fetch_tasks = all_locations_without_checksums(release_json)
-            .cloned()
-            .map(|location| {
-                // Note the spawn is inside the async block, which ensures that
-                // the task is only spawned after being pulled off of the
-                // buffer_unordered queue.
-                async {
-                    let result = spawn_fetch_and_checksum_task(location.url.clone()).await;
-                    (location.url, result)
-                }
-            });
+        let fetch_tasks = all_locations_without_checksums(release_json).map(|location| {
+            let url = location.url.clone();
+            // Note the spawn is inside the async block, which ensures that
+            // the task is only spawned after being pulled off of the
+            // scheduler's queue.
+            async {
+                let result = spawn_fetch_and_checksum_task(fetcher.clone(), url.clone()).await;
+                (url, result)
+            }
+        });
 
-        let mut stream = futures_util::stream::iter(fetch_tasks).buffer_unordered(download_jobs);
+        let mut stream = scheduler.run(fetch_tasks);
         let mut results = BTreeMap::new();
 
         let mut succeeded = 0;
@@ -126,13 +177,10 @@ pub(crate) async fn backfill_checksums(release_json: &mut MuktiReleasesJson, dow
 
     // Once all are done, update the release JSON with the new checksums.
     for project in release_json.projects.values_mut() {
-        for range_data in project.ranges.values_mut() {
-            for version in range_data.versions.values_mut() {
-                for location in &mut version.locations {
-                    if let Some(checksum) = results.get(&location.url) {
-                        location.checksums = checksum.to_checksum_map();
-                    }
-                }
+        for (_, location) in project.locations_mut() {
+            if let Some(checksum) = results.get(&location.url) {
+                location.checksums = checksum.to_checksum_map();
+                location.size = checksum.size.or(location.size);
             }
         }
     }
@@ -141,64 +189,93 @@ pub(crate) async fn backfill_checksums(release_json: &mut MuktiReleasesJson, dow
 fn all_locations_without_checksums(
     release_json: &MuktiReleasesJson,
 ) -> impl Iterator<Item = &ReleaseLocation> {
-    all_locations(release_json).filter(|location| {
-        !(location.checksums.contains_key(&DigestAlgorithm::SHA256)
-            && location.checksums.contains_key(&DigestAlgorithm::BLAKE2B))
-    })
+    all_locations(release_json)
+        // Checksum files and signatures are only useful alongside the artifacts they cover.
+        .filter(|location| {
+            !matches!(
+                location.kind,
+                ArtifactKind::ChecksumFile | ArtifactKind::Signature
+            )
+        })
+        .filter(|location| {
+            !(location.checksums.contains_key(&DigestAlgorithm::Sha256)
+                && location.checksums.contains_key(&DigestAlgorithm::Blake2b))
+        })
 }
 
 fn all_locations(release_json: &MuktiReleasesJson) -> impl Iterator<Item = &ReleaseLocation> {
-    release_json.projects.values().flat_map(|project| {
-        project
-            .all_versions()
-            .flat_map(|(_, version_data)| &version_data.locations)
-    })
+    release_json
+        .projects
+        .values()
+        .flat_map(|project| project.locations().map(|(_, location)| location))
 }
 
-fn spawn_fetch_and_checksum_task(url: String) -> JoinHandle<Result<Checksums, reqwest::Error>> {
+fn spawn_fetch_and_checksum_task(fetcher: Fetcher, url: String) -> JoinHandle<Result<Checksums>> {
     tokio::spawn(async move {
-        // Attempt to fetch the URL 3 times.
-        let bytes = {
-            let mut attempt = 0;
-            loop {
-                match fetch_url(&url).await {
-                    Ok(bytes) => break bytes,
-                    Err(e) => {
-                        eprintln!("Error fetching checksum: {}", e);
-                        if attempt == 2 {
-                            return Err(e);
-                        }
-                    }
-                }
-                attempt += 1;
-            }
-        };
-
-        let sha256 = Sha256::digest(&bytes);
-        let blake2b = Blake2b::digest(&bytes);
-
-        Ok(Checksums {
-            sha256: sha256.into(),
-            blake2b: blake2b.into(),
-        })
+        let bytes = fetcher.fetch_bytes(&url).await?;
+        Ok(Checksums::compute(&bytes))
     })
 }
 
-async fn fetch_url(url: &str) -> reqwest::Result<Bytes> {
-    let resp = reqwest::get(url).await?;
-    resp.bytes().await
-}
-
+#[derive(Clone, Debug)]
 pub(crate) struct Checksums {
-    sha256: [u8; 32],
-    blake2b: [u8; 64],
+    pub(crate) sha256: [u8; 32],
+    pub(crate) blake2b: [u8; 64],
+    // The size of the checksummed file, if known.
+    pub(crate) size: Option<u64>,
 }
 
 impl Checksums {
+    pub(crate) fn compute(bytes: &[u8]) -> Self {
+        Self {
+            sha256: Sha256::digest(bytes).into(),
+            blake2b: Blake2b::digest(bytes).into(),
+            size: Some(bytes.len() as u64),
+        }
+    }
+    pub(crate) fn from_hex(sha256: &str, blake2b: &str) -> Result<Self> {
+        let mut checksums = Self {
+            sha256: [0; 32],
+            blake2b: [0; 64],
+            size: None,
+        };
+        hex::decode_to_slice(sha256, &mut checksums.sha256)
+            .map_err(|err| eyre!("invalid SHA-256 checksum {sha256}: {err}"))?;
+        hex::decode_to_slice(blake2b, &mut checksums.blake2b)
+            .map_err(|err| eyre!("invalid BLAKE2b checksum {blake2b}: {err}"))?;
+        Ok(checksums)
+    }
+
+    /// Verify these checksums against a map of expected checksums.
+    ///
+    /// Returns the number of checksums verified. Algorithms mukti doesn't know about are skipped.
+    pub(crate) fn verify_against(
+        &self,
+        url: &str,
+        expected: &BTreeMap<DigestAlgorithm, Digest>,
+    ) -> Result<usize> {
+        let actual = self.to_checksum_map();
+        let mut verified = 0;
+        for (algorithm, expected) in expected {
+            let Some(actual) = actual.get(algorithm) else {
+                continue;
+            };
+            if !expected.0.eq_ignore_ascii_case(&actual.0) {
+                bail!(
+                    "{algorithm} checksum mismatch for {url}: expected {}, actual {}",
+                    expected.0,
+                    actual.0
+                );
+            }
+            verified += 1;
+        }
+        Ok(verified)
+    }
+
     pub(crate) fn to_checksum_map(&self) -> BTreeMap<DigestAlgorithm, Digest> {
         [
-            (DigestAlgorithm::SHA256, Digest(hex::encode(self.sha256))),
-            (DigestAlgorithm::BLAKE2B, Digest(hex::encode(self.blake2b))),
+            (DigestAlgorithm::Sha256, Digest(hex::encode(self.sha256))),
+            (DigestAlgorithm::Blake2b, Digest(hex::encode(self.blake2b))),
         ]
         .into_iter()
         .collect()
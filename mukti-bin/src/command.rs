@@ -2,27 +2,108 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    checksums::{backfill_checksums, fetch_release_checksums},
-    errors::NameValueParseError,
-    redirects::{generate_redirects, RedirectFlavor},
-    release_json::{read_release_json, update_release_json, write_releases_json},
+    aliases::{add_aliases, list_aliases, remove_aliases},
+    annotate::{annotate, read_notes},
+    api::generate_api,
+    appcast::generate_appcast,
+    apt::generate_apt,
+    asdf::generate_asdf,
+    bench::bench_parse,
+    budget::{check_size_budgets, BudgetOpts},
+    channels::promote,
+    checksums::{backfill_checksums, fetch_release_checksums, Checksums},
+    completions::{complete_aliases, complete_archives, complete_targets, complete_versions},
+    deploy::deploy_netlify,
+    destination::Destination,
+    diff::{diff_release_json, DiffFormat},
+    download::{download, resolve, VersionSpec},
+    eol::set_eol,
+    errors::{
+        error_to_json, AliasParseError, ErrorFormat, ErrorReported, MinPlatformParseError,
+        NameValueParseError,
+    },
+    fetch::{FetchOpts, Fetcher},
+    fmt::{format_release_json, DigestEncodingArg},
+    gc::gc_release_json,
+    hooks::HookOpts,
+    link_latest::{link_latest, LinkMode},
+    lint::lint_release_json,
+    manifest::read_manifest,
+    markdown::generate_markdown,
+    migrate::migrate_release_json,
+    mirror::{mirror, MirrorUrlMode},
+    netlify::NETLIFY_SITE_ID,
+    nix::generate_nix,
+    notify::{notify_redirects_generated, notify_release_added, NotifyOpts},
+    output::OutputOpts,
+    project_info::set_project_info,
+    provenance::verify_provenance,
+    publish::{
+        publish_github_assets, publish_github_release, publish_to_destination, CacheControl,
+        DEFAULT_IMMUTABLE_CACHE_CONTROL, DEFAULT_MUTABLE_CACHE_CONTROL,
+    },
+    purge::{purge_cache, PurgeOptions, PurgeProvider},
+    redirects::{
+        generate_redirects, GenerateOptions, OutputSelection, RedirectFlavor, StatusCodeOpts,
+    },
+    release_json::{
+        check_conflict, read_release_json, set_latest, update_release_json, write_releases_json,
+        OnConflict, ReleaseDetails,
+    },
+    report::{report, ReportFormat},
+    scheduler::Scheduler,
+    schema::{print_schema, SchemaKind},
+    secrets::{SecretOpts, SecretResolver},
+    self_update::generate_self_update,
+    shard::shard_release_json,
+    site::generate_site,
+    staging::StagingFile,
+    targets::TargetOpts,
+    url_template::set_url_template,
+    yank::yank,
+    yum::generate_yum,
 };
 use camino::Utf8PathBuf;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
-use color_eyre::Result;
-use semver::Version;
-use std::str::FromStr;
+use clap_complete::ArgValueCompleter;
+use color_eyre::{eyre::bail, Result};
+use mukti_metadata::{
+    ArtifactKind, AttestationEntry, Digest, DigestAlgorithm, PathAlias, ProjectInfo,
+    ReleaseLocation, SignatureEntry, VersionRange,
+};
+use semver::{Version, VersionReq};
+use std::{collections::BTreeMap, str::FromStr};
 
 #[doc(hidden)]
 #[derive(Debug, Parser)]
-#[clap(version)]
+#[clap(
+    version,
+    after_help = "Shell completions can be enabled with e.g. `source <(COMPLETE=bash mukti-bin)`."
+)]
 pub struct MuktiApp {
     #[clap(subcommand)]
     command: MuktiCommand,
 
     /// JSON file to edit
-    #[clap(long, global = true, default_value = ".releases.json")]
+    #[clap(
+        long,
+        global = true,
+        env = "MUKTI_JSON",
+        default_value = ".releases.json"
+    )]
     json: Utf8PathBuf,
+
+    /// Format to report errors in
+    #[clap(long, global = true, value_enum, default_value_t)]
+    error_format: ErrorFormat,
+
+    /// Run downloads and uploads one at a time and in order, so that output is reproducible
+    #[clap(long, global = true, env = "MUKTI_DETERMINISTIC")]
+    deterministic: bool,
+
+    #[clap(flatten)]
+    secret_opts: SecretOpts,
 }
 
 #[derive(Debug, Subcommand)]
@@ -42,84 +123,1558 @@ enum MuktiCommand {
         version: Version,
 
         /// Archive names.
-        #[clap(long = "archive", value_name = "TARGET:FORMAT=NAME")]
+        ///
+        /// Use the `source` target for artifacts that aren't specific to a platform, e.g.
+        /// `source:tar.gz=NAME` for a vendored source tarball.
+        #[clap(
+            long = "archive",
+            value_name = "TARGET:FORMAT=NAME",
+            add = ArgValueCompleter::new(complete_archives)
+        )]
         archives: Vec<Archive>,
 
+        /// Other artifacts, such as checksum files, signatures, SBOMs or debug symbols.
+        ///
+        /// KIND is one of archive, installer, checksum-file, signature, sbom or debug-symbols.
+        /// Only archives and installers get redirects.
+        #[clap(long = "artifact", value_name = "KIND:TARGET:FORMAT=NAME")]
+        artifacts: Vec<KindedArchive>,
+
+        /// Debug symbols for a target, e.g. a .dSYM.zip, .pdb or split debuginfo file.
+        ///
+        /// These are served at /{version}/{target}-debug-symbols. Shorthand for
+        /// `--artifact debug-symbols:TARGET:FORMAT=NAME`.
+        #[clap(long = "debug-symbols", value_name = "TARGET:FORMAT=NAME")]
+        debug_symbols: Vec<Archive>,
+
+        /// Read archives from a JSON or TOML manifest, in addition to any passed in with --archive
+        #[clap(long, value_name = "PATH")]
+        from_manifest: Option<Utf8PathBuf>,
+
+        /// What to do if the version already exists
+        #[clap(long, value_enum, default_value_t)]
+        on_conflict: OnConflict,
+
+        /// When the version was released, in RFC 3339 format [default: now]
+        #[clap(long, value_name = "DATE")]
+        date: Option<DateTime<FixedOffset>>,
+
+        /// File containing release notes for the version, typically a changelog excerpt
+        #[clap(long, value_name = "PATH")]
+        notes_file: Option<Utf8PathBuf>,
+
+        /// URL of the changelog entry for the version
+        #[clap(long, value_name = "URL")]
+        changelog_url: Option<String>,
+
+        /// The git commit the version was built from, e.g. `$GITHUB_SHA`
+        #[clap(long, value_name = "SHA")]
+        commit: Option<String>,
+
+        /// The version's license as an SPDX expression, if it differs from the project's
+        #[clap(long, value_name = "SPDX")]
+        license: Option<String>,
+
+        /// Record a signature published next to each archive, at the archive URL plus SUFFIX
+        ///
+        /// For example, `minisign=.minisig` records https://example.com/foo.tar.gz.minisig as the
+        /// minisign signature for https://example.com/foo.tar.gz.
+        #[clap(long = "signature", value_name = "SCHEME=SUFFIX")]
+        signatures: Vec<SignatureSuffix>,
+
+        /// Record an attestation published next to each archive, at the archive URL plus SUFFIX
+        ///
+        /// For example, `https://slsa.dev/provenance/v1=.intoto.jsonl` records SLSA provenance
+        /// published at https://example.com/foo.tar.gz.intoto.jsonl.
+        #[clap(long = "attestation", value_name = "PREDICATE_TYPE=SUFFIX")]
+        attestations: Vec<AttestationSuffix>,
+
+        /// Record the oldest platform version a target's artifacts run on
+        ///
+        /// PLATFORM is one of glibc, macos or windows. For example,
+        /// `x86_64-unknown-linux-gnu:glibc=2.17` lets install scripts warn on older distributions
+        /// before downloading anything.
+        #[clap(long = "min-platform", value_name = "TARGET:PLATFORM=VERSION")]
+        min_platforms: Vec<MinPlatform>,
+
         /// Number of release files to download in parallel.
         #[clap(long, short, default_value = "8")]
         jobs: usize,
+
+        /// File to record progress in [default: the JSON file with a .staging suffix]
+        #[clap(long, value_name = "PATH")]
+        staging_file: Option<Utf8PathBuf>,
+
+        /// Resume an interrupted run, reusing checksums recorded in the staging file
+        #[clap(long)]
+        resume: bool,
+
+        #[clap(flatten)]
+        budget_opts: BudgetOpts,
+
+        #[clap(flatten)]
+        target_opts: TargetOpts,
+
+        #[clap(flatten)]
+        notify_opts: NotifyOpts,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
     },
     /// Generate a _redirects file from the release JSON
     GenerateRedirects {
-        /// Aliases to use.
-        #[clap(long = "alias", value_name = "ALIAS=TARGET:FORMAT")]
+        /// Aliases to use, in addition to those stored in the release JSON with `alias add`.
+        ///
+        /// An alias can be limited to one project with a PROJECT: prefix, and to versions matching
+        /// a requirement with an @REQ suffix, e.g. `nextest:linux@>=0.9=TARGET:FORMAT`.
+        ///
+        /// The target or format can be `*` to match any target or format. `{target}` and
+        /// `{format}` in the alias are replaced with the ones matched, and if the format is `*`
+        /// and the alias doesn't contain `{format}`, `.FORMAT` is appended. For example,
+        /// `macos=aarch64-apple-darwin:*` generates `macos.tar.gz` and `macos.zip`.
+        #[clap(
+            long = "alias",
+            value_name = "ALIAS=TARGET:FORMAT",
+            add = ArgValueCompleter::new(complete_aliases)
+        )]
         aliases: Vec<Alias>,
 
+        /// Also generate aliases like linux-x64 and windows-x64.zip from the targets in each
+        /// version. Aliases passed in with --alias take precedence.
+        #[clap(long)]
+        auto_aliases: bool,
+
         /// The flavor of redirects to generate.
         #[clap(long, short, value_enum)]
         flavor: RedirectFlavor,
 
+        /// Prefix for URLs (can be repeated, to generate the same redirects under each prefix)
+        #[clap(
+            long = "prefix",
+            value_name = "PREFIX",
+            default_value = "/",
+            conflicts_with = "project_prefixes"
+        )]
+        prefixes: Vec<String>,
+
+        /// In a release JSON with several projects, generate redirects for a project under a
+        /// prefix (can be repeated)
+        #[clap(long = "project-prefix", value_name = "PROJECT=PREFIX")]
+        project_prefixes: Vec<ProjectPrefix>,
+
+        /// Output directory.
+        out_dir: Utf8PathBuf,
+
+        #[clap(flatten)]
+        status_codes: StatusCodeOpts,
+
+        /// Apply redirects even if a file exists at the same path (Netlify flavors only)
+        #[clap(long)]
+        force: bool,
+
+        /// Write out a redirect for every version, rather than collapsing them into :version
+        /// placeholders where possible (netlify and cloudflare flavors)
+        #[clap(long)]
+        no_compact: bool,
+
+        /// Fail instead of warning if the generated redirects exceed the hosting provider's limits
+        #[clap(long)]
+        strict: bool,
+
+        /// Also redirect each prefix itself (e.g. `/`) to the latest release page
+        #[clap(long)]
+        root_redirect: bool,
+
+        /// Serve this page with a 404 status for paths under each prefix that no redirect
+        /// matches (netlify, netlify-toml and custom flavors)
+        #[clap(long, value_name = "PATH")]
+        not_found: Option<String>,
+
+        /// Template to render redirects with, using Tera syntax (required with --flavor custom)
+        #[clap(long, value_name = "PATH", required_if_eq("flavor", "custom"))]
+        template: Option<Utf8PathBuf>,
+
+        /// A variable to make available to the template as `vars.NAME`, or to the Worker as
+        /// `VARS.NAME` (custom and cloudflare-worker flavors)
+        #[clap(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<TemplateVar>,
+
+        #[clap(flatten)]
+        output_opts: OutputOpts,
+
+        #[clap(flatten)]
+        output_selection: OutputSelection,
+
+        #[clap(flatten)]
+        target_opts: TargetOpts,
+
+        #[clap(flatten)]
+        hook_opts: HookOpts,
+
+        #[clap(flatten)]
+        notify_opts: NotifyOpts,
+    },
+    /// Generate a static JSON API with a document per project, version range and version
+    ///
+    /// Documents are written to v1/PROJECT/latest.json, v1/PROJECT/RANGE.json and
+    /// v1/PROJECT/VERSION.json in the output directory.
+    GenerateApi {
+        /// Output directory
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+    },
+    /// Print a Markdown table of a version's downloads and their SHA-256 checksums
+    ///
+    /// The table is suitable for pasting into release notes or docs.
+    GenerateMarkdown {
+        /// The version to list downloads for: "latest", an exact version, or a version requirement
+        #[clap(
+            long = "version",
+            default_value = "latest",
+            add = ArgValueCompleter::new(complete_versions)
+        )]
+        version: VersionSpec,
+    },
+    /// Generate a Sparkle appcast, so macOS apps can update themselves
+    ///
+    /// Each active version gets an item for its dmg, zip or tarball. Pre-releases are put on a
+    /// Sparkle channel named after their first pre-release identifier, e.g. beta.
+    GenerateAppcast {
+        /// The target to offer artifacts for [default: the first of universal-apple-darwin,
+        /// aarch64-apple-darwin and x86_64-apple-darwin with an artifact]
+        #[clap(long, add = ArgValueCompleter::new(complete_targets))]
+        target: Option<String>,
+
+        /// Path to write the appcast to
+        #[clap(long, default_value = "appcast.xml")]
+        out: Utf8PathBuf,
+    },
+    /// Generate static files for the self_update crate's GitHub backend
+    ///
+    /// Point self_update at the URL these files are served from with `with_url`, so binaries can
+    /// update themselves without talking to the GitHub API.
+    GenerateSelfUpdate {
+        /// The repository name self_update is configured with, in the form OWNER/NAME
+        #[clap(long, required = true)]
+        repo: String,
+
+        /// Output directory
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+    },
+    /// Generate a Nix derivation that fetches a version's prebuilt binaries
+    ///
+    /// Each Nix system gets the tarball for the best matching target, with its SHA-256 checksum
+    /// as an SRI hash.
+    GenerateNix {
+        /// The version to package: "latest", an exact version, or a version requirement
+        #[clap(
+            long = "version",
+            default_value = "latest",
+            add = ArgValueCompleter::new(complete_versions)
+        )]
+        version: VersionSpec,
+
+        /// Path to write the derivation to
+        #[clap(long, default_value = "default.nix")]
+        out: Utf8PathBuf,
+    },
+    /// Generate the version list and download mapping for a generic asdf or mise plugin
+    ///
+    /// Writes versions.txt, in the format `bin/list-all` prints, and versions/VERSION.tsv with the
+    /// OS, architecture, format, URL and SHA-256 checksum of each artifact.
+    GenerateAsdf {
+        /// Output directory
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+    },
+    /// Generate Packages, Packages.gz and Release files for a flat APT repository
+    ///
+    /// Every active .deb artifact is downloaded, and listed with the fields from its control
+    /// file. Release isn't signed, so sign it afterwards with e.g.
+    /// `gpg --clearsign -o InRelease Release`.
+    GenerateApt {
+        /// The URL the repository is served from
+        ///
+        /// Artifacts under this URL are listed by their path relative to it. Other artifacts are
+        /// listed by file name, and must be copied or redirected into the repository.
+        #[clap(long)]
+        base_url: Option<String>,
+
+        /// Output directory
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+
+        /// Number of packages to download in parallel.
+        #[clap(long, short, default_value = "8")]
+        jobs: usize,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Generate repodata for a YUM/DNF repository
+    ///
+    /// Every active .rpm artifact is downloaded, and listed in the primary metadata with the
+    /// fields from its header and its location pointing at the artifact URL.
+    GenerateYum {
+        /// Output directory; repodata/ is created under it
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+
+        /// Number of packages to download in parallel.
+        #[clap(long, short, default_value = "8")]
+        jobs: usize,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Render a download site from a directory of templates and assets
+    ///
+    /// Without --template-dir, a built-in index.html listing the downloads is rendered.
+    ///
+    /// Files ending in .tera are rendered as Tera templates with the release data and written
+    /// without the suffix. Templates starting with `_` can be extended or included by others,
+    /// but aren't written. Other files, like stylesheets and images, are copied as is.
+    GenerateSite {
+        /// Directory with templates and assets [default: a built-in index.html]
+        #[clap(long)]
+        template_dir: Option<Utf8PathBuf>,
+
+        /// Output directory
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+
+        /// The URL the site is served from; if set, a sitemap.xml of the HTML pages is written
+        #[clap(long)]
+        base_url: Option<String>,
+
+        /// A variable to make available to templates as `vars.NAME`
+        #[clap(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<TemplateVar>,
+    },
+    /// Purge CDN caches for redirects that change when releases are added
+    ///
+    /// By default, the latest and version range paths are purged. Pass the same --prefix, --alias
+    /// and --auto-aliases options as to generate-redirects.
+    PurgeCache {
+        /// The CDN to purge
+        #[clap(long, value_enum)]
+        provider: PurgeProvider,
+
+        /// The URL redirects are served from, e.g. https://get.example.com
+        #[clap(long, value_name = "URL")]
+        base_url: Option<String>,
+
         /// Prefix for URLs.
         #[clap(long, default_value = "/")]
         prefix: String,
 
-        /// Output directory.
+        /// Aliases to purge.
+        #[clap(
+            long = "alias",
+            value_name = "ALIAS=TARGET:FORMAT",
+            add = ArgValueCompleter::new(complete_aliases)
+        )]
+        aliases: Vec<Alias>,
+
+        /// Also purge aliases derived from target triples.
+        #[clap(long)]
+        auto_aliases: bool,
+
+        /// Purge everything cached for the site, rather than individual paths
+        #[clap(long)]
+        all: bool,
+
+        /// Print what would be purged, without purging it
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Give the latest artifacts stable names, for hosts that can't serve redirects
+    ///
+    /// Creates `PROJECT-latest-TARGET.FORMAT` files, plus files for any aliases, in the output
+    /// directory.
+    LinkLatest {
+        /// Directory containing artifacts, either directly or under PROJECT/VERSION/ as mirror
+        /// writes them
+        #[clap(long, required = true)]
+        artifact_dir: Utf8PathBuf,
+
+        /// Directory to create stable-named files in
+        #[clap(long, required = true)]
         out_dir: Utf8PathBuf,
+
+        /// How stable-named files refer to artifacts
+        #[clap(long, value_enum, default_value_t)]
+        mode: LinkMode,
+
+        /// Aliases to create stable-named files for.
+        #[clap(
+            long = "alias",
+            value_name = "ALIAS=TARGET:FORMAT",
+            add = ArgValueCompleter::new(complete_aliases)
+        )]
+        aliases: Vec<Alias>,
+
+        /// Also create stable-named files for aliases derived from target triples.
+        #[clap(long)]
+        auto_aliases: bool,
     },
     /// Add checksums to the release JSON
     BackfillChecksums {
         /// Number of release files to download in parallel.
         #[clap(long, short, default_value = "8")]
         jobs: usize,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Point latest at a specific version
+    ///
+    /// Without --pin, any existing pin is removed, and adding a newer release will advance latest
+    /// as usual.
+    SetLatest {
+        /// The version to make the latest
+        #[clap(long = "version", required = true, add = ArgValueCompleter::new(complete_versions))]
+        version: Version,
+
+        /// Prevent add-release from advancing latest until it is unpinned
+        #[clap(long)]
+        pin: bool,
+    },
+    /// Set or clear the date a version range reaches end of life
+    ///
+    /// Ranges past their end-of-life date are flagged on generated sites and in the static API,
+    /// and lint warns if one of them is still the latest range.
+    SetEol {
+        /// The version range, e.g. 0.9 or 1
+        range: VersionRange,
+
+        /// The end-of-life date, in YYYY-MM-DD format
+        #[clap(long, required_unless_present = "clear")]
+        date: Option<NaiveDate>,
+
+        /// Remove the end-of-life date
+        #[clap(long, conflicts_with = "date")]
+        clear: bool,
+    },
+    /// Set or clear the template artifact URLs are generated from
+    ///
+    /// Locations whose URLs match the template are stored without a URL, so a project with many
+    /// versions and targets doesn't store thousands of nearly identical URLs. Locations with other
+    /// URLs keep them.
+    SetUrlTemplate {
+        /// The template, e.g.
+        /// `https://example.com/{version}/{name}-{version}-{target}.{format}`.
+        ///
+        /// `{name}` is the project name, and `{version}`, `{target}` and `{format}` are the
+        /// location's. A relative template is resolved against the project's base URL.
+        #[clap(required_unless_present = "clear")]
+        template: Option<String>,
+
+        /// Remove the template, storing every location's URL
+        #[clap(long, conflicts_with = "template")]
+        clear: bool,
+    },
+    /// Point a release channel at a version
+    Promote {
+        /// The channel the version is being promoted from, which must currently point to it
+        #[clap(long, value_name = "CHANNEL")]
+        from: Option<String>,
+
+        /// The channel to point at the version
+        #[clap(long, value_name = "CHANNEL", required = true)]
+        to: String,
+
+        /// The version to promote
+        #[clap(long = "version", required = true, add = ArgValueCompleter::new(complete_versions))]
+        version: Version,
+    },
+    /// Attach release notes or a changelog link to a version
+    Annotate {
+        /// The version to annotate
+        #[clap(long = "version", required = true, add = ArgValueCompleter::new(complete_versions))]
+        version: Version,
+
+        /// File containing release notes for the version, typically a changelog excerpt
+        #[clap(long, value_name = "PATH")]
+        notes_file: Option<Utf8PathBuf>,
+
+        /// URL of the changelog entry for the version
+        #[clap(long, value_name = "URL")]
+        changelog_url: Option<String>,
+
+        /// Remove existing notes and changelog URL before applying any new ones
+        #[clap(long)]
+        clear: bool,
+    },
+    /// Set descriptive information about the project, used by the site, feed and package
+    /// generators
+    SetProjectInfo {
+        /// A human-readable name for the project
+        #[clap(long)]
+        display_name: Option<String>,
+
+        /// A one-line description of the project
+        #[clap(long)]
+        description: Option<String>,
+
+        /// The URL of the project's homepage
+        #[clap(long, value_name = "URL")]
+        homepage: Option<String>,
+
+        /// The URL of the project's source repository
+        #[clap(long, value_name = "URL")]
+        repository: Option<String>,
+
+        /// The project's license as an SPDX expression, e.g. "MIT OR Apache-2.0"
+        #[clap(long, value_name = "SPDX")]
+        license: Option<String>,
+
+        /// Remove existing information before applying any new values
+        #[clap(long)]
+        clear: bool,
+    },
+    /// Mark a version as yanked, recording why and when
+    ///
+    /// If the version is the latest in its range, latest moves to the newest version that isn't
+    /// yanked.
+    Yank {
+        /// The version to yank
+        #[clap(long = "version", required = true, add = ArgValueCompleter::new(complete_versions))]
+        version: Version,
+
+        /// Why the version is being yanked
+        #[clap(long)]
+        reason: Option<String>,
+    },
+    /// Remove old yanked versions, and ranges that end up empty
+    Gc {
+        /// Only remove yanked versions older than this [default: the latest version]
+        #[clap(long, value_name = "VERSION", add = ArgValueCompleter::new(complete_versions))]
+        older_than: Option<Version>,
+
+        /// Only remove yanked versions released before this date, in RFC 3339 format
+        ///
+        /// Versions without a release date are never removed if this is passed in.
+        #[clap(long, value_name = "DATE")]
+        released_before: Option<DateTime<FixedOffset>>,
+
+        /// Print what would be removed, without changing the release JSON
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Download an artifact, verifying its checksums
+    Download {
+        /// The version to download: "latest", an exact version, or a version requirement
+        #[clap(
+            long = "version",
+            default_value = "latest",
+            add = ArgValueCompleter::new(complete_versions)
+        )]
+        version: VersionSpec,
+
+        /// The target to download the artifact for
+        #[clap(long, required = true, add = ArgValueCompleter::new(complete_targets))]
+        target: String,
+
+        /// The format of the artifact, if there is more than one for the target
+        #[clap(long)]
+        format: Option<String>,
+
+        /// Directory to write the artifact to
+        #[clap(long, default_value = ".")]
+        out: Utf8PathBuf,
+
+        /// Write the artifact even if there are no checksums to verify it against
+        #[clap(long)]
+        allow_unverified: bool,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Print the version that installers resolve a version requirement to
+    ///
+    /// Yanked versions are only picked if the requirement pins them exactly, e.g. `=0.5.2`, and
+    /// pre-releases only if the requirement mentions one.
+    Resolve {
+        /// The version to resolve: "latest", an exact version, or a version requirement
+        #[clap(
+            long = "version",
+            default_value = "latest",
+            add = ArgValueCompleter::new(complete_versions)
+        )]
+        version: VersionSpec,
+
+        /// Print the URL of the artifact for this target instead of the version
+        #[clap(long, add = ArgValueCompleter::new(complete_targets))]
+        target: Option<String>,
+
+        /// The format of the artifact, if there is more than one for the target
+        #[clap(long, requires = "target")]
+        format: Option<String>,
+    },
+    /// Check the attestations recorded for a version against its artifacts
+    ///
+    /// Each attestation must contain an in-toto statement with the recorded predicate type, whose
+    /// subjects include the artifact's SHA-256 digest. Signatures on attestations aren't checked.
+    VerifyProvenance {
+        /// The version to check: "latest", an exact version, or a version requirement
+        #[clap(
+            long = "version",
+            default_value = "latest",
+            add = ArgValueCompleter::new(complete_versions)
+        )]
+        version: VersionSpec,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Copy all artifacts to another location, verifying their checksums
+    ///
+    /// Uploading to S3 requires the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY secrets,
+    /// uploading to GCS requires the GCS_SERVICE_ACCOUNT_KEY secret or
+    /// GOOGLE_APPLICATION_CREDENTIALS, and uploading to Azure requires the
+    /// AZURE_STORAGE_SAS_TOKEN secret.
+    Mirror {
+        /// Where to copy artifacts to: a local directory, s3://BUCKET/PREFIX, gs://BUCKET/PREFIX or
+        /// azure://ACCOUNT/CONTAINER/PREFIX
+        #[clap(long, required = true)]
+        dest: Destination,
+
+        /// The URL that the destination is publicly served from
+        #[clap(long, value_name = "URL")]
+        public_url: Option<String>,
+
+        /// How to record mirrored URLs in the release JSON
+        #[clap(long, value_enum, default_value_t)]
+        update_urls: MirrorUrlMode,
+
+        /// Number of artifacts to mirror in parallel
+        #[clap(long, short, default_value = "8")]
+        jobs: usize,
+
+        /// Don't mirror artifacts whose host disallows fetching them in its robots.txt
+        ///
+        /// Off by default, since mirroring copies a project's own artifacts rather than crawling
+        /// other sites.
+        #[clap(long)]
+        respect_robots_txt: bool,
+
+        #[clap(flatten)]
+        fetch_opts: FetchOpts,
+    },
+    /// Rewrite the release JSON in canonical form
+    Fmt {
+        /// Exit with an error if the file is not canonical, instead of rewriting it
+        #[clap(long)]
+        check: bool,
+
+        /// Change how digests are encoded in the file
+        #[clap(long, value_enum)]
+        digest_encoding: Option<DigestEncodingArg>,
+    },
+    /// Split the release JSON into an index and one file per version range
+    ///
+    /// Commands that only read the release JSON can be pointed at the generated index.json.
+    Shard {
+        /// Directory to write the index and shards to
+        #[clap(long, required = true)]
+        out_dir: Utf8PathBuf,
+
+        #[clap(flatten)]
+        hook_opts: HookOpts,
+    },
+    /// Measure parse, serialize and resolve times for a release JSON file
+    ///
+    /// Allocations are also reported if mukti-bin was built with the `count-allocations` feature.
+    BenchParse {
+        /// The release JSON file to measure
+        file: Utf8PathBuf,
+
+        /// Number of times to run each measurement
+        #[clap(long, short = 'n', default_value = "20")]
+        iterations: usize,
+    },
+    /// Summarize the release history
+    Report {
+        /// The format to print the report in
+        #[clap(long, value_enum, default_value_t)]
+        format: ReportFormat,
+    },
+    /// Show what changed in the release JSON compared to another copy of it
+    Diff {
+        /// The copy to compare against, e.g. the release JSON from before a change
+        old: Utf8PathBuf,
+
+        /// The format to print the differences in
+        #[clap(long, value_enum, default_value_t)]
+        format: DiffFormat,
+    },
+    /// Check the release JSON for suspicious metadata
+    Lint {
+        /// Exit with an error if any lints are found
+        #[clap(long)]
+        deny: bool,
+
+        #[clap(flatten)]
+        target_opts: TargetOpts,
+    },
+    /// Upgrade the release JSON to the current schema version
+    Migrate {
+        /// Exit with an error if the file needs to be migrated, instead of migrating it
+        #[clap(long)]
+        check: bool,
+    },
+    /// Print a JSON schema for the release JSON, or another file mukti writes
+    Schema {
+        /// The file to print a schema for
+        #[clap(long, value_enum, default_value_t)]
+        kind: SchemaKind,
+    },
+    /// Publish release metadata and generated files
+    ///
+    /// With a destination, uploads the releases JSON and the contents of any --dir directories
+    /// there, with content types and Cache-Control headers set. Uploading to S3 requires the
+    /// AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY secrets, uploading to GCS requires the
+    /// GCS_SERVICE_ACCOUNT_KEY secret or GOOGLE_APPLICATION_CREDENTIALS, and uploading to Azure
+    /// requires the AZURE_STORAGE_SAS_TOKEN secret. For an Azure static website, publish to the
+    /// `$web` container.
+    #[clap(args_conflicts_with_subcommands = true)]
+    Publish {
+        /// Where to publish to: s3://BUCKET/PREFIX, gs://BUCKET/PREFIX,
+        /// azure://ACCOUNT/CONTAINER/PREFIX or a local directory
+        dest: Option<Destination>,
+
+        /// A directory of generated files to publish, e.g. the output of generate-redirects or
+        /// shard (can be repeated)
+        #[clap(long = "dir", value_name = "DIR")]
+        dirs: Vec<Utf8PathBuf>,
+
+        /// The key to publish the releases JSON as, relative to the destination
+        #[clap(long, default_value = "releases.json")]
+        json_key: String,
+
+        /// Cache-Control for files that change as releases are added, such as the releases JSON,
+        /// redirect files and `latest` endpoints
+        #[clap(long, value_name = "VALUE", default_value = DEFAULT_MUTABLE_CACHE_CONTROL)]
+        cache_control: String,
+
+        /// Cache-Control for files under an exact version's path, which never change
+        #[clap(long, value_name = "VALUE", default_value = DEFAULT_IMMUTABLE_CACHE_CONTROL)]
+        immutable_cache_control: String,
+
+        /// Print the files that would be uploaded, without uploading them
+        #[clap(long)]
+        dry_run: bool,
+
+        #[clap(subcommand)]
+        command: Option<PublishCommand>,
+    },
+    /// Deploy generated files directly to a hosting provider
+    Deploy {
+        #[clap(subcommand)]
+        command: DeployCommand,
+    },
+    /// Manage aliases stored in the release JSON
+    ///
+    /// Stored aliases are used by every command that takes --alias. An alias passed in with
+    /// --alias takes precedence over a stored alias with the same name.
+    Alias {
+        #[clap(subcommand)]
+        command: AliasCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AliasCommand {
+    /// Add aliases, replacing any existing aliases with the same names
+    Add {
+        /// Aliases to add, in the same syntax as --alias
+        #[clap(
+            value_name = "ALIAS=TARGET:FORMAT",
+            required = true,
+            add = ArgValueCompleter::new(complete_aliases)
+        )]
+        aliases: Vec<Alias>,
+
+        #[clap(flatten)]
+        target_opts: TargetOpts,
+    },
+    /// Remove aliases
+    Remove {
+        /// Names of aliases to remove, as PROJECT:ALIAS if the release JSON has several projects
+        #[clap(value_name = "ALIAS", required = true)]
+        names: Vec<String>,
+    },
+    /// List aliases, in the same syntax as --alias
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+enum DeployCommand {
+    /// Deploy a directory of generated files to a Netlify site, replacing its current contents
+    ///
+    /// Requires the NETLIFY_AUTH_TOKEN secret, unless --auth-token is passed in.
+    Netlify {
+        /// The Netlify site ID [default: the NETLIFY_SITE_ID secret]
+        #[clap(long)]
+        site_id: Option<String>,
+
+        /// The Netlify personal access token
+        #[clap(long)]
+        auth_token: Option<String>,
+
+        /// Directory containing the files to deploy, e.g. the output of generate-redirects
+        dir: Utf8PathBuf,
+
+        /// Create a draft deploy for previewing, instead of publishing it
+        #[clap(long)]
+        draft: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum PublishCommand {
+    /// Upload checksum sidecars, SBOM references and a release report to a GitHub release
+    ///
+    /// Requires the GITHUB_TOKEN secret.
+    GithubAssets {
+        /// GitHub repository, in the form OWNER/NAME
+        #[clap(long, required = true)]
+        repo: String,
+
+        /// Version to upload assets for
+        #[clap(long = "version", required = true, add = ArgValueCompleter::new(complete_versions))]
+        version: Version,
+
+        /// Release tag [default: the last path component of the release URL]
+        #[clap(long)]
+        tag: Option<String>,
+
+        /// Print the assets that would be uploaded, without uploading them
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Upload the releases JSON to a GitHub release as an asset, replacing any existing one
+    ///
+    /// This lets consumers fetch release metadata from GitHub if the usual host is unavailable.
+    /// Requires the GITHUB_TOKEN secret.
+    GithubRelease {
+        /// GitHub repository, in the form OWNER/NAME
+        #[clap(long, required = true)]
+        repo: String,
+
+        /// Tag of the release to upload to
+        #[clap(long, required = true)]
+        tag: String,
+
+        /// Name of the uploaded asset
+        #[clap(long, default_value = "releases.json")]
+        asset_name: String,
+
+        /// Print the asset that would be uploaded, without uploading it
+        #[clap(long)]
+        dry_run: bool,
     },
 }
 
 impl MuktiApp {
     pub async fn exec(self) -> Result<()> {
+        let error_format = self.error_format;
+        let command = self.command.name();
+        let path = self.json.clone();
+        let version = self.command.version().cloned();
+
+        match self.exec_command().await {
+            Ok(()) => Ok(()),
+            Err(err) => match error_format {
+                ErrorFormat::Human => Err(err),
+                ErrorFormat::Json => {
+                    let json = error_to_json(&err, command, &path, version.as_ref());
+                    eprintln!("{json}");
+                    Err(ErrorReported.into())
+                }
+            },
+        }
+    }
+
+    async fn exec_command(self) -> Result<()> {
         match self.command {
             MuktiCommand::AddRelease {
                 release_url,
                 archive_prefix,
                 version,
-                archives,
+                mut archives,
+                artifacts,
+                debug_symbols,
+                from_manifest,
+                on_conflict,
+                date,
+                notes_file,
+                changelog_url,
+                commit,
+                license,
+                signatures,
+                attestations,
+                min_platforms,
                 jobs,
+                staging_file,
+                resume,
+                budget_opts,
+                target_opts,
+                notify_opts,
+                fetch_opts,
             } => {
                 let mut release_json = read_release_json(&self.json, true)?;
+                // Read the notes up front, so a missing file doesn't waste a fetch.
+                let details = ReleaseDetails {
+                    release_url,
+                    release_date: date.unwrap_or_else(|| Utc::now().fixed_offset()),
+                    notes: notes_file.as_deref().map(read_notes).transpose()?,
+                    changelog_url,
+                    commit,
+                    license,
+                    signatures,
+                    attestations,
+                    min_platforms,
+                };
+                archives.extend(artifacts.into_iter().map(|artifact| artifact.0));
+                archives.extend(debug_symbols.into_iter().map(|archive| Archive {
+                    kind: ArtifactKind::DebugSymbols,
+                    ..archive
+                }));
+                if let Some(manifest) = &from_manifest {
+                    archives.extend(read_manifest(manifest)?);
+                }
+                target_opts.check(
+                    archives
+                        .iter()
+                        .map(|archive| archive.target_format.target.as_str())
+                        .chain(details.min_platforms.iter().map(|min| min.target.as_str())),
+                )?;
+                check_conflict(&release_json, &version, on_conflict, &mut archives)?;
+
+                let staging_file =
+                    staging_file.unwrap_or_else(|| format!("{}.staging", self.json).into());
+                let mut staging = StagingFile::open(&staging_file, resume)?;
 
-                let archives = fetch_release_checksums(&archive_prefix, archives, jobs).await;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                let archives = fetch_release_checksums(
+                    &archive_prefix,
+                    archives,
+                    Scheduler::new(jobs, self.deterministic),
+                    &fetcher,
+                    &mut staging,
+                )
+                .await;
+                check_size_budgets(&release_json, &version, &archives, &budget_opts)?;
 
+                let old_release_json = release_json.clone();
                 update_release_json(
                     &mut release_json,
-                    &release_url,
                     &version,
+                    details,
                     archives,
+                    on_conflict,
                     &self.json,
                 )?;
+                staging.remove()?;
+
+                let secrets = SecretResolver::new(&self.secret_opts);
+                notify_release_added(
+                    &notify_opts,
+                    &secrets,
+                    &old_release_json,
+                    &release_json,
+                    &version,
+                )
+                .await;
             }
             MuktiCommand::GenerateRedirects {
                 aliases,
+                auto_aliases,
                 flavor,
+                prefixes,
+                project_prefixes,
+                out_dir,
+                status_codes,
+                force,
+                no_compact,
+                strict,
+                root_redirect,
+                not_found,
+                template,
+                vars,
+                output_opts,
+                output_selection,
+                target_opts,
+                hook_opts,
+                notify_opts,
+            } => {
+                target_opts.check(
+                    aliases
+                        .iter()
+                        .map(|alias| alias.target_format.target.as_str()),
+                )?;
+                let aliases: Vec<_> = aliases.iter().map(Alias::to_path_alias).collect();
+                let release_json = read_release_json(&self.json, false)?;
+                let opts = GenerateOptions {
+                    aliases: &aliases,
+                    auto_aliases,
+                    flavor,
+                    prefixes: &prefixes,
+                    project_prefixes: &project_prefixes,
+                    status_codes: &status_codes,
+                    force,
+                    compact: !no_compact,
+                    strict,
+                    root_redirect,
+                    not_found: not_found.as_deref(),
+                    template: template.as_deref(),
+                    vars: &TemplateVar::to_map(vars),
+                    output_opts: &output_opts,
+                    outputs: output_selection.outputs(),
+                };
+                let changed = generate_redirects(&release_json, &out_dir, &opts)?;
+                hook_opts.run(&out_dir, &changed)?;
+
+                let secrets = SecretResolver::new(&self.secret_opts);
+                notify_redirects_generated(
+                    &notify_opts,
+                    &secrets,
+                    &release_json,
+                    &out_dir,
+                    &changed,
+                )
+                .await;
+            }
+            MuktiCommand::GenerateApi { out_dir } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_api(&release_json, &out_dir)?;
+            }
+            MuktiCommand::GenerateMarkdown { version } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_markdown(&release_json, &version)?;
+            }
+            MuktiCommand::GenerateAppcast { target, out } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_appcast(&release_json, target.as_deref(), &out)?;
+            }
+            MuktiCommand::GenerateSelfUpdate { repo, out_dir } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_self_update(&release_json, &repo, &out_dir)?;
+            }
+            MuktiCommand::GenerateNix { version, out } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_nix(&release_json, &version, &out)?;
+            }
+            MuktiCommand::GenerateAsdf { out_dir } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_asdf(&release_json, &out_dir)?;
+            }
+            MuktiCommand::GenerateApt {
+                base_url,
+                out_dir,
+                jobs,
+                fetch_opts,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                let scheduler = Scheduler::new(jobs, self.deterministic);
+                generate_apt(
+                    &release_json,
+                    base_url.as_deref(),
+                    &out_dir,
+                    scheduler,
+                    &fetcher,
+                )
+                .await?;
+            }
+            MuktiCommand::GenerateYum {
+                out_dir,
+                jobs,
+                fetch_opts,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                let scheduler = Scheduler::new(jobs, self.deterministic);
+                generate_yum(&release_json, &out_dir, scheduler, &fetcher).await?;
+            }
+            MuktiCommand::GenerateSite {
+                template_dir,
+                out_dir,
+                base_url,
+                vars,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                generate_site(
+                    &release_json,
+                    template_dir.as_deref(),
+                    &out_dir,
+                    base_url.as_deref(),
+                    &TemplateVar::to_map(vars),
+                )?;
+            }
+            MuktiCommand::PurgeCache {
+                provider,
+                base_url,
                 prefix,
+                aliases,
+                auto_aliases,
+                all,
+                dry_run,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let secrets = SecretResolver::new(&self.secret_opts);
+                let aliases: Vec<_> = aliases.iter().map(Alias::to_path_alias).collect();
+                let opts = PurgeOptions {
+                    provider,
+                    base_url: base_url.as_deref(),
+                    prefix: &prefix,
+                    aliases: &aliases,
+                    auto_aliases,
+                    all,
+                    dry_run,
+                };
+                purge_cache(&release_json, &opts, &secrets).await?;
+            }
+            MuktiCommand::LinkLatest {
+                artifact_dir,
                 out_dir,
+                mode,
+                aliases,
+                auto_aliases,
             } => {
                 let release_json = read_release_json(&self.json, false)?;
-                generate_redirects(&release_json, &aliases, flavor, &prefix, &out_dir)?;
+                let aliases: Vec<_> = aliases.iter().map(Alias::to_path_alias).collect();
+                link_latest(
+                    &release_json,
+                    &artifact_dir,
+                    &out_dir,
+                    &aliases,
+                    auto_aliases,
+                    mode,
+                )?;
+            }
+            MuktiCommand::BackfillChecksums { jobs, fetch_opts } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                let scheduler = Scheduler::new(jobs, self.deterministic);
+                backfill_checksums(&mut release_json, scheduler, &fetcher).await;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::SetEol {
+                range,
+                date,
+                clear: _,
+            } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                set_eol(&mut release_json, range, date)?;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::SetUrlTemplate { template, clear: _ } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                set_url_template(&mut release_json, template)?;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::SetLatest { version, pin } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                set_latest(&mut release_json, &version, pin)?;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::Promote { from, to, version } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                promote(&mut release_json, from.as_deref(), &to, &version)?;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::Annotate {
+                version,
+                notes_file,
+                changelog_url,
+                clear,
+            } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                annotate(
+                    &mut release_json,
+                    &version,
+                    notes_file.as_deref(),
+                    changelog_url.as_deref(),
+                    clear,
+                )?;
+                write_releases_json(&release_json, &self.json)?;
             }
-            MuktiCommand::BackfillChecksums { jobs } => {
+            MuktiCommand::SetProjectInfo {
+                display_name,
+                description,
+                homepage,
+                repository,
+                license,
+                clear,
+            } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                let update = ProjectInfo {
+                    display_name,
+                    description,
+                    homepage,
+                    repository,
+                    license,
+                    extra: BTreeMap::new(),
+                };
+                set_project_info(&mut release_json, update, clear)?;
+                write_releases_json(&release_json, &self.json)?;
+            }
+            MuktiCommand::Yank { version, reason } => {
                 let mut release_json = read_release_json(&self.json, false)?;
-                backfill_checksums(&mut release_json, jobs).await;
+                yank(&mut release_json, &version, reason)?;
                 write_releases_json(&release_json, &self.json)?;
             }
+            MuktiCommand::Gc {
+                older_than,
+                released_before,
+                dry_run,
+            } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                let removed_any = gc_release_json(
+                    &mut release_json,
+                    older_than.as_ref(),
+                    released_before,
+                    dry_run,
+                )?;
+                if removed_any && !dry_run {
+                    write_releases_json(&release_json, &self.json)?;
+                }
+            }
+            MuktiCommand::Download {
+                version,
+                target,
+                format,
+                out,
+                allow_unverified,
+                fetch_opts,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                download(
+                    &release_json,
+                    &version,
+                    &target,
+                    format.as_deref(),
+                    &out,
+                    allow_unverified,
+                    &fetcher,
+                )
+                .await?;
+            }
+            MuktiCommand::Resolve {
+                version,
+                target,
+                format,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                resolve(
+                    &release_json,
+                    &version,
+                    target.as_deref(),
+                    format.as_deref(),
+                )?;
+            }
+            MuktiCommand::VerifyProvenance {
+                version,
+                fetch_opts,
+            } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, false)?;
+                verify_provenance(&release_json, &version, &fetcher).await?;
+            }
+            MuktiCommand::Mirror {
+                dest,
+                public_url,
+                update_urls,
+                jobs,
+                respect_robots_txt,
+                fetch_opts,
+            } => {
+                let mut release_json = read_release_json(&self.json, false)?;
+                let fetcher = Fetcher::new(&fetch_opts, respect_robots_txt)?;
+                let secrets = SecretResolver::new(&self.secret_opts);
+                let changed = mirror(
+                    &mut release_json,
+                    &dest,
+                    public_url.as_deref(),
+                    update_urls,
+                    Scheduler::new(jobs, self.deterministic),
+                    &fetcher,
+                    &secrets,
+                )
+                .await?;
+                if changed {
+                    write_releases_json(&release_json, &self.json)?;
+                }
+            }
+            MuktiCommand::Fmt {
+                check,
+                digest_encoding,
+            } => {
+                format_release_json(&self.json, check, digest_encoding)?;
+            }
+            MuktiCommand::Report { format } => {
+                let release_json = read_release_json(&self.json, false)?;
+                report(&release_json, format)?;
+            }
+            MuktiCommand::Diff { old, format } => {
+                let old_release_json = read_release_json(&old, false)?;
+                let release_json = read_release_json(&self.json, false)?;
+                diff_release_json(&old_release_json, &release_json, format)?;
+            }
+            MuktiCommand::Lint { deny, target_opts } => {
+                let release_json = read_release_json(&self.json, false)?;
+                lint_release_json(&release_json, deny, &target_opts)?;
+            }
+            MuktiCommand::Migrate { check } => {
+                migrate_release_json(&self.json, check)?;
+            }
+            MuktiCommand::Schema { kind } => {
+                print_schema(kind)?;
+            }
+            MuktiCommand::Shard { out_dir, hook_opts } => {
+                let release_json = read_release_json(&self.json, false)?;
+                let changed = shard_release_json(&release_json, &out_dir)?;
+                hook_opts.run(&out_dir, &changed)?;
+            }
+            MuktiCommand::BenchParse { file, iterations } => {
+                bench_parse(&file, iterations)?;
+            }
+            MuktiCommand::Deploy { command } => match command {
+                DeployCommand::Netlify {
+                    site_id,
+                    auth_token,
+                    dir,
+                    draft,
+                } => {
+                    let secrets = SecretResolver::new(&self.secret_opts);
+                    let site_id = match site_id {
+                        Some(site_id) => site_id,
+                        None => secrets.require(NETLIFY_SITE_ID)?,
+                    };
+                    deploy_netlify(&site_id, auth_token.as_deref(), &dir, draft, &secrets).await?;
+                }
+            },
+            MuktiCommand::Alias { command } => match command {
+                AliasCommand::Add {
+                    aliases,
+                    target_opts,
+                } => {
+                    target_opts.check(
+                        aliases
+                            .iter()
+                            .map(|alias| alias.target_format.target.as_str()),
+                    )?;
+                    let mut release_json = read_release_json(&self.json, false)?;
+                    add_aliases(&mut release_json, &aliases)?;
+                    write_releases_json(&release_json, &self.json)?;
+                }
+                AliasCommand::Remove { names } => {
+                    let mut release_json = read_release_json(&self.json, false)?;
+                    remove_aliases(&mut release_json, &names)?;
+                    write_releases_json(&release_json, &self.json)?;
+                }
+                AliasCommand::List => {
+                    let release_json = read_release_json(&self.json, false)?;
+                    list_aliases(&release_json)?;
+                }
+            },
+            MuktiCommand::Publish {
+                dest,
+                dirs,
+                json_key,
+                cache_control,
+                immutable_cache_control,
+                dry_run,
+                command,
+            } => match command {
+                None => {
+                    let Some(dest) = dest else {
+                        bail!("a destination or a subcommand is required");
+                    };
+                    // Make sure the releases JSON is valid before publishing it.
+                    read_release_json(&self.json, false)?;
+                    let secrets = SecretResolver::new(&self.secret_opts);
+                    let cache_control = CacheControl {
+                        mutable: &cache_control,
+                        immutable: &immutable_cache_control,
+                    };
+                    publish_to_destination(
+                        &self.json,
+                        &json_key,
+                        &dirs,
+                        &dest,
+                        &cache_control,
+                        dry_run,
+                        &secrets,
+                    )
+                    .await?;
+                }
+                Some(PublishCommand::GithubAssets {
+                    repo,
+                    version,
+                    tag,
+                    dry_run,
+                }) => {
+                    let release_json = read_release_json(&self.json, false)?;
+                    let secrets = SecretResolver::new(&self.secret_opts);
+                    publish_github_assets(
+                        &release_json,
+                        &repo,
+                        &version,
+                        tag.as_deref(),
+                        dry_run,
+                        &secrets,
+                    )
+                    .await?;
+                }
+                Some(PublishCommand::GithubRelease {
+                    repo,
+                    tag,
+                    asset_name,
+                    dry_run,
+                }) => {
+                    let release_json = read_release_json(&self.json, false)?;
+                    let secrets = SecretResolver::new(&self.secret_opts);
+                    publish_github_release(
+                        &self.json,
+                        &release_json,
+                        &repo,
+                        &tag,
+                        &asset_name,
+                        dry_run,
+                        &secrets,
+                    )
+                    .await?;
+                }
+            },
         }
 
         Ok(())
     }
 }
 
+impl MuktiCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::AddRelease { .. } => "add-release",
+            Self::GenerateRedirects { .. } => "generate-redirects",
+            Self::GenerateApi { .. } => "generate-api",
+            Self::GenerateMarkdown { .. } => "generate-markdown",
+            Self::GenerateAppcast { .. } => "generate-appcast",
+            Self::GenerateSelfUpdate { .. } => "generate-self-update",
+            Self::GenerateNix { .. } => "generate-nix",
+            Self::GenerateAsdf { .. } => "generate-asdf",
+            Self::GenerateApt { .. } => "generate-apt",
+            Self::GenerateYum { .. } => "generate-yum",
+            Self::GenerateSite { .. } => "generate-site",
+            Self::PurgeCache { .. } => "purge-cache",
+            Self::LinkLatest { .. } => "link-latest",
+            Self::BackfillChecksums { .. } => "backfill-checksums",
+            Self::SetLatest { .. } => "set-latest",
+            Self::Promote { .. } => "promote",
+            Self::Annotate { .. } => "annotate",
+            Self::Yank { .. } => "yank",
+            Self::Gc { .. } => "gc",
+            Self::Download { .. } => "download",
+            Self::Resolve { .. } => "resolve",
+            Self::VerifyProvenance { .. } => "verify-provenance",
+            Self::SetProjectInfo { .. } => "set-project-info",
+            Self::SetEol { .. } => "set-eol",
+            Self::SetUrlTemplate { .. } => "set-url-template",
+            Self::Mirror { .. } => "mirror",
+            Self::Fmt { .. } => "fmt",
+            Self::Report { .. } => "report",
+            Self::Diff { .. } => "diff",
+            Self::Lint { .. } => "lint",
+            Self::Migrate { .. } => "migrate",
+            Self::Schema { .. } => "schema",
+            Self::Shard { .. } => "shard",
+            Self::BenchParse { .. } => "bench-parse",
+            Self::Publish { command, .. } => match command {
+                None => "publish",
+                Some(PublishCommand::GithubAssets { .. }) => "publish github-assets",
+                Some(PublishCommand::GithubRelease { .. }) => "publish github-release",
+            },
+            Self::Deploy { command } => match command {
+                DeployCommand::Netlify { .. } => "deploy netlify",
+            },
+            Self::Alias { command } => match command {
+                AliasCommand::Add { .. } => "alias add",
+                AliasCommand::Remove { .. } => "alias remove",
+                AliasCommand::List => "alias list",
+            },
+        }
+    }
+
+    /// The version this command operates on, if any.
+    fn version(&self) -> Option<&Version> {
+        match self {
+            Self::AddRelease { version, .. }
+            | Self::SetLatest { version, .. }
+            | Self::Promote { version, .. }
+            | Self::Annotate { version, .. }
+            | Self::Yank { version, .. } => Some(version),
+            Self::Publish { command, .. } => match command {
+                None | Some(PublishCommand::GithubRelease { .. }) => None,
+                Some(PublishCommand::GithubAssets { version, .. }) => Some(version),
+            },
+            Self::GenerateRedirects { .. }
+            | Self::GenerateApi { .. }
+            | Self::GenerateMarkdown { .. }
+            | Self::GenerateAppcast { .. }
+            | Self::GenerateSelfUpdate { .. }
+            | Self::GenerateNix { .. }
+            | Self::GenerateAsdf { .. }
+            | Self::GenerateApt { .. }
+            | Self::GenerateYum { .. }
+            | Self::GenerateSite { .. }
+            | Self::PurgeCache { .. }
+            | Self::Deploy { .. }
+            | Self::Alias { .. }
+            | Self::LinkLatest { .. }
+            | Self::BackfillChecksums { .. }
+            | Self::Gc { .. }
+            | Self::Download { .. }
+            | Self::Resolve { .. }
+            | Self::VerifyProvenance { .. }
+            | Self::SetProjectInfo { .. }
+            | Self::SetEol { .. }
+            | Self::SetUrlTemplate { .. }
+            | Self::Mirror { .. }
+            | Self::Fmt { .. }
+            | Self::Report { .. }
+            | Self::Diff { .. }
+            | Self::Lint { .. }
+            | Self::Migrate { .. }
+            | Self::Schema { .. }
+            | Self::Shard { .. }
+            | Self::BenchParse { .. } => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Archive {
     pub(crate) target_format: TargetFormat,
     pub(crate) name: String,
+    pub(crate) kind: ArtifactKind,
+    // Checksums known ahead of time, e.g. from a manifest.
+    pub(crate) expected_checksums: BTreeMap<DigestAlgorithm, Digest>,
+}
+
+impl Archive {
+    /// If checksums for all algorithms mukti computes are known ahead of time, returns them so the
+    /// archive doesn't need to be fetched.
+    pub(crate) fn precomputed_checksums(&self) -> Option<Checksums> {
+        let sha256 = self.expected_checksums.get(&DigestAlgorithm::Sha256)?;
+        let blake2b = self.expected_checksums.get(&DigestAlgorithm::Blake2b)?;
+        Checksums::from_hex(&sha256.0, &blake2b.0).ok()
+    }
 }
 
 impl FromStr for Archive {
@@ -131,25 +1686,201 @@ impl FromStr for Archive {
         Ok(Self {
             target_format,
             name,
+            kind: ArtifactKind::Archive,
+            expected_checksums: BTreeMap::new(),
+        })
+    }
+}
+
+/// An artifact passed in with `--artifact`, with its kind before the target.
+#[derive(Clone, Debug)]
+pub(crate) struct KindedArchive(pub(crate) Archive);
+
+impl FromStr for KindedArchive {
+    type Err = NameValueParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (kind, archive) = name_value_parse(input, ':')?;
+        let mut archive: Archive = archive.parse()?;
+        archive.kind = ArtifactKind::from(kind.as_str());
+        Ok(Self(archive))
+    }
+}
+
+/// An attestation predicate type, and the suffix appended to archive URLs to get attestation URLs.
+#[derive(Clone, Debug)]
+pub(crate) struct AttestationSuffix {
+    pub(crate) predicate_type: String,
+    pub(crate) suffix: String,
+}
+
+impl AttestationSuffix {
+    pub(crate) fn entry_for(&self, url: &str) -> AttestationEntry {
+        AttestationEntry {
+            predicate_type: self.predicate_type.clone(),
+            url: format!("{url}{}", self.suffix),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+impl FromStr for AttestationSuffix {
+    type Err = NameValueParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (predicate_type, suffix) = name_value_parse(input, '=')?;
+        Ok(Self {
+            predicate_type,
+            suffix,
         })
     }
 }
 
+/// The oldest version of a platform that a target's artifacts run on.
+#[derive(Clone, Debug)]
+pub(crate) struct MinPlatform {
+    pub(crate) target: String,
+    pub(crate) platform: Platform,
+    pub(crate) version: String,
+}
+
+/// A platform whose minimum version can be recorded with `--min-platform`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Platform {
+    Glibc,
+    Macos,
+    Windows,
+}
+
+impl MinPlatform {
+    /// Record the minimum version on `location`, if it's for this target.
+    pub(crate) fn apply(&self, location: &mut ReleaseLocation) {
+        if location.target != self.target {
+            return;
+        }
+        let field = match self.platform {
+            Platform::Glibc => &mut location.min_glibc,
+            Platform::Macos => &mut location.min_macos,
+            Platform::Windows => &mut location.min_windows,
+        };
+        *field = Some(self.version.clone());
+    }
+}
+
+impl FromStr for MinPlatform {
+    type Err = MinPlatformParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (target_platform, version) = name_value_parse(input, '=')?;
+        let (target, platform) = name_value_parse(&target_platform, ':')?;
+        let platform = match platform.as_str() {
+            "glibc" => Platform::Glibc,
+            "macos" => Platform::Macos,
+            "windows" => Platform::Windows,
+            _ => return Err(MinPlatformParseError::Platform { input: platform }),
+        };
+        Ok(Self {
+            target,
+            platform,
+            version,
+        })
+    }
+}
+
+/// A signature scheme, and the suffix appended to archive URLs to get signature URLs.
+#[derive(Clone, Debug)]
+pub(crate) struct SignatureSuffix {
+    pub(crate) scheme: String,
+    pub(crate) suffix: String,
+}
+
+impl SignatureSuffix {
+    pub(crate) fn entry_for(&self, url: &str) -> SignatureEntry {
+        SignatureEntry {
+            scheme: self.scheme.clone(),
+            key_id: None,
+            url: Some(format!("{url}{}", self.suffix)),
+            value: None,
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+impl FromStr for SignatureSuffix {
+    type Err = NameValueParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (scheme, suffix) = name_value_parse(input, '=')?;
+        Ok(Self { scheme, suffix })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Alias {
     pub(crate) alias: String,
     pub(crate) target_format: TargetFormat,
+    // If set, the alias is only generated for this project.
+    pub(crate) project: Option<String>,
+    // If set, the alias is only generated for versions matching this requirement.
+    pub(crate) version_req: Option<VersionReq>,
+}
+
+impl Alias {
+    pub(crate) fn to_path_alias(&self) -> PathAlias {
+        PathAlias {
+            alias: self.alias.clone(),
+            target: self.target_format.target.clone(),
+            format: self.target_format.format.clone(),
+            project: self.project.clone(),
+            version_req: self.version_req.clone(),
+        }
+    }
 }
 
 impl FromStr for Alias {
-    type Err = NameValueParseError;
+    type Err = AliasParseError;
 
+    /// Parses `[PROJECT:]ALIAS[@VERSION_REQ]=TARGET:FORMAT`.
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let (alias, target_format) = name_value_parse(input, '=')?;
+        // Version requirements can contain '=', but targets and formats can't.
+        let (scoped_alias, target_format) = match input.rsplit_once('=') {
+            Some((k, v)) => (k, v),
+            None => {
+                return Err(NameValueParseError {
+                    input: input.to_owned(),
+                    delimiter: '=',
+                }
+                .into())
+            }
+        };
         let target_format: TargetFormat = target_format.parse()?;
+        if target_format.target == "*" && !scoped_alias.contains("{target}") {
+            return Err(AliasParseError::WildcardTarget {
+                input: input.to_owned(),
+            });
+        }
+
+        // Aliases can contain '/' to publish under a subdirectory, so the project is scoped with ':'.
+        let (project, alias) = match scoped_alias.split_once(':') {
+            Some((project, alias)) => (Some(project.to_owned()), alias),
+            None => (None, scoped_alias),
+        };
+        let (alias, version_req) = match alias.split_once('@') {
+            Some((alias, req)) => {
+                let req = req.parse().map_err(|error| AliasParseError::VersionReq {
+                    input: req.to_owned(),
+                    error,
+                })?;
+                (alias, Some(req))
+            }
+            None => (alias, None),
+        };
+
         Ok(Self {
-            alias,
+            alias: alias.to_owned(),
             target_format,
+            project,
+            version_req,
         })
     }
 }
@@ -169,6 +1900,45 @@ impl FromStr for TargetFormat {
     }
 }
 
+/// A project to generate redirects for, and the prefix to generate them under.
+#[derive(Clone, Debug)]
+pub(crate) struct ProjectPrefix {
+    pub(crate) project: String,
+    pub(crate) prefix: String,
+}
+
+impl FromStr for ProjectPrefix {
+    type Err = NameValueParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (project, prefix) = name_value_parse(input, '=')?;
+        Ok(Self { project, prefix })
+    }
+}
+
+/// A variable passed to templates with `--var`.
+#[derive(Clone, Debug)]
+pub(crate) struct TemplateVar {
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+impl TemplateVar {
+    /// Collect variables into a map, with later values for the same name taking precedence.
+    fn to_map(vars: Vec<Self>) -> BTreeMap<String, String> {
+        vars.into_iter().map(|var| (var.name, var.value)).collect()
+    }
+}
+
+impl FromStr for TemplateVar {
+    type Err = NameValueParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (name, value) = name_value_parse(input, '=')?;
+        Ok(Self { name, value })
+    }
+}
+
 fn name_value_parse(input: &str, delimiter: char) -> Result<(String, String), NameValueParseError> {
     match input.split_once(delimiter) {
         Some((k, v)) => Ok((k.to_owned(), v.to_owned())),
@@ -178,3 +1948,23 @@ fn name_value_parse(input: &str, delimiter: char) -> Result<(String, String), Na
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_parse() {
+        let alias: Alias = "linux/x64=x86_64-unknown-linux-gnu:tar.gz".parse().unwrap();
+        assert_eq!(alias.alias, "linux/x64");
+        assert_eq!(alias.project, None);
+
+        let alias: Alias = "nextest:linux@>=0.9=x86_64-unknown-linux-gnu:tar.gz"
+            .parse()
+            .unwrap();
+        assert_eq!(alias.alias, "linux");
+        assert_eq!(alias.project.as_deref(), Some("nextest"));
+        assert_eq!(alias.version_req, Some(">=0.9".parse().unwrap()));
+        assert_eq!(alias.target_format.format, "tar.gz");
+    }
+}
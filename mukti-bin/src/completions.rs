@@ -0,0 +1,107 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dynamic shell completions for values stored in the release JSON.
+//!
+//! Completions are registered with e.g. `source <(COMPLETE=bash mukti-bin)`. Since completers
+//! don't have access to the rest of the command line, the release JSON is read from
+//! `$MUKTI_JSON`, falling back to `.releases.json` in the current directory.
+
+use crate::release_json::read_release_json;
+use camino::Utf8PathBuf;
+use clap_complete::engine::CompletionCandidate;
+use mukti_metadata::MuktiReleasesJson;
+use std::{collections::BTreeSet, ffi::OsStr};
+
+/// Complete versions recorded in the release JSON.
+pub(crate) fn complete_versions(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(release_json) = completion_release_json() else {
+        return Vec::new();
+    };
+    let versions = release_json.projects.values().flat_map(|project| {
+        project
+            .all_versions()
+            .map(|(version, _)| version.to_string())
+    });
+    candidates(current, "", versions)
+}
+
+/// Complete targets recorded in the release JSON.
+pub(crate) fn complete_targets(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(release_json) = completion_release_json() else {
+        return Vec::new();
+    };
+    candidates(
+        current,
+        "",
+        all_target_formats(&release_json).map(|(t, _)| t),
+    )
+}
+
+/// Complete values of the form `TARGET:FORMAT=NAME`.
+pub(crate) fn complete_archives(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(release_json) = completion_release_json() else {
+        return Vec::new();
+    };
+    let current_str = current.to_string_lossy();
+    if current_str.contains('=') {
+        // The file name can't be completed from the release JSON.
+        return Vec::new();
+    }
+    candidates(
+        current,
+        "",
+        all_target_formats(&release_json).map(|(target, format)| format!("{target}:{format}=")),
+    )
+}
+
+/// Complete values of the form `ALIAS=TARGET:FORMAT`.
+pub(crate) fn complete_aliases(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(release_json) = completion_release_json() else {
+        return Vec::new();
+    };
+    let current_str = current.to_string_lossy();
+    // Scoped aliases can contain '=' in their version requirement.
+    let Some((alias, _)) = current_str.rsplit_once('=') else {
+        return Vec::new();
+    };
+    candidates(
+        current,
+        &format!("{alias}="),
+        all_target_formats(&release_json).map(|(target, format)| format!("{target}:{format}")),
+    )
+}
+
+fn completion_release_json() -> Option<MuktiReleasesJson> {
+    let path: Utf8PathBuf = std::env::var("MUKTI_JSON")
+        .unwrap_or_else(|_| ".releases.json".to_owned())
+        .into();
+    read_release_json(&path, false).ok()
+}
+
+fn all_target_formats(
+    release_json: &MuktiReleasesJson,
+) -> impl Iterator<Item = (String, String)> + '_ {
+    let set: BTreeSet<_> = release_json
+        .projects
+        .values()
+        .flat_map(|project| project.all_versions())
+        .flat_map(|(_, version_data)| &version_data.locations)
+        .map(|location| (location.target.clone(), location.format.to_string()))
+        .collect();
+    set.into_iter()
+}
+
+fn candidates(
+    current: &OsStr,
+    prefix: &str,
+    values: impl IntoIterator<Item = String>,
+) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let values: BTreeSet<_> = values
+        .into_iter()
+        .map(|value| format!("{prefix}{value}"))
+        .filter(|value| value.starts_with(&*current))
+        .collect();
+    values.into_iter().map(CompletionCandidate::new).collect()
+}
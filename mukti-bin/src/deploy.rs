@@ -0,0 +1,92 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Deploy generated files directly to a hosting provider.
+
+use crate::{
+    netlify::{NetlifyClient, NETLIFY_AUTH_TOKEN},
+    secrets::SecretResolver,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{bail, Result, WrapErr};
+use sha1_checked::{Digest, Sha1};
+use std::collections::BTreeMap;
+
+/// Deploy the contents of `dir` to a Netlify site, replacing everything currently deployed.
+pub(crate) async fn deploy_netlify(
+    site_id: &str,
+    auth_token: Option<&str>,
+    dir: &Utf8Path,
+    draft: bool,
+    secrets: &SecretResolver,
+) -> Result<()> {
+    let files = collect_files(dir)?;
+    if files.is_empty() {
+        bail!("no files to deploy found in {dir}");
+    }
+
+    // Netlify identifies files by SHA-1, and only asks for the ones it doesn't already have.
+    let mut digests = BTreeMap::new();
+    let mut by_digest = BTreeMap::new();
+    for (path, local_path) in &files {
+        let contents =
+            std::fs::read(local_path).wrap_err_with(|| format!("failed to read {local_path}"))?;
+        let digest = hex::encode(Sha1::digest(&contents));
+        digests.insert(path.clone(), digest.clone());
+        by_digest.entry(digest).or_insert((path.clone(), contents));
+    }
+
+    let token = match auth_token {
+        Some(token) => token.to_owned(),
+        None => secrets.require(NETLIFY_AUTH_TOKEN)?,
+    };
+    let client = NetlifyClient::new(&token)?;
+    let deploy = client.create_deploy(site_id, &digests, draft).await?;
+    eprintln!(
+        "created {}deploy {} with {} files, {} to upload",
+        if draft { "draft " } else { "" },
+        deploy.id,
+        digests.len(),
+        deploy.required.len()
+    );
+
+    for digest in &deploy.required {
+        let Some((path, contents)) = by_digest.remove(digest) else {
+            bail!("Netlify requested a file with unknown digest {digest}");
+        };
+        eprintln!("uploading {path}");
+        client.upload_file(&deploy, &path, contents).await?;
+    }
+
+    match &deploy.deploy_ssl_url {
+        Some(url) => eprintln!("deployed to {url}"),
+        None => eprintln!("deployed {}", deploy.id),
+    }
+    Ok(())
+}
+
+/// Return a map of deploy paths (starting with `/`) to local paths, for all files within `dir`.
+pub(crate) fn collect_files(dir: &Utf8Path) -> Result<BTreeMap<String, Utf8PathBuf>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(current) = dirs.pop() {
+        let entries = current
+            .read_dir_utf8()
+            .wrap_err_with(|| format!("failed to read directory {current}"))?;
+        for entry in entries {
+            let entry = entry.wrap_err_with(|| format!("failed to read directory {current}"))?;
+            let path = entry.path();
+            let file_type = entry
+                .file_type()
+                .wrap_err_with(|| format!("failed to read file type of {path}"))?;
+            if file_type.is_dir() {
+                dirs.push(path.to_owned());
+            } else {
+                let relative = path.strip_prefix(dir).expect("path is within dir");
+                let deploy_path = format!("/{}", relative.as_str().replace('\\', "/"));
+                files.insert(deploy_path, path.to_owned());
+            }
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,182 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Locations that files can be uploaded to: local directories or remote object stores.
+
+use crate::{azure::AzureBlobClient, gcs::GcsClient, s3::S3Client, secrets::SecretResolver};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8PathBuf;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use std::{fmt, io::Write as _, str::FromStr};
+
+/// A destination, parsed from either a URL like `s3://bucket/prefix`, `gs://bucket/prefix` or
+/// `azure://account/container/prefix`, or a local path.
+#[derive(Clone, Debug)]
+pub(crate) enum Destination {
+    Local(Utf8PathBuf),
+    S3 {
+        bucket: String,
+        prefix: String,
+    },
+    Gcs {
+        bucket: String,
+        prefix: String,
+    },
+    Azure {
+        account: String,
+        container: String,
+        prefix: String,
+    },
+}
+
+impl Destination {
+    /// Prepare to upload files to this destination, resolving any credentials required.
+    pub(crate) fn connect(&self, secrets: &SecretResolver) -> Result<Uploader> {
+        match self {
+            Self::Local(dir) => Ok(Uploader::Local(dir.clone())),
+            Self::S3 { bucket, prefix } => Ok(Uploader::S3 {
+                client: S3Client::new(bucket, secrets)?,
+                prefix: prefix.clone(),
+            }),
+            Self::Gcs { bucket, prefix } => Ok(Uploader::Gcs {
+                client: Box::new(GcsClient::new(bucket, secrets)?),
+                prefix: prefix.clone(),
+            }),
+            Self::Azure {
+                account,
+                container,
+                prefix,
+            } => Ok(Uploader::Azure {
+                client: AzureBlobClient::new(account, container, secrets)?,
+                prefix: prefix.clone(),
+            }),
+        }
+    }
+}
+
+impl FromStr for Destination {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = input.strip_prefix("s3://") {
+            let (bucket, prefix) = split_first(rest, input, "bucket")?;
+            return Ok(Self::S3 { bucket, prefix });
+        }
+        if let Some(rest) = input.strip_prefix("gs://") {
+            let (bucket, prefix) = split_first(rest, input, "bucket")?;
+            return Ok(Self::Gcs { bucket, prefix });
+        }
+        if let Some(rest) = input.strip_prefix("azure://") {
+            let (account, rest) = split_first(rest, input, "storage account")?;
+            let (container, prefix) = split_first(&rest, input, "container")?;
+            return Ok(Self::Azure {
+                account,
+                container,
+                prefix,
+            });
+        }
+        if let Some((scheme, _)) = input.split_once("://") {
+            return Err(format!("unsupported destination scheme `{scheme}://`"));
+        }
+        Ok(Self::Local(input.into()))
+    }
+}
+
+/// Split the first path component (e.g. a bucket name) off `rest`, returning it along with the
+/// remainder with surrounding slashes trimmed.
+fn split_first(rest: &str, input: &str, what: &str) -> Result<(String, String), String> {
+    let (first, remainder) = rest.split_once('/').unwrap_or((rest, ""));
+    if first.is_empty() {
+        return Err(format!("no {what} specified in `{input}`"));
+    }
+    Ok((first.to_owned(), remainder.trim_matches('/').to_owned()))
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(dir) => write!(f, "{dir}"),
+            Self::S3 { bucket, prefix } => write!(f, "s3://{bucket}/{prefix}"),
+            Self::Gcs { bucket, prefix } => write!(f, "gs://{bucket}/{prefix}"),
+            Self::Azure {
+                account,
+                container,
+                prefix,
+            } => write!(f, "azure://{account}/{container}/{prefix}"),
+        }
+    }
+}
+
+/// Optional metadata for an uploaded file. Ignored for local destinations.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ObjectMetadata<'a> {
+    pub(crate) content_type: Option<&'a str>,
+    pub(crate) cache_control: Option<&'a str>,
+}
+
+pub(crate) enum Uploader {
+    Local(Utf8PathBuf),
+    S3 {
+        client: S3Client,
+        prefix: String,
+    },
+    Gcs {
+        client: Box<GcsClient>,
+        prefix: String,
+    },
+    Azure {
+        client: AzureBlobClient,
+        prefix: String,
+    },
+}
+
+impl Uploader {
+    /// Upload `body` to `key`, a relative path within the destination.
+    pub(crate) async fn put(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        metadata: &ObjectMetadata<'_>,
+    ) -> Result<()> {
+        if key.split('/').any(|component| component == "..") {
+            bail!("invalid key `{key}`: must not contain `..`");
+        }
+
+        match self {
+            Self::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+                }
+                let file = AtomicFile::new(&path, OverwriteBehavior::AllowOverwrite);
+                file.write(|f| f.write_all(&body))
+                    .wrap_err_with(|| format!("failed to write {path}"))?;
+                Ok(())
+            }
+            Self::S3 { client, prefix } => {
+                client
+                    .put_object(&prefixed_key(prefix, key), body, metadata)
+                    .await
+            }
+            Self::Gcs { client, prefix } => {
+                client
+                    .put_object(&prefixed_key(prefix, key), body, metadata)
+                    .await
+            }
+            Self::Azure { client, prefix } => {
+                client
+                    .put_blob(&prefixed_key(prefix, key), body, metadata)
+                    .await
+            }
+        }
+    }
+}
+
+fn prefixed_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{prefix}/{key}")
+    }
+}
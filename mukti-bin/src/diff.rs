@@ -0,0 +1,101 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Show what changed between two copies of the release JSON.
+
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{diff, MuktiReleasesJson, ProjectDiff, ReleaseDiff, VersionDiff};
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum DiffFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+
+    /// JSON, in the same shape as webhook payloads
+    Json,
+}
+
+pub(crate) fn diff_release_json(
+    old: &MuktiReleasesJson,
+    new: &MuktiReleasesJson,
+    format: DiffFormat,
+) -> Result<()> {
+    let release_diff = diff(old, new);
+    match format {
+        DiffFormat::Text => {
+            if release_diff.is_empty() {
+                eprintln!("no changes");
+            }
+            print_text(&release_diff);
+        }
+        DiffFormat::Json => {
+            let json =
+                serde_json::to_string_pretty(&release_diff).wrap_err("failed to serialize diff")?;
+            println!("{json}");
+        }
+    }
+    Ok(())
+}
+
+fn print_text(release_diff: &ReleaseDiff) {
+    for (name, project_diff) in &release_diff.projects {
+        println!("{name}:");
+        print_project(project_diff);
+    }
+}
+
+fn print_project(project_diff: &ProjectDiff) {
+    for version in &project_diff.added_versions {
+        println!("  added {version}");
+    }
+    for version in &project_diff.removed_versions {
+        println!("  removed {version}");
+    }
+    if let Some(latest) = &project_diff.latest {
+        let display = |version: &Option<_>| match version {
+            Some(version) => format!("{version}"),
+            None => "none".to_owned(),
+        };
+        println!(
+            "  latest: {} -> {}",
+            display(&latest.old),
+            display(&latest.new)
+        );
+    }
+    for (version, version_diff) in &project_diff.changed_versions {
+        println!("  changed {version}:");
+        print_version(version_diff);
+    }
+}
+
+fn print_version(version_diff: &VersionDiff) {
+    if let Some(status) = &version_diff.status {
+        println!(
+            "    status: {} -> {}",
+            status.old.as_str(),
+            status.new.as_str()
+        );
+    }
+    for location in &version_diff.added_locations {
+        println!("    added {location}");
+    }
+    for location in &version_diff.removed_locations {
+        println!("    removed {location}");
+    }
+    for change in &version_diff.checksum_changes {
+        let what = match (&change.old, &change.new) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ => "changed",
+        };
+        println!(
+            "    {what} {} checksum for {}",
+            change.algorithm, change.location
+        );
+    }
+    for field in &version_diff.changed_fields {
+        println!("    changed {field}");
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Download artifacts using the release JSON, as a reference client would.
+
+use crate::{
+    checksums::verify_checksums, fetch::Fetcher, publish::file_name_from_url,
+    release_json::single_project,
+};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use mukti_metadata::{MuktiProject, MuktiReleasesJson, ReleaseLocation, ReleaseVersionData};
+use semver::{Version, VersionReq};
+use std::{fmt, io::Write as _, str::FromStr};
+
+/// A way to pick a version from the release JSON.
+#[derive(Clone, Debug)]
+pub(crate) enum VersionSpec {
+    /// The latest non-prerelease version.
+    Latest,
+
+    /// An exact version.
+    Exact(Version),
+
+    /// The version a requirement resolves to, as with [`MuktiProject::resolve_req`].
+    Req(VersionReq),
+}
+
+impl VersionSpec {
+    pub(crate) fn resolve<'a>(
+        &self,
+        project: &'a MuktiProject,
+    ) -> Option<(&'a Version, &'a ReleaseVersionData)> {
+        match self {
+            Self::Latest => project.latest_version_data(),
+            Self::Exact(version) => project.get_version_data(version),
+            Self::Req(req) => project.resolve_req(req),
+        }
+    }
+}
+
+impl FromStr for VersionSpec {
+    type Err = semver::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input == "latest" {
+            return Ok(Self::Latest);
+        }
+        match Version::parse(input) {
+            Ok(version) => Ok(Self::Exact(version)),
+            Err(_) => VersionReq::parse(input).map(Self::Req),
+        }
+    }
+}
+
+impl fmt::Display for VersionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Exact(version) => write!(f, "{version}"),
+            Self::Req(req) => write!(f, "{req}"),
+        }
+    }
+}
+
+/// Print the version `version` resolves to, or with a target, the URL of its artifact for that
+/// target.
+pub(crate) fn resolve(
+    release_json: &MuktiReleasesJson,
+    version: &VersionSpec,
+    target: Option<&str>,
+    format: Option<&str>,
+) -> Result<()> {
+    let (_, project) = single_project(release_json)?;
+    let (version, version_data) = version
+        .resolve(project)
+        .ok_or_else(|| eyre!("no version matching {version} found"))?;
+    match target {
+        Some(target) => {
+            let location = find_location(version, version_data, target, format)?;
+            println!("{}", location.url);
+        }
+        None => println!("{version}"),
+    }
+    Ok(())
+}
+
+pub(crate) async fn download(
+    release_json: &MuktiReleasesJson,
+    version: &VersionSpec,
+    target: &str,
+    format: Option<&str>,
+    out_dir: &Utf8Path,
+    allow_unverified: bool,
+    fetcher: &Fetcher,
+) -> Result<()> {
+    let (_, project) = single_project(release_json)?;
+    let (version, version_data) = version
+        .resolve(project)
+        .ok_or_else(|| eyre!("no version matching {version} found"))?;
+
+    let location = find_location(version, version_data, target, format)?;
+    let file_name = file_name_from_url(&location.url)
+        .ok_or_else(|| eyre!("unable to determine file name from URL {}", location.url))?;
+
+    // Try the primary URL, then each mirror in order, until one downloads and verifies.
+    let mut urls = std::iter::once(&location.url)
+        .chain(&location.mirror_urls)
+        .peekable();
+    let (bytes, verified) = loop {
+        let url = urls.next().expect("at least the primary URL is present");
+        eprintln!("downloading {version} for {target} from {url}");
+        let result = fetcher
+            .fetch_bytes(url)
+            .await
+            .and_then(|bytes| verify_checksums(location, &bytes).map(|verified| (bytes, verified)));
+        match result {
+            Ok(result) => break result,
+            Err(err) if urls.peek().is_some() => {
+                eprintln!("warning: {err:#}, trying the next mirror");
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    if verified == 0 {
+        if !allow_unverified {
+            bail!(
+                "no known checksums recorded for {} (pass in --allow-unverified to download anyway)",
+                location.url
+            );
+        }
+        eprintln!("warning: no known checksums recorded for {}", location.url);
+    }
+
+    std::fs::create_dir_all(out_dir)
+        .wrap_err_with(|| format!("failed to create output directory {out_dir}"))?;
+    let out_path = out_dir.join(file_name);
+    let file = AtomicFile::new(&out_path, OverwriteBehavior::AllowOverwrite);
+    file.write(|f| f.write_all(&bytes))
+        .wrap_err_with(|| format!("failed to write {out_path}"))?;
+
+    eprintln!(
+        "wrote {out_path} ({} bytes, {verified} checksums verified)",
+        bytes.len()
+    );
+    Ok(())
+}
+
+/// Find the one artifact for `target`, and `format` if specified, that users download.
+fn find_location<'a>(
+    version: &Version,
+    version_data: &'a ReleaseVersionData,
+    target: &str,
+    format: Option<&str>,
+) -> Result<&'a ReleaseLocation> {
+    let mut locations = version_data.redirect_locations().filter(|location| {
+        location.target == target && format.is_none_or(|format| location.format.as_str() == format)
+    });
+    match (locations.next(), locations.next()) {
+        (Some(location), None) => Ok(location),
+        (Some(_), Some(_)) => {
+            bail!("version {version} has multiple formats for target {target} (pass in --format)")
+        }
+        (None, _) => bail!(
+            "version {version} has no artifact for target {target}{}",
+            format.map_or_else(String::new, |format| format!(" and format {format}"))
+        ),
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! End-of-life dates for version ranges.
+
+use crate::release_json::single_project_mut;
+use chrono::NaiveDate;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{MuktiReleasesJson, ReleaseRangeData, VersionRange};
+
+/// The format end-of-life dates are stored in.
+const EOL_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Set or clear the end-of-life date for `range`.
+pub(crate) fn set_eol(
+    release_json: &mut MuktiReleasesJson,
+    range: VersionRange,
+    date: Option<NaiveDate>,
+) -> Result<()> {
+    let (_, project) = single_project_mut(release_json)?;
+    let Some(data) = project.ranges.get_mut(&range) else {
+        bail!("version range {range} not found in releases JSON");
+    };
+
+    match date {
+        Some(date) => {
+            eprintln!("range {range} reaches end of life on {date}");
+            data.eol_date = Some(date.format(EOL_DATE_FORMAT).to_string());
+        }
+        None => {
+            eprintln!("cleared end-of-life date for range {range}");
+            data.eol_date = None;
+        }
+    }
+    if date.is_some() && project.latest == Some(range) {
+        eprintln!("warning: range {range} is still the latest range");
+    }
+
+    Ok(())
+}
+
+/// Parse the end-of-life date for a range, if it has one.
+pub(crate) fn parse_eol_date(
+    data: &ReleaseRangeData,
+) -> Option<Result<NaiveDate, chrono::ParseError>> {
+    data.eol_date
+        .as_deref()
+        .map(|date| NaiveDate::parse_from_str(date, EOL_DATE_FORMAT))
+}
+
+/// Returns true if the range's end-of-life date is on or before `today`.
+///
+/// Dates that can't be parsed are treated as not having passed.
+pub(crate) fn is_past_eol(data: &ReleaseRangeData, today: NaiveDate) -> bool {
+    matches!(parse_eol_date(data), Some(Ok(date)) if date <= today)
+}
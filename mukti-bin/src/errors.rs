@@ -1,7 +1,85 @@
 // Copyright (c) The mukti Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::{error, fmt};
+use camino::Utf8Path;
+use clap::ValueEnum;
+use color_eyre::Report;
+use semver::Version;
+use std::{error, fmt, io};
+
+/// How errors are reported when a command fails.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum ErrorFormat {
+    /// Human-readable output with a backtrace
+    #[default]
+    Human,
+
+    /// A single JSON object on stderr
+    Json,
+}
+
+/// Returned by [`MuktiApp::exec`](crate::MuktiApp::exec) once an error has been printed in
+/// [`ErrorFormat::Json`], so the caller should exit with a failure code without printing it again.
+#[derive(Debug)]
+pub struct ErrorReported;
+
+impl fmt::Display for ErrorReported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("error already reported")
+    }
+}
+
+impl error::Error for ErrorReported {}
+
+/// Produce a machine-readable description of an error.
+///
+/// The `code` field is one of `invalid-argument`, `http`, `io`, `json`, or `other`, and is
+/// stable across releases.
+pub(crate) fn error_to_json(
+    err: &Report,
+    command: &str,
+    path: &Utf8Path,
+    version: Option<&Version>,
+) -> serde_json::Value {
+    let mut code = "other";
+    let mut url = None;
+    let mut status = None;
+
+    // Use the outermost error of a known type to classify the failure.
+    for cause in err.chain() {
+        if cause.is::<NameValueParseError>()
+            || cause.is::<AliasParseError>()
+            || cause.is::<SizeBudgetParseError>()
+            || cause.is::<MinPlatformParseError>()
+        {
+            code = "invalid-argument";
+        } else if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            code = "http";
+            url = err.url().map(|url| url.to_string());
+            status = err.status().map(|status| status.as_u16());
+        } else if cause.is::<io::Error>() {
+            code = "io";
+        } else if cause.is::<serde_json::Error>() {
+            code = "json";
+        } else {
+            continue;
+        }
+        break;
+    }
+
+    serde_json::json!({
+        "code": code,
+        "message": err.to_string(),
+        "causes": err.chain().skip(1).map(|cause| cause.to_string()).collect::<Vec<_>>(),
+        "context": {
+            "command": command,
+            "path": path,
+            "version": version,
+            "url": url,
+            "status": status,
+        },
+    })
+}
 
 #[derive(Clone, Debug)]
 pub(crate) struct NameValueParseError {
@@ -20,3 +98,95 @@ impl fmt::Display for NameValueParseError {
 }
 
 impl error::Error for NameValueParseError {}
+
+#[derive(Debug)]
+pub(crate) enum AliasParseError {
+    NameValue(NameValueParseError),
+    VersionReq { input: String, error: semver::Error },
+    WildcardTarget { input: String },
+}
+
+impl fmt::Display for AliasParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NameValue(err) => write!(f, "{}", err),
+            Self::VersionReq { input, .. } => {
+                write!(f, "unable to parse version requirement '{}'", input)
+            }
+            Self::WildcardTarget { input } => write!(
+                f,
+                "alias '{}' matches any target, so it must contain {{target}}",
+                input
+            ),
+        }
+    }
+}
+
+impl error::Error for AliasParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::NameValue(_) | Self::WildcardTarget { .. } => None,
+            Self::VersionReq { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<NameValueParseError> for AliasParseError {
+    fn from(err: NameValueParseError) -> Self {
+        Self::NameValue(err)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum SizeBudgetParseError {
+    NameValue(NameValueParseError),
+    Size { input: String },
+}
+
+impl fmt::Display for SizeBudgetParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NameValue(err) => write!(f, "{}", err),
+            Self::Size { input } => write!(
+                f,
+                "unable to parse size '{}' (expected e.g. 15MB, 1.5GiB or a number of bytes)",
+                input
+            ),
+        }
+    }
+}
+
+impl error::Error for SizeBudgetParseError {}
+
+impl From<NameValueParseError> for SizeBudgetParseError {
+    fn from(err: NameValueParseError) -> Self {
+        Self::NameValue(err)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum MinPlatformParseError {
+    NameValue(NameValueParseError),
+    Platform { input: String },
+}
+
+impl fmt::Display for MinPlatformParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NameValue(err) => write!(f, "{}", err),
+            Self::Platform { input } => write!(
+                f,
+                "unknown platform '{}' (expected glibc, macos or windows)",
+                input
+            ),
+        }
+    }
+}
+
+impl error::Error for MinPlatformParseError {}
+
+impl From<NameValueParseError> for MinPlatformParseError {
+    fn from(err: NameValueParseError) -> Self {
+        Self::NameValue(err)
+    }
+}
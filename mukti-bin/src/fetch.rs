@@ -0,0 +1,353 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! HTTP fetching that is well-behaved towards the hosts being fetched from.
+//!
+//! All requests made through a [`Fetcher`] are rate-limited (to 10 per second by default), honor
+//! `Retry-After` headers, and add jitter to retry backoff. Only failures that might be transient
+//! are retried.
+//!
+//! `robots.txt` is only honored when asked for, with `mirror --respect-robots-txt`: mukti fetches
+//! artifacts on behalf of the project that published them, and hosts often disallow their
+//! download paths to crawlers.
+
+use bytes::Bytes;
+use clap::Args;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use reqwest::{header, Client, Response, StatusCode};
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{sync::OnceCell, time::Instant};
+
+const USER_AGENT: &str = concat!("mukti/", env!("CARGO_PKG_VERSION"));
+
+/// The number of times a fetch is attempted before giving up.
+const FETCH_ATTEMPTS: u32 = 3;
+
+/// The longest `Retry-After` that will be honored before giving up.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+#[derive(Clone, Debug, Args)]
+pub(crate) struct FetchOpts {
+    /// Maximum number of HTTP requests to start per second, across all jobs
+    #[clap(long, value_name = "RATE", default_value = "10")]
+    max_requests_per_second: f64,
+}
+
+#[derive(Clone)]
+pub(crate) struct Fetcher {
+    client: Client,
+    inner: Arc<FetcherInner>,
+}
+
+struct FetcherInner {
+    min_interval: Duration,
+    next_slot: tokio::sync::Mutex<Instant>,
+    respect_robots_txt: bool,
+    // Map of origin (scheme://host:port) to its parsed robots.txt, fetched on first use.
+    robots: Mutex<HashMap<String, Arc<OnceCell<RobotsTxt>>>>,
+    random_state: RandomState,
+    jitter_counter: AtomicU64,
+}
+
+impl Fetcher {
+    /// Create a new fetcher.
+    ///
+    /// If `respect_robots_txt` is true, URLs disallowed by their host's robots.txt aren't fetched.
+    /// This is meant for crawling other hosts, not for fetching a project's own artifacts.
+    pub(crate) fn new(opts: &FetchOpts, respect_robots_txt: bool) -> Result<Self> {
+        let rate = opts.max_requests_per_second;
+        if !(rate > 0.0 && rate.is_finite()) {
+            bail!("--max-requests-per-second must be positive, found {rate}");
+        }
+        let min_interval = Duration::from_secs_f64(1.0 / rate);
+        let client = Client::builder().user_agent(USER_AGENT).build()?;
+
+        Ok(Self {
+            client,
+            inner: Arc::new(FetcherInner {
+                min_interval,
+                next_slot: tokio::sync::Mutex::new(Instant::now()),
+                respect_robots_txt,
+                robots: Default::default(),
+                random_state: RandomState::new(),
+                jitter_counter: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// Fetch the full body at `url`, retrying on failure.
+    pub(crate) async fn fetch_bytes(&self, url: &str) -> Result<Bytes> {
+        if !self.is_allowed_by_robots(url).await? {
+            bail!("fetching {url} is disallowed by robots.txt");
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let (err, retry_after) = match self.get(url).await {
+                Ok(resp) if resp.status().is_success() => match resp.bytes().await {
+                    Ok(bytes) => return Ok(bytes),
+                    Err(err) => (eyre!(err), None),
+                },
+                Ok(resp) => {
+                    let retry_after = retry_after(&resp);
+                    let status = resp.status();
+                    let err = resp
+                        .error_for_status()
+                        .expect_err("status is not a success");
+                    if !is_retryable(status) {
+                        return Err(err.into());
+                    }
+                    (eyre!(err), retry_after)
+                }
+                // Errors building the request, such as an invalid URL, won't go away on retry.
+                Err(err) if err.is_builder() => return Err(err.into()),
+                Err(err) => (eyre!(err), None),
+            };
+
+            if attempt == FETCH_ATTEMPTS {
+                return Err(err);
+            }
+
+            let delay = match retry_after {
+                Some(delay) if delay > MAX_RETRY_AFTER => {
+                    return Err(err.wrap_err(format!(
+                        "server asked to retry after {}s, which is too long",
+                        delay.as_secs()
+                    )));
+                }
+                Some(delay) => delay,
+                // Exponential backoff: 1s, 2s, 4s...
+                None => Duration::from_secs(1 << (attempt - 1)),
+            };
+            let delay = delay + self.jitter(delay / 2);
+            eprintln!(
+                "error fetching {url} (attempt {attempt}/{FETCH_ATTEMPTS}), retrying in {:.1}s: {err}",
+                delay.as_secs_f64()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Perform a single rate-limited GET request.
+    async fn get(&self, url: &str) -> reqwest::Result<Response> {
+        self.wait_for_slot().await;
+        self.client.get(url).send().await
+    }
+
+    async fn wait_for_slot(&self) {
+        let min_interval = self.inner.min_interval;
+        let slot = {
+            let mut next_slot = self.inner.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + min_interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// Returns a random duration between zero and `max`.
+    fn jitter(&self, max: Duration) -> Duration {
+        // RandomState is randomly seeded, so hashing a counter with it is a cheap source of
+        // randomness that's good enough for jitter.
+        let mut hasher = self.inner.random_state.build_hasher();
+        hasher.write_u64(self.inner.jitter_counter.fetch_add(1, Ordering::Relaxed));
+        let random = hasher.finish();
+        max.mul_f64(random as f64 / u64::MAX as f64)
+    }
+
+    async fn is_allowed_by_robots(&self, url: &str) -> Result<bool> {
+        if !self.inner.respect_robots_txt {
+            return Ok(true);
+        }
+        let parsed = reqwest::Url::parse(url).wrap_err_with(|| format!("invalid URL {url}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Ok(true);
+        }
+        let origin = parsed.origin().ascii_serialization();
+
+        // Only hold the lock to look up the cell, so a slow host doesn't hold up fetches from
+        // other hosts. The cell makes sure each robots.txt is only fetched once.
+        let cell = self
+            .inner
+            .robots
+            .lock()
+            .expect("robots lock isn't poisoned")
+            .entry(origin.clone())
+            .or_default()
+            .clone();
+        let robots = cell.get_or_init(|| self.fetch_robots_txt(&origin)).await;
+
+        let mut path = parsed.path().to_owned();
+        if let Some(query) = parsed.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        Ok(robots.is_allowed(&path))
+    }
+
+    async fn fetch_robots_txt(&self, origin: &str) -> RobotsTxt {
+        let url = format!("{origin}/robots.txt");
+        match self.get(&url).await {
+            Ok(resp) if resp.status().is_success() => match resp.text().await {
+                Ok(text) => RobotsTxt::parse(&text, "mukti"),
+                Err(_) => RobotsTxt::default(),
+            },
+            // A missing or unreachable robots.txt means everything is allowed.
+            _ => RobotsTxt::default(),
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(header::RETRY_AFTER)?.to_str().ok()?;
+    // Only the delay-seconds form is supported, not the HTTP-date form.
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// The rules from a robots.txt that apply to a particular user agent.
+#[derive(Debug, Default)]
+struct RobotsTxt {
+    // (allow, pattern)
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsTxt {
+    fn parse(text: &str, user_agent: &str) -> Self {
+        // Rules from the group for our user agent take precedence over those for "*".
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut has_specific = false;
+
+        let mut group_agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        group_agents.clear();
+                        in_rules = false;
+                    }
+                    let agent = value.to_ascii_lowercase();
+                    has_specific |= agent == user_agent;
+                    group_agents.push(agent);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow means everything is allowed.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_owned());
+                    if group_agents.iter().any(|agent| agent == user_agent) {
+                        specific.push(rule.clone());
+                    }
+                    if group_agents.iter().any(|agent| agent == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if has_specific { specific } else { wildcard },
+        }
+    }
+
+    fn is_allowed(&self, path: &str) -> bool {
+        // The longest matching rule wins, with allow rules winning ties.
+        let mut best: Option<(usize, bool)> = None;
+        for (allow, pattern) in &self.rules {
+            if robots_pattern_matches(pattern, path) {
+                let len = pattern.len();
+                match best {
+                    Some((best_len, best_allow))
+                        if best_len > len || (best_len == len && best_allow) => {}
+                    _ => best = Some((len, *allow)),
+                }
+            }
+        }
+        best.is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// Match a robots.txt path pattern, which supports `*` wildcards and a trailing `$` anchor.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_txt() {
+        let robots_txt = RobotsTxt::parse(
+            "# comment\n\
+             User-agent: *\n\
+             Disallow: /private/\n\
+             Allow: /private/public\n\
+             Disallow: /*.tar.gz$\n\
+             \n\
+             User-agent: other-bot\n\
+             Disallow: /\n",
+            "mukti",
+        );
+        assert!(robots_txt.is_allowed("/releases/foo.zip"));
+        assert!(!robots_txt.is_allowed("/private/foo.zip"));
+        assert!(robots_txt.is_allowed("/private/public/foo.zip"));
+        assert!(!robots_txt.is_allowed("/releases/foo.tar.gz"));
+        assert!(robots_txt.is_allowed("/releases/foo.tar.gz.sha256"));
+
+        let robots_txt = RobotsTxt::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: mukti\nDisallow: /private/\n",
+            "mukti",
+        );
+        assert!(
+            robots_txt.is_allowed("/releases/foo.zip"),
+            "group for our user agent overrides *"
+        );
+        assert!(!robots_txt.is_allowed("/private/foo.zip"));
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Canonicalize the releases JSON file.
+
+use crate::release_json::{read_release_json, serialize_releases_json, write_releases_json};
+use camino::Utf8Path;
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::{DigestEncoding, MuktiReleasesJson};
+
+/// Digest encodings that can be selected on the command line.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum DigestEncodingArg {
+    /// Hex-encoded digests
+    Hex,
+
+    /// Base64-encoded digests, which are smaller
+    Base64,
+}
+
+impl From<DigestEncodingArg> for DigestEncoding {
+    fn from(arg: DigestEncodingArg) -> Self {
+        match arg {
+            DigestEncodingArg::Hex => Self::Hex,
+            DigestEncodingArg::Base64 => Self::Base64,
+        }
+    }
+}
+
+pub(crate) fn format_release_json(
+    path: &Utf8Path,
+    check: bool,
+    digest_encoding: Option<DigestEncodingArg>,
+) -> Result<()> {
+    let mut release_json = read_release_json(path, false)?;
+    normalize_release_json(&mut release_json);
+    if let Some(digest_encoding) = digest_encoding {
+        release_json.digest_encoding = digest_encoding.into();
+        if release_json.digest_encoding != DigestEncoding::Hex {
+            // Readers that predate schema version 1 would misinterpret non-hex digests.
+            release_json.schema_version = release_json.schema_version.max(1);
+        }
+    }
+
+    let original = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read releases JSON file at {}", path))?;
+    let canonical = serialize_releases_json(&release_json)?;
+
+    if original == canonical {
+        return Ok(());
+    }
+
+    if check {
+        bail!(
+            "releases JSON at {} is not in canonical form (run `mukti fmt` to fix)",
+            path
+        );
+    }
+
+    write_releases_json(&release_json, path)?;
+    eprintln!("formatted {}", path);
+    Ok(())
+}
+
+/// Normalize parts of the release JSON that have more than one valid representation.
+///
+/// Digests are always hex in memory, whatever their encoding on disk.
+fn normalize_release_json(release_json: &mut MuktiReleasesJson) {
+    for project in release_json.projects.values_mut() {
+        for (_, location) in project.locations_mut() {
+            for digest in location.checksums.values_mut() {
+                digest.0.make_ascii_lowercase();
+            }
+        }
+    }
+}
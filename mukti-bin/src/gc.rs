@@ -0,0 +1,160 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Garbage-collect yanked versions and empty ranges from the release JSON.
+
+use crate::release_json::{compute_project_latest, compute_range_latest, single_project_mut};
+use chrono::{DateTime, FixedOffset};
+use color_eyre::eyre::Result;
+use mukti_metadata::MuktiReleasesJson;
+use semver::Version;
+
+/// Remove yanked versions older than `older_than`, along with ranges that end up empty.
+///
+/// If `older_than` is `None`, the latest version (the latest version in the project's `latest`
+/// range) is used as the threshold, so yanked versions newer than it are retained. If
+/// `released_before` is passed in, only versions with a release date before it are removed.
+///
+/// Versions that channels point to, and the latest version of a pinned `latest` range, are never
+/// removed.
+///
+/// Returns true if anything was removed.
+pub(crate) fn gc_release_json(
+    release_json: &mut MuktiReleasesJson,
+    older_than: Option<&Version>,
+    released_before: Option<DateTime<FixedOffset>>,
+    dry_run: bool,
+) -> Result<bool> {
+    let (_, project) = single_project_mut(release_json)?;
+
+    let threshold = match older_than {
+        Some(version) => version.clone(),
+        None => match project.latest_version_data() {
+            Some((version, _)) => version.clone(),
+            None => return Ok(false),
+        },
+    };
+    let channel_versions: Vec<_> = project.channels.values().cloned().collect();
+    let pinned_version = project
+        .latest
+        .filter(|_| project.latest_pinned)
+        .and_then(|range| project.ranges.get(&range))
+        .map(|range_data| range_data.latest.clone());
+    let verb = if dry_run { "would remove" } else { "removing" };
+
+    let is_pinned = |version: &Version| {
+        if pinned_version.as_ref() != Some(version) {
+            return false;
+        }
+        eprintln!(
+            "warning: not removing yanked version {version}, the latest version of the pinned \
+             latest range (use `set-latest` to point latest elsewhere first)"
+        );
+        true
+    };
+
+    let mut removed_any = false;
+    let mut empty_ranges = Vec::new();
+    for (range, data) in &mut project.ranges {
+        let before = data.versions.len();
+        data.versions.retain(|version, version_data| {
+            let remove = version_data.status.is_yanked()
+                && version.cmp_precedence(&threshold).is_lt()
+                && !channel_versions.contains(version)
+                && released_before.is_none_or(|released_before| {
+                    version_data
+                        .release_date
+                        .as_deref()
+                        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                        .is_some_and(|date| date < released_before)
+                })
+                && !is_pinned(version);
+            if remove {
+                eprintln!("{verb} yanked version {version}");
+            }
+            !remove
+        });
+        if data.versions.len() == before {
+            continue;
+        }
+
+        removed_any = true;
+        if data.versions.is_empty() {
+            eprintln!("{verb} empty range {range}");
+            empty_ranges.push(*range);
+        } else {
+            data.latest = compute_range_latest(data);
+        }
+    }
+
+    for range in &empty_ranges {
+        project.ranges.remove(range);
+    }
+    if !project.latest_pinned {
+        project.latest = compute_project_latest(project);
+    }
+
+    if !removed_any {
+        eprintln!("nothing to remove");
+    }
+
+    Ok(removed_any)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn version(status: &str) -> serde_json::Value {
+        json!({ "release_url": "https://example.com", "status": status, "locations": [] })
+    }
+
+    #[test]
+    fn test_gc_default_threshold() {
+        let mut release_json: MuktiReleasesJson = serde_json::from_value(json!({
+            "projects": {
+                "hello": {
+                    "latest": "1",
+                    "latest_pinned": true,
+                    "ranges": {
+                        "2": {
+                            "latest": "2.0.0-beta.2",
+                            "is_prerelease": true,
+                            "versions": {
+                                "2.0.0-beta.1": version("yanked"),
+                                "2.0.0-beta.2": version("active"),
+                            },
+                        },
+                        "1": {
+                            "latest": "1.0.1",
+                            "is_prerelease": false,
+                            "versions": {
+                                "1.0.0": version("yanked"),
+                                "1.0.1": version("yanked"),
+                            },
+                        },
+                        "0.9": {
+                            "latest": "0.9.0",
+                            "is_prerelease": false,
+                            "versions": { "0.9.0": version("yanked") },
+                        },
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        assert!(gc_release_json(&mut release_json, None, None, false).unwrap());
+        let project = &release_json.projects["hello"];
+        let versions: Vec<_> = project
+            .all_versions()
+            .map(|(version, _)| version.to_string())
+            .collect();
+        // The yanked prerelease is newer than the latest version, and the pinned latest version is
+        // kept even though it's yanked.
+        assert_eq!(versions, ["2.0.0-beta.2", "2.0.0-beta.1", "1.0.1"]);
+        assert_eq!(project.latest.unwrap().to_string(), "1");
+        assert!(project.latest_pinned);
+    }
+}
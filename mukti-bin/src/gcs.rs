@@ -0,0 +1,220 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal Google Cloud Storage client, supporting just enough of the JSON API (multipart
+//! uploads) to upload files.
+//!
+//! Authentication uses a service account key, resolved through [`SecretResolver`] as
+//! `GCS_SERVICE_ACCOUNT_KEY`, falling back to the file pointed to by
+//! `GOOGLE_APPLICATION_CREDENTIALS`. The key is exchanged for an OAuth access token, which is
+//! reused until shortly before it expires.
+
+use crate::{destination::ObjectMetadata, secrets::SecretResolver};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use reqwest::{header, Client};
+use ring::{
+    rand::SystemRandom,
+    signature::{RsaKeyPair, RSA_PKCS1_SHA256},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const GCS_SERVICE_ACCOUNT_KEY: &str = "GCS_SERVICE_ACCOUNT_KEY";
+
+const UPLOAD_BASE: &str = "https://storage.googleapis.com/upload/storage/v1";
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+/// How long requested access tokens are valid for. This is the maximum Google allows.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Refresh access tokens this long before they expire, to allow for clock skew and slow uploads.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// The fields of a service account key file that mukti uses.
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+pub(crate) struct GcsClient {
+    client: Client,
+    bucket: String,
+    client_email: String,
+    token_uri: String,
+    key_pair: RsaKeyPair,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl GcsClient {
+    pub(crate) fn new(bucket: &str, secrets: &SecretResolver) -> Result<Self> {
+        let key_json = match secrets.resolve(GCS_SERVICE_ACCOUNT_KEY)? {
+            Some(key_json) => key_json,
+            None => match std::env::var_os("GOOGLE_APPLICATION_CREDENTIALS") {
+                Some(path) => std::fs::read_to_string(&path).wrap_err_with(|| {
+                    format!(
+                        "failed to read service account key from {}",
+                        path.to_string_lossy()
+                    )
+                })?,
+                None => bail!(
+                    "no GCS credentials found (set {GCS_SERVICE_ACCOUNT_KEY} through any \
+                     secret provider, or GOOGLE_APPLICATION_CREDENTIALS to a key file)"
+                ),
+            },
+        };
+        let key: ServiceAccountKey =
+            serde_json::from_str(&key_json).wrap_err("failed to parse service account key")?;
+        let key_pair = RsaKeyPair::from_pkcs8(&pem_to_der(&key.private_key)?)
+            .map_err(|err| eyre!("invalid service account private key: {err}"))?;
+
+        Ok(Self {
+            client: Client::builder()
+                .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            bucket: bucket.to_owned(),
+            client_email: key.client_email,
+            token_uri: key
+                .token_uri
+                .unwrap_or_else(|| DEFAULT_TOKEN_URI.to_owned()),
+            key_pair,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Upload an object, overwriting any existing object at `key`.
+    pub(crate) async fn put_object(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        metadata: &ObjectMetadata<'_>,
+    ) -> Result<()> {
+        let token = self.access_token().await?;
+        let content_type = metadata.content_type.unwrap_or("application/octet-stream");
+
+        let mut object = json!({ "name": key, "contentType": content_type });
+        if let Some(cache_control) = metadata.cache_control {
+            object["cacheControl"] = cache_control.into();
+        }
+
+        // A multipart upload is the only single-request upload that can set object metadata.
+        let boundary = "mukti-upload-boundary";
+        let mut request_body = format!(
+            "--{boundary}\r\n\
+             Content-Type: application/json; charset=UTF-8\r\n\r\n\
+             {object}\r\n\
+             --{boundary}\r\n\
+             Content-Type: {content_type}\r\n\r\n"
+        )
+        .into_bytes();
+        request_body.extend_from_slice(&body);
+        request_body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        self.client
+            .post(format!(
+                "{UPLOAD_BASE}/b/{}/o?uploadType=multipart",
+                self.bucket
+            ))
+            .bearer_auth(token)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/related; boundary={boundary}"),
+            )
+            .body(request_body)
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err_with(|| format!("failed to upload gs://{}/{key}", self.bucket))?;
+        Ok(())
+    }
+
+    /// Return a cached access token, or request a new one if it's missing or about to expire.
+    async fn access_token(&self) -> Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, expires_at)) = &*cached {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let requested_at = Instant::now();
+        let assertion = self.sign_assertion()?;
+        let form = format!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer&assertion={assertion}"
+        );
+        let response: TokenResponse = self
+            .client
+            .post(&self.token_uri)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(form)
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err("failed to get GCS access token")?
+            .json()
+            .await
+            .wrap_err_with(|| format!("failed to parse token response from {}", self.token_uri))?;
+
+        *cached = Some((response.access_token.clone(), requested_at + TOKEN_LIFETIME));
+        Ok(response.access_token)
+    }
+
+    /// Create a signed JWT asserting the service account's identity, as described in
+    /// <https://developers.google.com/identity/protocols/oauth2/service-account#authorizingrequests>.
+    fn sign_assertion(&self) -> Result<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .wrap_err("system clock is before the Unix epoch")?
+            .as_secs();
+        let header = json!({ "alg": "RS256", "typ": "JWT" });
+        let claims = json!({
+            "iss": self.client_email,
+            "scope": SCOPE,
+            "aud": self.token_uri,
+            "iat": now,
+            "exp": now + TOKEN_LIFETIME.as_secs(),
+        });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+
+        let mut signature = vec![0; self.key_pair.public().modulus_len()];
+        self.key_pair
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                signing_input.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|_| eyre!("failed to sign GCS access token request"))?;
+        Ok(format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature)
+        ))
+    }
+}
+
+/// Decode a PEM-encoded PKCS#8 private key, as found in service account key files.
+fn pem_to_der(pem: &str) -> Result<Vec<u8>> {
+    let body: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .wrap_err("failed to decode service account private key")
+}
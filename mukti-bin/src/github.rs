@@ -0,0 +1,114 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal client for the parts of the GitHub REST API that mukti uses.
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use reqwest::{header, Client, StatusCode};
+use serde::Deserialize;
+
+const API_BASE: &str = "https://api.github.com";
+
+pub(crate) struct GitHubClient {
+    client: Client,
+    repo: String,
+}
+
+impl GitHubClient {
+    /// Create a new client for `repo`, in the form `owner/name`.
+    pub(crate) fn new(repo: &str, token: &str) -> Result<Self> {
+        let valid = matches!(
+            repo.split_once('/'),
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        );
+        if !valid {
+            bail!("invalid repository `{repo}`: expected the form OWNER/NAME");
+        }
+
+        let mut headers = header::HeaderMap::new();
+        let mut auth = header::HeaderValue::from_str(&format!("Bearer {token}"))
+            .wrap_err("GitHub token contains invalid characters")?;
+        auth.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, auth);
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/vnd.github+json"),
+        );
+
+        let client = Client::builder()
+            .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .build()?;
+
+        Ok(Self {
+            client,
+            repo: repo.to_owned(),
+        })
+    }
+
+    /// Look up a release by its tag.
+    pub(crate) async fn release_by_tag(&self, tag: &str) -> Result<GitHubRelease> {
+        let url = format!("{API_BASE}/repos/{}/releases/tags/{tag}", self.repo);
+        let resp = self.client.get(&url).send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            bail!("no release with tag `{tag}` found in {}", self.repo);
+        }
+        let release = resp
+            .error_for_status()?
+            .json()
+            .await
+            .wrap_err_with(|| format!("failed to parse GitHub response from {url}"))?;
+        Ok(release)
+    }
+
+    /// Upload an asset to a release, replacing any existing asset with the same name.
+    pub(crate) async fn upload_asset(
+        &self,
+        release: &GitHubRelease,
+        name: &str,
+        content_type: &str,
+        contents: Vec<u8>,
+    ) -> Result<()> {
+        if let Some(existing) = release.assets.iter().find(|asset| asset.name == name) {
+            let url = format!(
+                "{API_BASE}/repos/{}/releases/assets/{}",
+                self.repo, existing.id
+            );
+            self.client
+                .delete(&url)
+                .send()
+                .await?
+                .error_for_status()
+                .wrap_err_with(|| format!("failed to delete existing asset {name}"))?;
+        }
+
+        // upload_url is a URI template like ".../assets{?name,label}".
+        let upload_url = match release.upload_url.split_once('{') {
+            Some((base, _)) => base,
+            None => &release.upload_url,
+        };
+        self.client
+            .post(upload_url)
+            .query(&[("name", name)])
+            .header(header::CONTENT_TYPE, content_type)
+            .body(contents)
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err_with(|| format!("failed to upload asset {name}"))?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GitHubRelease {
+    pub(crate) upload_url: String,
+    pub(crate) assets: Vec<GitHubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GitHubAsset {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+}
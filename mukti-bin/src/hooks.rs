@@ -0,0 +1,131 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Run commands after generated outputs are written, e.g. to sync them to a web server.
+//!
+//! Hooks only run if at least one output changed. Shell commands are passed the changed files,
+//! relative to the output directory, as arguments, and run with the output directory as their
+//! working directory. The list is also available as the newline-separated
+//! `MUKTI_CHANGED_FILES` environment variable, along with `MUKTI_OUT_DIR`.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Args;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use std::{
+    io::Write as _,
+    process::{Command, Stdio},
+};
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct HookOpts {
+    /// Shell command to run after outputs are written, with changed files as arguments (can be
+    /// repeated)
+    #[clap(long = "post-write-hook", value_name = "CMD")]
+    commands: Vec<String>,
+
+    /// Copy changed files to an rsync destination, e.g. host:/srv/www, after they're written
+    #[clap(long, value_name = "DEST")]
+    rsync_to: Option<String>,
+
+    /// Run `aws s3 sync` to an S3 URL after outputs are written
+    #[clap(long, value_name = "S3_URL")]
+    aws_s3_sync_to: Option<String>,
+}
+
+impl HookOpts {
+    /// Run all configured hooks in order, stopping at the first one that fails.
+    ///
+    /// `changed` is the list of files that changed, relative to `out_dir`.
+    pub(crate) fn run(&self, out_dir: &Utf8Path, changed: &[Utf8PathBuf]) -> Result<()> {
+        let has_hooks =
+            !self.commands.is_empty() || self.rsync_to.is_some() || self.aws_s3_sync_to.is_some();
+        if !has_hooks {
+            return Ok(());
+        }
+        if changed.is_empty() {
+            eprintln!("no outputs changed, skipping post-write hooks");
+            return Ok(());
+        }
+
+        let changed_list: String = changed.iter().map(|path| format!("{path}\n")).collect();
+
+        for cmd in &self.commands {
+            let mut command = shell_command(cmd);
+            command.args(changed.iter().map(|path| path.as_str()));
+            run_hook(cmd, command, out_dir, &changed_list, None)?;
+        }
+
+        if let Some(dest) = &self.rsync_to {
+            // Only transfer changed files, read from stdin relative to the output directory.
+            let mut command = Command::new("rsync");
+            command.args(["--archive", "--files-from=-", "."]).arg(dest);
+            let description = format!("rsync to {dest}");
+            run_hook(
+                &description,
+                command,
+                out_dir,
+                &changed_list,
+                Some(&changed_list),
+            )?;
+        }
+
+        if let Some(dest) = &self.aws_s3_sync_to {
+            let mut command = Command::new("aws");
+            command.args(["s3", "sync", "."]).arg(dest);
+            let description = format!("aws s3 sync to {dest}");
+            run_hook(&description, command, out_dir, &changed_list, None)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn shell_command(cmd: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    } else {
+        // Arguments appended to this command are available to the script as "$@".
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd).arg("sh");
+        command
+    }
+}
+
+fn run_hook(
+    description: &str,
+    mut command: Command,
+    out_dir: &Utf8Path,
+    changed_list: &str,
+    stdin: Option<&str>,
+) -> Result<()> {
+    eprintln!("running post-write hook: {description}");
+    let out_dir = std::path::absolute(out_dir)
+        .wrap_err_with(|| format!("failed to resolve output directory {out_dir}"))?;
+    command
+        .current_dir(&out_dir)
+        .env("MUKTI_OUT_DIR", &out_dir)
+        .env("MUKTI_CHANGED_FILES", changed_list)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+
+    let mut child = command
+        .spawn()
+        .wrap_err_with(|| format!("failed to run post-write hook `{description}`"))?;
+    if let (Some(input), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
+        child_stdin
+            .write_all(input.as_bytes())
+            .wrap_err_with(|| format!("failed to write to post-write hook `{description}`"))?;
+    }
+    let status = child
+        .wait()
+        .wrap_err_with(|| format!("failed to wait on post-write hook `{description}`"))?;
+    if !status.success() {
+        bail!("post-write hook `{description}` failed with {status}");
+    }
+    Ok(())
+}
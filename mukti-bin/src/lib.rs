@@ -1,10 +1,64 @@
 // Copyright (c) The mukti Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+mod aliases;
+mod annotate;
+mod api;
+mod appcast;
+mod apt;
+mod asdf;
+mod azure;
+mod bench;
+mod budget;
+mod channels;
 mod checksums;
 mod command;
+mod completions;
+mod deploy;
+mod destination;
+mod diff;
+mod download;
+mod eol;
 mod errors;
+mod fetch;
+mod fmt;
+mod gc;
+mod gcs;
+mod github;
+mod hooks;
+mod link_latest;
+mod lint;
+mod manifest;
+mod markdown;
+mod migrate;
+mod mirror;
+mod netlify;
+mod nix;
+mod notify;
+mod output;
+mod packages;
+mod project_info;
+mod provenance;
+mod publish;
+mod purge;
 mod redirects;
 mod release_json;
+mod report;
+mod s3;
+mod scheduler;
+mod schema;
+mod secrets;
+mod self_update;
+mod shard;
+mod site;
+mod staging;
+mod targets;
+mod template;
+mod url_template;
+mod yank;
+mod yum;
 
+#[cfg(feature = "count-allocations")]
+pub use bench::CountingAllocator;
 pub use command::MuktiApp;
+pub use errors::ErrorReported;
@@ -0,0 +1,181 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Give the latest artifacts stable names, for hosts where redirects aren't available.
+
+use crate::{publish::file_name_from_url, release_json::single_project};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::{friendly_aliases, project_aliases, MuktiReleasesJson, PathAlias};
+use std::{collections::BTreeMap, io};
+
+/// How stable-named files refer to the original artifacts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum LinkMode {
+    /// Hard links, falling back to copies if the directories are on different filesystems
+    #[default]
+    Hardlink,
+
+    /// Symbolic links
+    Symlink,
+
+    /// Full copies
+    Copy,
+}
+
+/// Create or update `{project}-latest-{name}` files in `out_dir` for the latest version's
+/// artifacts, where names are `{target}.{format}` and any aliases.
+///
+/// Artifacts are looked for in `artifact_dir` as `{project}/{version}/{file name}` (the layout
+/// `mirror` writes), then `{version}/{file name}` and `{file name}`. Stale `{project}-latest-*` files are removed.
+pub(crate) fn link_latest(
+    release_json: &MuktiReleasesJson,
+    artifact_dir: &Utf8Path,
+    out_dir: &Utf8Path,
+    aliases: &[PathAlias],
+    auto_aliases: bool,
+    mode: LinkMode,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+    let aliases = project_aliases(aliases, project_name, project);
+    let Some(range) = &project.latest else {
+        bail!("no latest version in release JSON");
+    };
+    let range_data = &project.ranges[range];
+    let version = &range_data.latest;
+    let version_data = &range_data.versions[version];
+
+    // Map of stable name to the artifact it refers to.
+    let mut links = BTreeMap::new();
+    let mut add_link = |name: &str, url: &str, format: &str| -> Result<()> {
+        let Some(file_name) = file_name_from_url(url) else {
+            eprintln!("skipping {name}: no file name in URL {url}");
+            return Ok(());
+        };
+        let source = [
+            artifact_dir
+                .join(project_name)
+                .join(version.to_string())
+                .join(file_name),
+            artifact_dir.join(version.to_string()).join(file_name),
+            artifact_dir.join(file_name),
+        ]
+        .into_iter()
+        .find(|path| path.is_file());
+        let Some(source) = source else {
+            bail!("artifact {file_name} for {version} not found in {artifact_dir}");
+        };
+
+        // Aliases don't always include the format, but stable names should.
+        let extension = format.trim_start_matches('.');
+        let name = if name.ends_with(&format!(".{extension}")) {
+            name.to_owned()
+        } else {
+            format!("{name}.{extension}")
+        };
+        links.insert(format!("{project_name}-latest-{name}"), source);
+        Ok(())
+    };
+
+    for location in version_data.redirect_locations() {
+        add_link(&location.target, &location.url, location.format.as_str())?;
+    }
+    for alias in aliases
+        .iter()
+        .filter(|alias| alias.applies_to_version(version))
+    {
+        for location in version_data
+            .redirect_locations()
+            .filter(|location| alias.matches_location(location))
+        {
+            add_link(
+                &alias.name_for(location),
+                &location.url,
+                location.format.as_str(),
+            )?;
+        }
+    }
+    if auto_aliases {
+        for (alias, location) in friendly_aliases(version_data) {
+            if !aliases
+                .iter()
+                .any(|explicit| explicit.is_named(&alias, version_data))
+            {
+                add_link(&alias, &location.url, location.format.as_str())?;
+            }
+        }
+    }
+
+    std::fs::create_dir_all(out_dir)
+        .wrap_err_with(|| format!("failed to create directory {out_dir}"))?;
+    remove_stale(out_dir, &format!("{project_name}-latest-"), &links)?;
+    for (name, source) in &links {
+        create_link(source, &out_dir.join(name), mode)?;
+    }
+
+    eprintln!(
+        "linked {} files for {project_name} {version} into {out_dir}",
+        links.len()
+    );
+    Ok(())
+}
+
+fn remove_stale(
+    out_dir: &Utf8Path,
+    prefix: &str,
+    links: &BTreeMap<String, Utf8PathBuf>,
+) -> Result<()> {
+    let entries = out_dir
+        .read_dir_utf8()
+        .wrap_err_with(|| format!("failed to read directory {out_dir}"))?;
+    for entry in entries {
+        let entry = entry.wrap_err_with(|| format!("failed to read directory {out_dir}"))?;
+        let name = entry.file_name();
+        if name.starts_with(prefix) && !links.contains_key(name) {
+            eprintln!("removing stale {name}");
+            std::fs::remove_file(entry.path())
+                .wrap_err_with(|| format!("failed to remove {}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create `dest` referring to `source`, replacing whatever is at `dest` atomically.
+fn create_link(source: &Utf8Path, dest: &Utf8Path, mode: LinkMode) -> Result<()> {
+    let tmp = dest.with_file_name(format!(
+        ".{}.tmp",
+        dest.file_name().expect("dest has a file name")
+    ));
+    // Clean up after any earlier interrupted run.
+    match std::fs::remove_file(&tmp) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err).wrap_err_with(|| format!("failed to remove {tmp}")),
+    }
+
+    let result = match mode {
+        LinkMode::Hardlink => std::fs::hard_link(source, &tmp).or_else(|err| {
+            if err.kind() == io::ErrorKind::CrossesDevices {
+                std::fs::copy(source, &tmp).map(|_| ())
+            } else {
+                Err(err)
+            }
+        }),
+        LinkMode::Symlink => symlink(&std::path::absolute(source)?, &tmp),
+        LinkMode::Copy => std::fs::copy(source, &tmp).map(|_| ()),
+    };
+    result.wrap_err_with(|| format!("failed to link {source} to {dest}"))?;
+
+    std::fs::rename(&tmp, dest).wrap_err_with(|| format!("failed to rename {tmp} to {dest}"))
+}
+
+#[cfg(unix)]
+fn symlink(source: &std::path::Path, dest: &Utf8Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, dest)
+}
+
+#[cfg(windows)]
+fn symlink(source: &std::path::Path, dest: &Utf8Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(source, dest)
+}
@@ -0,0 +1,313 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Check the release JSON for metadata that is valid, but probably a mistake.
+
+use crate::{eol::parse_eol_date, publish::file_name_from_url, targets::TargetOpts};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{Digest, MuktiReleasesJson};
+use std::{collections::BTreeSet, fmt};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum LintCode {
+    /// The file name in a URL doesn't contain the version.
+    UrlMissingVersion,
+
+    /// The file name in a URL doesn't contain the target.
+    UrlMissingTarget,
+
+    /// A URL doesn't end with a file name.
+    UrlMissingFileName,
+
+    /// A version lacks a target that other versions in its range have.
+    MissingTarget,
+
+    /// Some URLs use http and others use https.
+    MixedScheme,
+
+    /// An active release has a location without checksums.
+    EmptyChecksums,
+
+    /// Some formats start with a dot and others don't.
+    InconsistentFormatDot,
+
+    /// A release date isn't in RFC 3339 format.
+    InvalidReleaseDate,
+
+    /// A signature has neither a URL nor an inline value.
+    EmptySignature,
+
+    /// A version range's end-of-life date isn't in YYYY-MM-DD format.
+    InvalidEolDate,
+
+    /// The latest version range is past its end-of-life date.
+    EolLatest,
+
+    /// A target isn't a known Rust target triple.
+    UnknownTarget,
+
+    /// A checksum isn't hex, or has the wrong length for its algorithm.
+    InvalidChecksum,
+
+    /// A URL is relative, but the project has no base URL to resolve it against.
+    RelativeUrl,
+
+    /// A location has no URL, and the project has no URL template to generate one from.
+    MissingUrl,
+}
+
+impl fmt::Display for LintCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            Self::UrlMissingVersion => "url-missing-version",
+            Self::UrlMissingTarget => "url-missing-target",
+            Self::UrlMissingFileName => "url-missing-file-name",
+            Self::MissingTarget => "missing-target",
+            Self::MixedScheme => "mixed-scheme",
+            Self::EmptyChecksums => "empty-checksums",
+            Self::InconsistentFormatDot => "inconsistent-format-dot",
+            Self::InvalidReleaseDate => "invalid-release-date",
+            Self::EmptySignature => "empty-signature",
+            Self::InvalidEolDate => "invalid-eol-date",
+            Self::EolLatest => "eol-latest",
+            Self::UnknownTarget => "unknown-target",
+            Self::InvalidChecksum => "invalid-checksum",
+            Self::RelativeUrl => "relative-url",
+            Self::MissingUrl => "missing-url",
+        };
+        f.write_str(code)
+    }
+}
+
+struct Lint {
+    code: LintCode,
+    message: String,
+}
+
+/// Print lints for the release JSON, failing if there are any and `deny` is set.
+pub(crate) fn lint_release_json(
+    release_json: &MuktiReleasesJson,
+    deny: bool,
+    target_opts: &TargetOpts,
+) -> Result<()> {
+    let lints = collect_lints(release_json, target_opts);
+    let level = if deny { "error" } else { "warning" };
+    for lint in &lints {
+        eprintln!("{level}[{}]: {}", lint.code, lint.message);
+    }
+
+    match (lints.len(), deny) {
+        (0, _) => {
+            eprintln!("no lints found");
+            Ok(())
+        }
+        (count, true) => bail!("{count} lints found"),
+        (count, false) => {
+            eprintln!("{count} lints found (pass --deny to fail on them)");
+            Ok(())
+        }
+    }
+}
+
+fn collect_lints(release_json: &MuktiReleasesJson, target_opts: &TargetOpts) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut schemes = BTreeSet::new();
+    let mut dotted_formats = BTreeSet::new();
+    let mut undotted_formats = BTreeSet::new();
+
+    let today = Utc::now().date_naive();
+    for (project_name, project) in &release_json.projects {
+        for (range, range_data) in &project.ranges {
+            match parse_eol_date(range_data) {
+                Some(Err(err)) => lints.push(Lint {
+                    code: LintCode::InvalidEolDate,
+                    message: format!(
+                        "{project_name} range {range}: end-of-life date `{}` isn't in YYYY-MM-DD \
+                         format: {err}",
+                        range_data.eol_date.as_deref().unwrap_or_default()
+                    ),
+                }),
+                Some(Ok(date)) if date <= today && project.latest == Some(*range) => {
+                    lints.push(Lint {
+                        code: LintCode::EolLatest,
+                        message: format!(
+                            "{project_name} range {range} reached end of life on {date}, but is \
+                             still the latest range"
+                        ),
+                    })
+                }
+                _ => {}
+            }
+
+            let range_targets: BTreeSet<_> = range_data
+                .versions
+                .values()
+                .flat_map(|version_data| &version_data.locations)
+                .map(|location| location.target.as_str())
+                .collect();
+            for target in &range_targets {
+                if let Some(message) = target_opts.unknown_target_message(target) {
+                    lints.push(Lint {
+                        code: LintCode::UnknownTarget,
+                        message: format!(
+                            "{project_name} range {range}: {message} (pass in --custom-target to \
+                             allow it)"
+                        ),
+                    });
+                }
+            }
+
+            for (version, version_data) in &range_data.versions {
+                let version_targets: BTreeSet<_> = version_data
+                    .locations
+                    .iter()
+                    .map(|location| location.target.as_str())
+                    .collect();
+                for target in range_targets.difference(&version_targets) {
+                    lints.push(Lint {
+                        code: LintCode::MissingTarget,
+                        message: format!(
+                            "{project_name} {version} has no artifact for {target}, which other \
+                             versions in range {range} have"
+                        ),
+                    });
+                }
+
+                if let Some(date) = &version_data.release_date {
+                    if let Err(err) = DateTime::parse_from_rfc3339(date) {
+                        lints.push(Lint {
+                            code: LintCode::InvalidReleaseDate,
+                            message: format!(
+                                "{project_name} {version}: release date `{date}` isn't in RFC \
+                                 3339 format: {err}"
+                            ),
+                        });
+                    }
+                }
+
+                let version_str = version.to_string();
+                for location in &version_data.locations {
+                    let what = format!(
+                        "{project_name} {version} {}:{}",
+                        location.target, location.format
+                    );
+
+                    match location.url.split_once("://") {
+                        Some((scheme, _)) => {
+                            schemes.insert(scheme.to_ascii_lowercase());
+                        }
+                        // URLs are only omitted on disk if they match the URL template, which is
+                        // expanded when the file is read.
+                        None if location.url.is_empty() => lints.push(Lint {
+                            code: LintCode::MissingUrl,
+                            message: format!(
+                                "{what}: no URL, and {project_name} has no url_template"
+                            ),
+                        }),
+                        // URLs are resolved against the base URL when the file is read, so any
+                        // that are still relative have nothing to resolve against.
+                        None if project.base_url.is_none() => lints.push(Lint {
+                            code: LintCode::RelativeUrl,
+                            message: format!(
+                                "{what}: URL `{}` is relative, but {project_name} has no base_url",
+                                location.url
+                            ),
+                        }),
+                        None => {}
+                    }
+
+                    match file_name_from_url(&location.url) {
+                        Some(file_name) => {
+                            if !file_name.contains(&version_str) {
+                                lints.push(Lint {
+                                    code: LintCode::UrlMissingVersion,
+                                    message: format!(
+                                        "{what}: file name `{file_name}` doesn't contain the \
+                                         version"
+                                    ),
+                                });
+                            }
+                            if !location.is_target_independent()
+                                && !file_name.contains(&location.target)
+                            {
+                                lints.push(Lint {
+                                    code: LintCode::UrlMissingTarget,
+                                    message: format!(
+                                        "{what}: file name `{file_name}` doesn't contain the \
+                                         target"
+                                    ),
+                                });
+                            }
+                        }
+                        None if !location.url.is_empty() => lints.push(Lint {
+                            code: LintCode::UrlMissingFileName,
+                            message: format!("{what}: URL `{}` has no file name", location.url),
+                        }),
+                        None => {}
+                    }
+
+                    if version_data.status.is_available() && location.checksums.is_empty() {
+                        lints.push(Lint {
+                            code: LintCode::EmptyChecksums,
+                            message: format!(
+                                "{what}: no checksums recorded (run `mukti backfill-checksums`)"
+                            ),
+                        });
+                    }
+
+                    for (algorithm, digest) in &location.checksums {
+                        if let Err(err) = Digest::parse(algorithm, &digest.0) {
+                            lints.push(Lint {
+                                code: LintCode::InvalidChecksum,
+                                message: format!("{what}: {err}"),
+                            });
+                        }
+                    }
+
+                    for signature in &location.signatures {
+                        if signature.url.is_none() && signature.value.is_none() {
+                            lints.push(Lint {
+                                code: LintCode::EmptySignature,
+                                message: format!(
+                                    "{what}: {} signature has neither a URL nor a value",
+                                    signature.scheme
+                                ),
+                            });
+                        }
+                    }
+
+                    if location.format.as_str().starts_with('.') {
+                        dotted_formats.insert(location.format.as_str());
+                    } else {
+                        undotted_formats.insert(location.format.as_str());
+                    }
+                }
+            }
+        }
+    }
+
+    if schemes.contains("http") && schemes.contains("https") {
+        lints.push(Lint {
+            code: LintCode::MixedScheme,
+            message: "some URLs use http and others use https".to_owned(),
+        });
+    }
+    if !dotted_formats.is_empty() && !undotted_formats.is_empty() {
+        lints.push(Lint {
+            code: LintCode::InconsistentFormatDot,
+            message: format!(
+                "formats {} start with a dot, but formats {} don't",
+                join(&dotted_formats),
+                join(&undotted_formats),
+            ),
+        });
+    }
+
+    lints.sort_by_key(|lint| lint.code);
+    lints
+}
+
+fn join(formats: &BTreeSet<&str>) -> String {
+    formats.iter().copied().collect::<Vec<_>>().join(", ")
+}
@@ -3,13 +3,28 @@
 
 //! A simple script to update a releases.json file, and optionally a netlify.toml.
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
 use color_eyre::Result;
-use mukti_bin::MuktiApp;
+use mukti_bin::{ErrorReported, MuktiApp};
+use std::process::ExitCode;
+
+// Only used to let `bench-parse` report allocations: the system allocator is used otherwise.
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: mukti_bin::CountingAllocator = mukti_bin::CountingAllocator;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
+    // Handle shell completion requests, if any.
+    CompleteEnv::with_factory(MuktiApp::command).complete();
+
     color_eyre::install()?;
     let app = MuktiApp::parse();
-    app.exec().await
+    match app.exec().await {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        // The error was already printed as JSON.
+        Err(err) if err.is::<ErrorReported>() => Ok(ExitCode::FAILURE),
+        Err(err) => Err(err),
+    }
 }
@@ -0,0 +1,81 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Read the list of archives for `add-release` from a manifest file.
+//!
+//! A manifest is a JSON or TOML file (determined by its extension) of the form:
+//!
+//! ```toml
+//! [[archives]]
+//! target = "x86_64-unknown-linux-gnu"
+//! format = "tar.gz"
+//! name = "mukti-bin-0.7.0-x86_64-unknown-linux-gnu.tar.gz"
+//! # Optional. If both sha256 and blake2b are specified, the archive isn't downloaded.
+//! checksums = { sha256 = "..." }
+//! ```
+
+use crate::command::{Archive, TargetFormat};
+use camino::Utf8Path;
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{ArtifactKind, Digest, DigestAlgorithm};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    archives: Vec<ManifestArchive>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestArchive {
+    target: String,
+    format: String,
+    name: String,
+    #[serde(default)]
+    kind: ArtifactKind,
+    #[serde(default)]
+    checksums: BTreeMap<DigestAlgorithm, Digest>,
+}
+
+pub(crate) fn read_manifest(path: &Utf8Path) -> Result<Vec<Archive>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read manifest at {path}"))?;
+    let manifest: Manifest = if path.extension() == Some("toml") {
+        toml::from_str(&contents).wrap_err_with(|| format!("failed to parse manifest at {path}"))?
+    } else {
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse manifest at {path}"))?
+    };
+
+    manifest
+        .archives
+        .into_iter()
+        .map(|archive| {
+            // Reject malformed checksums now, rather than after fetching everything else.
+            let expected_checksums = archive
+                .checksums
+                .into_iter()
+                .map(|(algorithm, digest)| {
+                    let digest = Digest::parse(&algorithm, &digest.0).wrap_err_with(|| {
+                        format!(
+                            "invalid checksum for {} in manifest at {path}",
+                            archive.name
+                        )
+                    })?;
+                    Ok((algorithm, digest))
+                })
+                .collect::<Result<_>>()?;
+            Ok(Archive {
+                target_format: TargetFormat {
+                    target: archive.target,
+                    format: archive.format,
+                },
+                name: archive.name,
+                kind: archive.kind,
+                expected_checksums,
+            })
+        })
+        .collect()
+}
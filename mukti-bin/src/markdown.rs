@@ -0,0 +1,52 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Print a Markdown table of downloads for a version, for release notes and docs.
+//!
+//! The version's release notes come before the table, and a link to its changelog entry after it.
+
+use crate::{download::VersionSpec, publish::file_name_from_url, release_json::single_project};
+use color_eyre::eyre::{eyre, Result};
+use mukti_metadata::{DigestAlgorithm, MuktiReleasesJson};
+
+pub(crate) fn generate_markdown(
+    release_json: &MuktiReleasesJson,
+    version: &VersionSpec,
+) -> Result<()> {
+    let (_, project) = single_project(release_json)?;
+    let (version, version_data) = version
+        .resolve(project)
+        .ok_or_else(|| eyre!("no version matching {version} found"))?;
+
+    eprintln!("downloads for {version}:");
+    if let Some(notes) = &version_data.notes {
+        println!("{notes}\n");
+    }
+    println!("| Platform | Download | SHA-256 |");
+    println!("| --- | --- | --- |");
+    for location in &version_data.locations {
+        let name = file_name_from_url(&location.url).unwrap_or(&location.url);
+        let sha256 = match location.checksums.get(&DigestAlgorithm::Sha256) {
+            Some(digest) => format!("`{}`", digest.0),
+            None => "-".to_owned(),
+        };
+        println!(
+            "| {} | [{}]({}) | {sha256} |",
+            cell(&format!("{} ({})", location.target, location.format)),
+            cell(name),
+            location.url,
+        );
+    }
+    if let Some(changelog_url) = &version_data.changelog_url {
+        println!("\nSee the [changelog]({changelog_url}) for details.");
+    }
+    Ok(())
+}
+
+/// Escape characters that would end a table cell or a link label.
+fn cell(input: &str) -> String {
+    input
+        .replace('|', "\\|")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
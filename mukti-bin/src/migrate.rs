@@ -0,0 +1,89 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Upgrade the release JSON to the current schema version.
+
+use crate::release_json::write_releases_json;
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use mukti_metadata::{detect_layout, Layout, MuktiReleasesJson, CURRENT_SCHEMA_VERSION};
+use serde_json::{Map, Value};
+
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// Migrations from each schema version to the next, indexed by the version they migrate from.
+///
+/// Migrations operate on the raw JSON, so that fields can be renamed or restructured before the
+/// file is parsed with the current schema.
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [
+    // 0 -> 1: no structural changes. Version 1 marks files that may use `digest_encoding`.
+    |_| Ok(()),
+    // 1 -> 2: no structural changes. Version 2 marks files that may have relative URLs.
+    |_| Ok(()),
+];
+
+pub(crate) fn migrate_release_json(path: &Utf8Path, check: bool) -> Result<()> {
+    let json = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read releases JSON file at {}", path))?;
+    if detect_layout(&json).ok() == Some(Layout::Sharded) {
+        bail!(
+            "{} is the index of a sharded releases JSON (migrate the original file and run \
+             `mukti shard` again)",
+            path
+        );
+    }
+
+    let mut value: Map<String, Value> = serde_json::from_str(&json)
+        .wrap_err_with(|| format!("failed to deserialize releases JSON at {}", path))?;
+    let from_version = match value.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| eyre!("invalid schema_version in {}: {}", path, version))?,
+    };
+
+    if from_version > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "{} has schema version {}, which is newer than this version of mukti supports \
+             (up to {})",
+            path,
+            from_version,
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+    if from_version == CURRENT_SCHEMA_VERSION {
+        eprintln!("{} is already at schema version {}", path, from_version);
+        return Ok(());
+    }
+    if check {
+        bail!(
+            "{} has schema version {}, but the current version is {} (run `mukti migrate` to \
+             upgrade it)",
+            path,
+            from_version,
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from_version as usize) {
+        migration(&mut value).wrap_err_with(|| {
+            format!(
+                "failed to migrate from schema version {} to {}",
+                version,
+                version + 1
+            )
+        })?;
+    }
+    value.insert("schema_version".to_owned(), CURRENT_SCHEMA_VERSION.into());
+
+    let release_json: MuktiReleasesJson = serde_json::from_value(Value::Object(value))
+        .wrap_err("migrated releases JSON doesn't match the current schema")?;
+    write_releases_json(&release_json, path)?;
+
+    eprintln!(
+        "migrated {} from schema version {} to {}",
+        path, from_version, CURRENT_SCHEMA_VERSION
+    );
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Copy all artifacts in the release JSON to another location.
+
+use crate::{
+    checksums::verify_checksums,
+    destination::{Destination, ObjectMetadata},
+    fetch::Fetcher,
+    publish::file_name_from_url,
+    scheduler::Scheduler,
+    secrets::SecretResolver,
+};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre, Result};
+use futures_util::StreamExt;
+use mukti_metadata::MuktiReleasesJson;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// What to do with the release JSON after mirroring artifacts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum MirrorUrlMode {
+    /// Leave the release JSON unchanged
+    #[default]
+    None,
+
+    /// Replace each location's URL with its mirrored URL
+    Rewrite,
+
+    /// Add mirrored URLs to each location's list of mirror URLs
+    Append,
+}
+
+/// Mirror all artifacts to `dest`, under `{project}/{version}/{file name}`.
+///
+/// Returns true if the release JSON was changed.
+pub(crate) async fn mirror(
+    release_json: &mut MuktiReleasesJson,
+    dest: &Destination,
+    public_url: Option<&str>,
+    url_mode: MirrorUrlMode,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+    secrets: &SecretResolver,
+) -> Result<bool> {
+    let public_url = match (url_mode, public_url) {
+        (MirrorUrlMode::None, _) => None,
+        (_, Some(public_url)) => Some(public_url.trim_end_matches('/')),
+        (_, None) => bail!("--public-url is required to update URLs in the release JSON"),
+    };
+
+    // Map of source URL to (key, location) for every artifact.
+    let mut artifacts = BTreeMap::new();
+    for (project_name, project) in &release_json.projects {
+        for (version, version_data) in project.all_versions() {
+            for location in &version_data.locations {
+                let Some(file_name) = file_name_from_url(&location.url) else {
+                    eprintln!(
+                        "skipping {project_name} {version} {}.{}: no file name in URL {}",
+                        location.target, location.format, location.url
+                    );
+                    continue;
+                };
+                artifacts.entry(location.url.clone()).or_insert_with(|| {
+                    (
+                        format!("{project_name}/{version}/{file_name}"),
+                        location.clone(),
+                    )
+                });
+            }
+        }
+    }
+
+    let uploader = Arc::new(dest.connect(secrets)?);
+    let total = artifacts.len();
+    let tasks = artifacts.into_iter().map(|(url, (key, location))| {
+        let fetcher = fetcher.clone();
+        let uploader = uploader.clone();
+        async move {
+            let result = async {
+                let bytes = fetcher.fetch_bytes(&url).await?;
+                if verify_checksums(&location, &bytes)? == 0 {
+                    eprintln!("warning: no known checksums recorded for {url}, mirroring anyway");
+                }
+                uploader
+                    .put(&key, bytes.to_vec(), &ObjectMetadata::default())
+                    .await
+            }
+            .await;
+            (url, key, result)
+        }
+    });
+    let mut stream = scheduler.run(tasks);
+
+    let mut mirrored = BTreeMap::new();
+    let mut failed = 0;
+    while let Some((url, key, result)) = stream.next().await {
+        match result {
+            Ok(()) => {
+                mirrored.insert(url, key);
+            }
+            Err(err) => {
+                eprintln!("for {url}, error mirroring: {err}");
+                failed += 1;
+            }
+        }
+        eprintln!(
+            "mirrored {}/{total} artifacts to {dest}, {failed} failed",
+            mirrored.len()
+        );
+    }
+
+    let mut changed = false;
+    if let Some(public_url) = public_url {
+        for project in release_json.projects.values_mut() {
+            for (_, location) in project.locations_mut() {
+                let Some(key) = mirrored.get(&location.url) else {
+                    continue;
+                };
+                let mirror_url = format!("{public_url}/{key}");
+                match url_mode {
+                    MirrorUrlMode::None => {}
+                    MirrorUrlMode::Rewrite => {
+                        if location.url != mirror_url {
+                            location.url = mirror_url;
+                            changed = true;
+                        }
+                    }
+                    MirrorUrlMode::Append => {
+                        if !location.mirror_urls.contains(&mirror_url) {
+                            location.mirror_urls.push(mirror_url);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(eyre!("failed to mirror {failed} artifacts"));
+    }
+    Ok(changed)
+}
@@ -0,0 +1,100 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal client for the parts of the Netlify API that mukti uses.
+
+use color_eyre::eyre::{Result, WrapErr};
+use reqwest::{header, Client};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+pub(crate) const NETLIFY_AUTH_TOKEN: &str = "NETLIFY_AUTH_TOKEN";
+pub(crate) const NETLIFY_SITE_ID: &str = "NETLIFY_SITE_ID";
+
+const API_BASE: &str = "https://api.netlify.com/api/v1";
+
+pub(crate) struct NetlifyClient {
+    client: Client,
+}
+
+impl NetlifyClient {
+    pub(crate) fn new(token: &str) -> Result<Self> {
+        let mut headers = header::HeaderMap::new();
+        let mut auth = header::HeaderValue::from_str(&format!("Bearer {token}"))
+            .wrap_err("Netlify auth token contains invalid characters")?;
+        auth.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, auth);
+
+        let client = Client::builder()
+            .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .build()?;
+        Ok(Self { client })
+    }
+
+    /// Purge everything cached for a site.
+    pub(crate) async fn purge(&self, site_id: &str) -> Result<()> {
+        self.client
+            .post(format!("{API_BASE}/purge"))
+            .json(&json!({ "site_id": site_id }))
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err("Netlify cache purge failed")?;
+        Ok(())
+    }
+
+    /// Create a deploy from a map of paths (starting with `/`) to SHA-1 digests.
+    ///
+    /// The deploy replaces all files on the site. Files that Netlify doesn't already have must
+    /// then be uploaded with [`Self::upload_file`].
+    pub(crate) async fn create_deploy(
+        &self,
+        site_id: &str,
+        files: &BTreeMap<String, String>,
+        draft: bool,
+    ) -> Result<NetlifyDeploy> {
+        let url = format!("{API_BASE}/sites/{site_id}/deploys");
+        let deploy = self
+            .client
+            .post(&url)
+            .json(&json!({ "files": files, "draft": draft }))
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err("failed to create Netlify deploy")?
+            .json()
+            .await
+            .wrap_err_with(|| format!("failed to parse Netlify response from {url}"))?;
+        Ok(deploy)
+    }
+
+    /// Upload a file that a deploy requires.
+    pub(crate) async fn upload_file(
+        &self,
+        deploy: &NetlifyDeploy,
+        path: &str,
+        contents: Vec<u8>,
+    ) -> Result<()> {
+        self.client
+            .put(format!("{API_BASE}/deploys/{}/files{path}", deploy.id))
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(contents)
+            .send()
+            .await?
+            .error_for_status()
+            .wrap_err_with(|| format!("failed to upload {path} to Netlify"))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct NetlifyDeploy {
+    pub(crate) id: String,
+    /// SHA-1 digests of files that need to be uploaded.
+    #[serde(default)]
+    pub(crate) required: Vec<String>,
+    #[serde(default)]
+    pub(crate) deploy_ssl_url: Option<String>,
+}
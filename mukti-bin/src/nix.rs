@@ -0,0 +1,203 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate a Nix derivation that fetches prebuilt binaries, with hashes in SRI format.
+
+use crate::{download::VersionSpec, output::OutputOpts, release_json::single_project};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, eyre, Result};
+use mukti_metadata::{DigestAlgorithm, MuktiReleasesJson, ReleaseLocation, ReleaseVersionData};
+use std::{collections::BTreeMap, fmt::Write as _};
+
+/// Nix systems, and the targets that can provide them in order of preference. Static musl
+/// binaries come first on Linux, since they work on NixOS without patching.
+const NIX_SYSTEMS: &[(&str, &[&str])] = &[
+    (
+        "x86_64-linux",
+        &["x86_64-unknown-linux-musl", "x86_64-unknown-linux-gnu"],
+    ),
+    (
+        "aarch64-linux",
+        &["aarch64-unknown-linux-musl", "aarch64-unknown-linux-gnu"],
+    ),
+    (
+        "x86_64-darwin",
+        &["x86_64-apple-darwin", "universal-apple-darwin"],
+    ),
+    (
+        "aarch64-darwin",
+        &["aarch64-apple-darwin", "universal-apple-darwin"],
+    ),
+];
+
+/// Formats the default unpack phase handles, in order of preference.
+const NIX_FORMATS: &[&str] = &["tar.gz", "tar.xz"];
+
+pub(crate) fn generate_nix(
+    release_json: &MuktiReleasesJson,
+    version: &VersionSpec,
+    out: &Utf8Path,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+    let (version, version_data) = version
+        .resolve(project)
+        .ok_or_else(|| eyre!("no version matching {version} found"))?;
+
+    let mut sources = BTreeMap::new();
+    for (system, targets) in NIX_SYSTEMS {
+        let Some(location) = best_location(version_data, targets) else {
+            continue;
+        };
+        let Some(digest) = location.checksums.get(&DigestAlgorithm::Sha256) else {
+            eprintln!(
+                "warning: skipping {system}: {}.{} has no SHA-256 checksum",
+                location.target, location.format
+            );
+            continue;
+        };
+        let bytes = hex::decode(&digest.0)
+            .map_err(|err| eyre!("invalid SHA-256 checksum for {}: {err}", location.url))?;
+        let hash = format!("sha256-{}", BASE64_STANDARD.encode(bytes));
+        sources.insert(*system, (location, hash));
+    }
+    if sources.is_empty() {
+        bail!("version {version} has no tarballs with SHA-256 checksums for any Nix system");
+    }
+
+    let mut nix = String::with_capacity(2048);
+    writeln!(
+        nix,
+        "# Generated by mukti for {project_name} {version}
+
+{{ lib, stdenv, fetchurl, autoPatchelfHook }}:
+
+let
+  sources = {{"
+    )
+    .expect("writing to a string is infallible");
+    for (system, (location, hash)) in &sources {
+        writeln!(
+            nix,
+            "    {system} = fetchurl {{
+      url = {};
+      hash = {};
+    }};",
+            nix_string(&location.url),
+            nix_string(hash),
+        )
+        .expect("writing to a string is infallible");
+    }
+    let mut meta = String::new();
+    if let Some(description) = &project.info.description {
+        writeln!(meta, "    description = {};", nix_string(description))
+            .expect("writing to a string is infallible");
+    }
+    if let Some(homepage) = &project.info.homepage {
+        writeln!(meta, "    homepage = {};", nix_string(homepage))
+            .expect("writing to a string is infallible");
+    }
+    if let Some(license) = project.license_for(version_data) {
+        match nix_licenses(license) {
+            Some(licenses) => writeln!(meta, "    license = {licenses};")
+                .expect("writing to a string is infallible"),
+            None => eprintln!(
+                "warning: not setting meta.license: {license} isn't a license ID or a list of \
+                 IDs joined with OR"
+            ),
+        }
+    }
+    writeln!(
+        nix,
+        r#"  }};
+in
+stdenv.mkDerivation {{
+  pname = {};
+  version = {};
+
+  src =
+    sources.${{stdenv.hostPlatform.system}}
+      or (throw "unsupported system: ${{stdenv.hostPlatform.system}}");
+  sourceRoot = ".";
+
+  nativeBuildInputs = lib.optionals stdenv.hostPlatform.isLinux [ autoPatchelfHook ];
+
+  installPhase = ''
+    runHook preInstall
+    find . -maxdepth 2 -type f -perm -u+x -exec install -Dm755 -t $out/bin {{}} +
+    runHook postInstall
+  '';
+
+  meta = {{
+{meta}    changelog = {};
+    platforms = builtins.attrNames sources;
+    sourceProvenance = [ lib.sourceTypes.binaryNativeCode ];
+  }};
+}}"#,
+        nix_string(project_name),
+        nix_string(&version.to_string()),
+        nix_string(&version_data.release_url),
+    )
+    .expect("writing to a string is infallible");
+
+    OutputOpts::default().write_file(out, &nix)?;
+    eprintln!(
+        "wrote {project_name} {version} for {} to {out}",
+        sources.keys().copied().collect::<Vec<_>>().join(", ")
+    );
+    Ok(())
+}
+
+fn best_location<'a>(
+    version_data: &'a ReleaseVersionData,
+    targets: &[&str],
+) -> Option<&'a ReleaseLocation> {
+    targets.iter().find_map(|target| {
+        NIX_FORMATS
+            .iter()
+            .find_map(|format| version_data.location(target, format))
+    })
+}
+
+/// Convert an SPDX license expression to Nix license attributes, if it's a single license ID or
+/// a choice between IDs (which Nix expresses as a list).
+fn nix_licenses(expression: &str) -> Option<String> {
+    let ids: Vec<&str> = expression.split(" OR ").map(str::trim).collect();
+    let is_id = |id: &&str| {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+    };
+    if !ids.iter().all(is_id) {
+        return None;
+    }
+    let licenses: Vec<_> = ids
+        .iter()
+        .map(|id| format!("lib.getLicenseFromSpdxId {}", nix_string(id)))
+        .collect();
+    Some(match licenses.as_slice() {
+        [license] => license.clone(),
+        _ => format!("[ ({}) ]", licenses.join(") (")),
+    })
+}
+
+/// Quote `input` as a Nix string, escaping interpolation.
+fn nix_string(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 2);
+    out.push('"');
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
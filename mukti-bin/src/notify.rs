@@ -0,0 +1,193 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Notify a webhook after a command changes something.
+//!
+//! The webhook URL is taken from `--notify-webhook`, or else the `MUKTI_NOTIFY_WEBHOOK` secret, so
+//! it can be configured once through a secrets directory or command. Failing to notify is reported
+//! as a warning, since the operation itself has already succeeded by then.
+
+use crate::{release_json::single_project, secrets::SecretResolver};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{diff, MuktiReleasesJson};
+use reqwest::Client;
+use semver::Version;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, time::Duration};
+
+const MUKTI_NOTIFY_WEBHOOK: &str = "MUKTI_NOTIFY_WEBHOOK";
+
+/// How long to wait for the webhook to respond before giving up.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The shape of the JSON payload sent to the webhook.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum NotifyFormat {
+    /// A JSON object describing the change, with `event` and `summary` fields
+    #[default]
+    Generic,
+
+    /// A Slack incoming webhook message
+    Slack,
+
+    /// A Discord webhook message
+    Discord,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct NotifyOpts {
+    /// POST a JSON payload describing what changed to this URL [default: the MUKTI_NOTIFY_WEBHOOK
+    /// secret, if set]
+    #[clap(long, value_name = "URL")]
+    notify_webhook: Option<String>,
+
+    /// The shape of the webhook payload
+    #[clap(long, value_enum, default_value_t)]
+    notify_format: NotifyFormat,
+}
+
+/// A change worth notifying about.
+struct Event {
+    /// A one-line summary, used for chat messages.
+    summary: String,
+    /// The full description, sent as-is in the generic format.
+    details: Value,
+}
+
+/// Notify that `version` was added to the release JSON, which was `old_release_json` before.
+pub(crate) async fn notify_release_added(
+    opts: &NotifyOpts,
+    secrets: &SecretResolver,
+    old_release_json: &MuktiReleasesJson,
+    release_json: &MuktiReleasesJson,
+    version: &Version,
+) {
+    let Ok((project_name, project)) = single_project(release_json) else {
+        return;
+    };
+    let Some((version, version_data)) = project.get_version_data(version) else {
+        return;
+    };
+    let latest = project
+        .latest
+        .and_then(|range| project.ranges.get(&range))
+        .map(|range_data| &range_data.latest);
+    let locations: Vec<_> = version_data
+        .locations
+        .iter()
+        .map(|location| {
+            json!({
+                "target": location.target,
+                "format": location.format,
+                "url": location.url,
+            })
+        })
+        .collect();
+
+    let event = Event {
+        summary: format!(
+            "Added {project_name} {version} with {} artifacts: {}",
+            locations.len(),
+            version_data.release_url
+        ),
+        details: json!({
+            "event": "add-release",
+            "project": project_name,
+            "version": version,
+            "release_url": version_data.release_url,
+            "latest": latest,
+            "locations": locations,
+            "diff": diff(old_release_json, release_json),
+        }),
+    };
+    send(opts, secrets, event).await;
+}
+
+/// Notify that generated redirect outputs changed.
+pub(crate) async fn notify_redirects_generated(
+    opts: &NotifyOpts,
+    secrets: &SecretResolver,
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+    changed: &[Utf8PathBuf],
+) {
+    if changed.is_empty() {
+        return;
+    }
+    // The release JSON can have several projects when generating redirects.
+    let latest: BTreeMap<_, _> = release_json
+        .projects
+        .iter()
+        .map(|(name, project)| {
+            let latest = project
+                .latest
+                .and_then(|range| project.ranges.get(&range))
+                .map(|range_data| &range_data.latest);
+            (name, latest)
+        })
+        .collect();
+    let project_names: Vec<_> = latest.keys().map(|name| name.as_str()).collect();
+
+    let event = Event {
+        summary: format!(
+            "Regenerated redirects for {} in {out_dir}: {}",
+            project_names.join(", "),
+            changed
+                .iter()
+                .map(|path| path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        details: json!({
+            "event": "generate-redirects",
+            "latest": latest,
+            "out_dir": out_dir,
+            "changed_files": changed,
+        }),
+    };
+    send(opts, secrets, event).await;
+}
+
+async fn send(opts: &NotifyOpts, secrets: &SecretResolver, event: Event) {
+    if let Err(err) = try_send(opts, secrets, event).await {
+        eprintln!("warning: failed to notify webhook: {err:#}");
+    }
+}
+
+async fn try_send(opts: &NotifyOpts, secrets: &SecretResolver, event: Event) -> Result<()> {
+    let url = match &opts.notify_webhook {
+        Some(url) => url.clone(),
+        None => match secrets.resolve(MUKTI_NOTIFY_WEBHOOK)? {
+            Some(url) => url,
+            None => return Ok(()),
+        },
+    };
+
+    let payload = match opts.notify_format {
+        NotifyFormat::Generic => {
+            let mut details = event.details;
+            details["summary"] = event.summary.into();
+            details
+        }
+        NotifyFormat::Slack => json!({ "text": event.summary }),
+        NotifyFormat::Discord => json!({ "content": event.summary }),
+    };
+
+    let client = Client::builder()
+        .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?;
+    // Webhook URLs often contain tokens, so they're stripped from errors before they're printed.
+    client
+        .post(&url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?
+        .error_for_status()
+        .map_err(reqwest::Error::without_url)
+        .wrap_err("webhook returned an error")?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Options controlling how generated text files are written out.
+
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8Path;
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{Result, WrapErr};
+use std::io::Write as _;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum Newline {
+    /// Unix-style line endings (\n)
+    #[default]
+    Lf,
+
+    /// Windows-style line endings (\r\n)
+    Crlf,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct OutputOpts {
+    /// Line endings to use in generated files
+    #[clap(long, value_enum, default_value_t)]
+    newline: Newline,
+
+    /// Start generated files with a UTF-8 byte order mark
+    #[clap(long)]
+    bom: bool,
+}
+
+impl OutputOpts {
+    /// Encode generated text, which is expected to use `\n` line endings.
+    pub(crate) fn encode(&self, contents: &str) -> Vec<u8> {
+        let mut out = Vec::with_capacity(contents.len() + UTF8_BOM.len());
+        if self.bom {
+            out.extend_from_slice(UTF8_BOM);
+        }
+        match self.newline {
+            Newline::Lf => out.extend_from_slice(contents.as_bytes()),
+            Newline::Crlf => {
+                for line in contents.split_inclusive('\n') {
+                    match line.strip_suffix('\n') {
+                        Some(line) => {
+                            out.extend_from_slice(line.trim_end_matches('\r').as_bytes());
+                            out.extend_from_slice(b"\r\n");
+                        }
+                        None => out.extend_from_slice(line.as_bytes()),
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Atomically write generated text to `path`, unless it already has these contents.
+    ///
+    /// Returns true if the file was written.
+    pub(crate) fn write_file(&self, path: &Utf8Path, contents: &str) -> Result<bool> {
+        write_bytes(path, &self.encode(contents))
+    }
+}
+
+/// Atomically write binary contents to `path`, unless it already has these contents.
+///
+/// Returns true if the file was written.
+pub(crate) fn write_bytes(path: &Utf8Path, bytes: &[u8]) -> Result<bool> {
+    if std::fs::read(path).is_ok_and(|existing| existing == bytes) {
+        return Ok(false);
+    }
+    let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+    file.write(|f| f.write_all(bytes))
+        .wrap_err_with(|| format!("failed to write {path}"))?;
+    Ok(true)
+}
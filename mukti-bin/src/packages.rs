@@ -0,0 +1,78 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Fetch the packages recorded in the release JSON and read their metadata, so that repository
+//! metadata describes the packages exactly as built.
+
+use crate::{checksums::verify_checksums, fetch::Fetcher, scheduler::Scheduler};
+use color_eyre::eyre::{bail, Report, Result, WrapErr};
+use futures_util::StreamExt;
+use mukti_metadata::{Format, MuktiProject, ReleaseLocation};
+use sha2::{Digest as _, Sha256};
+
+/// A package fetched from the release JSON, along with the metadata read from it.
+pub(crate) struct FetchedPackage<'a, M> {
+    pub(crate) location: &'a ReleaseLocation,
+    pub(crate) size: u64,
+    pub(crate) sha256: String,
+    pub(crate) metadata: M,
+}
+
+/// Fetch every package with `format` from the project's available versions, reading each one's
+/// metadata with `read_metadata`.
+///
+/// Fails if any package can't be fetched or read, rather than leaving it out of the repository.
+/// Packages are returned in the order they appear in the release JSON.
+pub(crate) async fn fetch_packages<'a, M>(
+    project: &'a MuktiProject,
+    format: Format,
+    read_metadata: fn(&[u8]) -> Result<M>,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+) -> Result<Vec<FetchedPackage<'a, M>>> {
+    let locations: Vec<_> = project
+        .active_versions()
+        .flat_map(|(_, version_data)| version_data.installable_locations())
+        .filter(|location| location.format.normalized() == format)
+        .collect();
+    let total = locations.len();
+
+    let tasks = locations.into_iter().enumerate().map(|(index, location)| {
+        let fetcher = fetcher.clone();
+        async move {
+            let result = async {
+                let bytes = fetcher.fetch_bytes(&location.url).await?;
+                verify_checksums(location, &bytes)?;
+                let metadata = read_metadata(&bytes)?;
+                Ok::<_, Report>(FetchedPackage {
+                    location,
+                    size: bytes.len() as u64,
+                    sha256: hex::encode(Sha256::digest(&bytes)),
+                    metadata,
+                })
+            }
+            .await
+            .wrap_err_with(|| format!("failed to read package {}", location.url));
+            (index, result)
+        }
+    });
+    let mut stream = scheduler.run(tasks);
+
+    let mut packages = Vec::with_capacity(total);
+    let mut failed = 0;
+    while let Some((index, result)) = stream.next().await {
+        match result {
+            Ok(package) => packages.push((index, package)),
+            Err(err) => {
+                eprintln!("{err:#}");
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("failed to read {failed} of {total} packages");
+    }
+
+    packages.sort_by_key(|(index, _)| *index);
+    Ok(packages.into_iter().map(|(_, package)| package).collect())
+}
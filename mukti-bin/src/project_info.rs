@@ -0,0 +1,57 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Set descriptive information about the project.
+
+use crate::release_json::single_project_mut;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{MuktiReleasesJson, ProjectInfo};
+
+/// Set the fields of `update` that are specified in the project's info.
+///
+/// Fields that aren't specified are left unchanged, unless `clear` is set, in which case they're
+/// removed.
+pub(crate) fn set_project_info(
+    release_json: &mut MuktiReleasesJson,
+    update: ProjectInfo,
+    clear: bool,
+) -> Result<()> {
+    if update.is_empty() && !clear {
+        bail!(
+            "at least one of --display-name, --description, --homepage, --repository, --license \
+             or --clear is required"
+        );
+    }
+
+    let (project_name, project) = single_project_mut(release_json)?;
+    let info = &mut project.info;
+    if clear {
+        *info = ProjectInfo::default();
+    }
+    let ProjectInfo {
+        display_name,
+        description,
+        homepage,
+        repository,
+        license,
+        extra: _,
+    } = update;
+    if display_name.is_some() {
+        info.display_name = display_name;
+    }
+    if description.is_some() {
+        info.description = description;
+    }
+    if homepage.is_some() {
+        info.homepage = homepage;
+    }
+    if repository.is_some() {
+        info.repository = repository;
+    }
+    if license.is_some() {
+        info.license = license;
+    }
+
+    eprintln!("updated project info for {project_name}");
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Check the attestations recorded for a version.
+//!
+//! Each attestation is fetched, and the in-toto statements in it are checked to have the recorded
+//! predicate type and to name the artifact's SHA-256 digest as a subject. Signatures on the
+//! attestations aren't verified: use `gh attestation verify` or `cosign verify-blob-attestation`
+//! for that.
+
+use crate::{download::VersionSpec, fetch::Fetcher, release_json::single_project};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use mukti_metadata::{AttestationEntry, DigestAlgorithm, MuktiReleasesJson, ReleaseLocation};
+use serde_json::Value;
+
+pub(crate) async fn verify_provenance(
+    release_json: &MuktiReleasesJson,
+    version: &VersionSpec,
+    fetcher: &Fetcher,
+) -> Result<()> {
+    let (_, project) = single_project(release_json)?;
+    let (version, version_data) = version
+        .resolve(project)
+        .ok_or_else(|| eyre!("no version matching {version} found"))?;
+
+    let mut verified = 0;
+    let mut failed = 0;
+    for location in &version_data.locations {
+        for attestation in &location.attestations {
+            match verify_attestation(location, attestation, fetcher).await {
+                Ok(()) => {
+                    verified += 1;
+                    eprintln!(
+                        "verified {} attestation for {}",
+                        attestation.predicate_type, location.url
+                    );
+                }
+                Err(err) => {
+                    failed += 1;
+                    eprintln!(
+                        "for {}, {} attestation at {} failed: {err:#}",
+                        location.url, attestation.predicate_type, attestation.url
+                    );
+                }
+            }
+        }
+    }
+
+    if verified + failed == 0 {
+        bail!("version {version} has no attestations recorded");
+    }
+    if failed > 0 {
+        bail!("{failed} of {} attestations failed", verified + failed);
+    }
+    eprintln!("verified {verified} attestations for version {version}");
+    Ok(())
+}
+
+async fn verify_attestation(
+    location: &ReleaseLocation,
+    attestation: &AttestationEntry,
+    fetcher: &Fetcher,
+) -> Result<()> {
+    let Some(digest) = location.checksums.get(&DigestAlgorithm::Sha256) else {
+        bail!("no SHA-256 checksum recorded to match against");
+    };
+    let bytes = fetcher.fetch_bytes(&attestation.url).await?;
+    let statements = parse_statements(&bytes)?;
+
+    let matches = statements.iter().any(|statement| {
+        statement["predicateType"].as_str() == Some(attestation.predicate_type.as_str())
+            && statement["subject"].as_array().is_some_and(|subjects| {
+                subjects.iter().any(|subject| {
+                    subject["digest"]["sha256"]
+                        .as_str()
+                        .is_some_and(|sha256| sha256.eq_ignore_ascii_case(&digest.0))
+                })
+            })
+    });
+    if !matches {
+        bail!(
+            "none of the {} statements found have predicate type {} and a subject with SHA-256 \
+             digest {}",
+            statements.len(),
+            attestation.predicate_type,
+            digest.0
+        );
+    }
+    Ok(())
+}
+
+/// Extract in-toto statements from a DSSE envelope, a Sigstore bundle, a bare statement, or JSON
+/// Lines containing any of them.
+fn parse_statements(bytes: &[u8]) -> Result<Vec<Value>> {
+    let documents: Vec<Value> = match serde_json::from_slice(bytes) {
+        Ok(document) => vec![document],
+        Err(_) => std::str::from_utf8(bytes)
+            .wrap_err("attestation isn't UTF-8")?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .wrap_err("attestation isn't JSON or JSON Lines")?,
+    };
+
+    documents
+        .iter()
+        .map(|document| {
+            let envelope = document.get("dsseEnvelope").unwrap_or(document);
+            match envelope["payload"].as_str() {
+                Some(payload) => {
+                    let payload = BASE64_STANDARD
+                        .decode(payload)
+                        .wrap_err("DSSE payload isn't valid base64")?;
+                    serde_json::from_slice(&payload).wrap_err("DSSE payload isn't JSON")
+                }
+                None => Ok(document.clone()),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,298 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Publish release metadata and generated files to external locations.
+
+use crate::{
+    deploy::collect_files,
+    destination::{Destination, ObjectMetadata},
+    github::GitHubClient,
+    release_json::single_project,
+    secrets::{SecretResolver, GITHUB_TOKEN},
+};
+use camino::Utf8Path;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use mukti_metadata::{detect_layout, ArtifactKind, Layout, MuktiReleasesJson, ReleaseVersionData};
+use semver::Version;
+use std::fmt::Write as _;
+
+/// A file generated in memory, ready to be published.
+#[derive(Clone, Debug)]
+pub(crate) struct GeneratedAsset {
+    pub(crate) name: String,
+    pub(crate) content_type: &'static str,
+    pub(crate) contents: Vec<u8>,
+}
+
+pub(crate) const DEFAULT_MUTABLE_CACHE_CONTROL: &str = "public, max-age=300";
+pub(crate) const DEFAULT_IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Cache-Control headers to publish files with.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CacheControl<'a> {
+    /// For files that change as releases are added, such as the releases JSON, redirect files
+    /// and `latest` endpoints.
+    pub(crate) mutable: &'a str,
+
+    /// For files under an exact version's path, which never change once written.
+    pub(crate) immutable: &'a str,
+}
+
+/// Upload the releases JSON, and every file within `dirs`, to `dest`.
+///
+/// The releases JSON is uploaded as `json_key`. Files within each directory keep their paths
+/// relative to that directory.
+pub(crate) async fn publish_to_destination(
+    json_path: &Utf8Path,
+    json_key: &str,
+    dirs: &[impl AsRef<Utf8Path>],
+    dest: &Destination,
+    cache_control: &CacheControl<'_>,
+    dry_run: bool,
+    secrets: &SecretResolver,
+) -> Result<()> {
+    let mut files = vec![(
+        json_key.trim_start_matches('/').to_owned(),
+        json_path.to_owned(),
+    )];
+    for dir in dirs {
+        let dir = dir.as_ref();
+        for (path, local_path) in collect_files(dir)? {
+            files.push((path.trim_start_matches('/').to_owned(), local_path));
+        }
+    }
+
+    if dry_run {
+        for (key, local_path) in &files {
+            let (content_type, cache_control) = object_headers(key, cache_control);
+            eprintln!(
+                "[dry-run] would upload {local_path} to {dest} as {key} \
+                 ({content_type}, {cache_control})"
+            );
+        }
+        return Ok(());
+    }
+
+    let uploader = dest.connect(secrets)?;
+    for (key, local_path) in &files {
+        let (content_type, cache_control) = object_headers(key, cache_control);
+        let body =
+            std::fs::read(local_path).wrap_err_with(|| format!("failed to read {local_path}"))?;
+        eprintln!("uploading {key}");
+        uploader
+            .put(
+                key,
+                body,
+                &ObjectMetadata {
+                    content_type: Some(content_type),
+                    cache_control: Some(cache_control),
+                },
+            )
+            .await
+            .wrap_err_with(|| format!("failed to upload {key} to {dest}"))?;
+    }
+
+    eprintln!("published {} files to {dest}", files.len());
+    Ok(())
+}
+
+/// Return the Content-Type and Cache-Control headers to upload `key` with.
+fn object_headers<'a>(key: &str, cache_control: &CacheControl<'a>) -> (&'static str, &'a str) {
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    let content_type = match file_name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("json") => "application/json",
+        Some("html") => "text/html; charset=utf-8",
+        Some("xml" | "config") => "application/xml",
+        Some("toml") => "application/toml",
+        Some("txt" | "ps1") => "text/plain; charset=utf-8",
+        Some("sh") => "text/x-shellscript",
+        Some("js") => "text/javascript",
+        Some("css") => "text/css",
+        // Redirect files such as `_redirects` have no extension.
+        None => "text/plain; charset=utf-8",
+        Some(_) => "application/octet-stream",
+    };
+    let is_versioned = key
+        .split('/')
+        .any(|component| Version::parse(component).is_ok());
+    let cache_control = if is_versioned {
+        cache_control.immutable
+    } else {
+        cache_control.mutable
+    };
+    (content_type, cache_control)
+}
+
+pub(crate) async fn publish_github_assets(
+    release_json: &MuktiReleasesJson,
+    repo: &str,
+    version: &Version,
+    tag: Option<&str>,
+    dry_run: bool,
+    secrets: &SecretResolver,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+    let (version, version_data) = project
+        .get_version_data(version)
+        .ok_or_else(|| eyre!("version {version} not found in releases JSON"))?;
+
+    let tag = match tag {
+        Some(tag) => tag.to_owned(),
+        None => file_name_from_url(&version_data.release_url)
+            .ok_or_else(|| {
+                eyre!(
+                    "unable to determine tag from release URL {} (pass in --tag)",
+                    version_data.release_url
+                )
+            })?
+            .to_owned(),
+    };
+
+    let assets = github_release_assets(project_name, version, version_data)?;
+
+    if dry_run {
+        for asset in &assets {
+            eprintln!(
+                "[dry-run] would upload {} ({} bytes) to {repo} release {tag}",
+                asset.name,
+                asset.contents.len()
+            );
+        }
+        return Ok(());
+    }
+
+    let token = secrets.require(GITHUB_TOKEN)?;
+    let client = GitHubClient::new(repo, &token)?;
+    let release = client.release_by_tag(&tag).await?;
+
+    for asset in assets {
+        eprintln!("uploading {} to {repo} release {tag}", asset.name);
+        client
+            .upload_asset(&release, &asset.name, asset.content_type, asset.contents)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Upload the releases JSON to a GitHub release as an asset named `asset_name`, replacing any
+/// existing asset with that name.
+///
+/// If the releases JSON is sharded, the assembled file is uploaded, so consumers only need to
+/// fetch one asset.
+pub(crate) async fn publish_github_release(
+    json_path: &Utf8Path,
+    release_json: &MuktiReleasesJson,
+    repo: &str,
+    tag: &str,
+    asset_name: &str,
+    dry_run: bool,
+    secrets: &SecretResolver,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(json_path)
+        .wrap_err_with(|| format!("failed to read {json_path}"))?;
+    let contents = if detect_layout(&contents).ok() == Some(Layout::Sharded) {
+        serde_json::to_vec_pretty(release_json)?
+    } else {
+        contents.into_bytes()
+    };
+
+    if dry_run {
+        eprintln!(
+            "[dry-run] would upload {asset_name} ({} bytes) to {repo} release {tag}",
+            contents.len()
+        );
+        return Ok(());
+    }
+
+    let token = secrets.require(GITHUB_TOKEN)?;
+    let client = GitHubClient::new(repo, &token)?;
+    let release = client.release_by_tag(tag).await?;
+    eprintln!("uploading {asset_name} to {repo} release {tag}");
+    client
+        .upload_asset(&release, asset_name, "application/json", contents)
+        .await?;
+
+    Ok(())
+}
+
+/// Generate the assets attached to a GitHub release: one checksum sidecar per location and
+/// algorithm, a list of the release's SBOMs if it has any, and a JSON report describing the
+/// release.
+fn github_release_assets(
+    project_name: &str,
+    version: &Version,
+    version_data: &ReleaseVersionData,
+) -> Result<Vec<GeneratedAsset>> {
+    let mut assets = Vec::new();
+
+    for location in &version_data.locations {
+        // Checksums of checksum files and signatures aren't useful.
+        if matches!(
+            location.kind,
+            ArtifactKind::ChecksumFile | ArtifactKind::Signature
+        ) {
+            continue;
+        }
+        let Some(file_name) = file_name_from_url(&location.url) else {
+            eprintln!(
+                "skipping checksum sidecars for {}.{}: no file name in URL {}",
+                location.target, location.format, location.url
+            );
+            continue;
+        };
+
+        for (algorithm, digest) in &location.checksums {
+            // This matches the output format of sha256sum, b2sum, etc.
+            let mut contents = String::new();
+            writeln!(contents, "{}  {file_name}", digest.0)
+                .expect("writing to a string is infallible");
+            assets.push(GeneratedAsset {
+                name: format!("{file_name}.{algorithm}"),
+                content_type: "text/plain",
+                contents: contents.into_bytes(),
+            });
+        }
+    }
+
+    // SBOMs may be hosted outside the GitHub release, so list where each one is.
+    let sboms: Vec<_> = version_data
+        .locations
+        .iter()
+        .filter(|location| location.kind == ArtifactKind::Sbom)
+        .collect();
+    if !sboms.is_empty() {
+        let references = serde_json::json!({
+            "project": project_name,
+            "version": version,
+            "sboms": sboms,
+        });
+        assets.push(GeneratedAsset {
+            name: "mukti-sbom-references.json".to_owned(),
+            content_type: "application/json",
+            contents: serde_json::to_vec_pretty(&references)?,
+        });
+    }
+
+    let report = serde_json::json!({
+        "project": project_name,
+        "version": version,
+        "release": version_data,
+    });
+    assets.push(GeneratedAsset {
+        name: "mukti-release-report.json".to_owned(),
+        content_type: "application/json",
+        contents: serde_json::to_vec_pretty(&report)?,
+    });
+
+    Ok(assets)
+}
+
+/// Return the last path component of a URL, if it is non-empty.
+pub(crate) fn file_name_from_url(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit_once('/') {
+        Some((_, name)) if !name.is_empty() => Some(name),
+        _ => None,
+    }
+}
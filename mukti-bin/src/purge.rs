@@ -0,0 +1,173 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Purge CDN caches for redirect paths whose targets change when a release is added.
+
+use crate::{
+    netlify::{NetlifyClient, NETLIFY_AUTH_TOKEN, NETLIFY_SITE_ID},
+    release_json::single_project,
+    secrets::SecretResolver,
+};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use mukti_metadata::{published_paths, MuktiReleasesJson, PathAlias, PublishedPathsConfig};
+use reqwest::{header, Client};
+use semver::Version;
+use serde_json::json;
+
+const CLOUDFLARE_API_TOKEN: &str = "CLOUDFLARE_API_TOKEN";
+const CLOUDFLARE_ZONE_ID: &str = "CLOUDFLARE_ZONE_ID";
+const FASTLY_API_TOKEN: &str = "FASTLY_API_TOKEN";
+const FASTLY_SERVICE_ID: &str = "FASTLY_SERVICE_ID";
+
+/// The maximum number of URLs Cloudflare accepts in a single purge request.
+const CLOUDFLARE_MAX_FILES: usize = 30;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum PurgeProvider {
+    /// Cloudflare: requires CLOUDFLARE_API_TOKEN and CLOUDFLARE_ZONE_ID
+    Cloudflare,
+
+    /// Netlify: requires NETLIFY_AUTH_TOKEN and NETLIFY_SITE_ID, and always purges the whole site
+    Netlify,
+
+    /// Fastly: requires FASTLY_API_TOKEN, and FASTLY_SERVICE_ID with --all
+    Fastly,
+}
+
+pub(crate) struct PurgeOptions<'a> {
+    pub(crate) provider: PurgeProvider,
+    pub(crate) base_url: Option<&'a str>,
+    pub(crate) prefix: &'a str,
+    pub(crate) aliases: &'a [PathAlias],
+    pub(crate) auto_aliases: bool,
+    pub(crate) all: bool,
+    pub(crate) dry_run: bool,
+}
+
+pub(crate) async fn purge_cache(
+    release_json: &MuktiReleasesJson,
+    opts: &PurgeOptions<'_>,
+    secrets: &SecretResolver,
+) -> Result<()> {
+    single_project(release_json)?;
+
+    let urls = if opts.all {
+        Vec::new()
+    } else {
+        let base_url = opts
+            .base_url
+            .ok_or_else(|| eyre!("--base-url is required unless --all is passed in"))?
+            .trim_end_matches('/');
+        affected_paths(release_json, opts)
+            .into_iter()
+            .map(|path| format!("{base_url}{path}"))
+            .collect()
+    };
+
+    if opts.dry_run {
+        if opts.all {
+            eprintln!("would purge everything");
+        }
+        for url in &urls {
+            eprintln!("would purge {url}");
+        }
+        return Ok(());
+    }
+
+    let client = Client::builder()
+        .user_agent(concat!("mukti/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    match opts.provider {
+        PurgeProvider::Cloudflare => {
+            let token = secrets.require(CLOUDFLARE_API_TOKEN)?;
+            let zone_id = secrets.require(CLOUDFLARE_ZONE_ID)?;
+            let endpoint =
+                format!("https://api.cloudflare.com/client/v4/zones/{zone_id}/purge_cache");
+            let bodies = if opts.all {
+                vec![json!({ "purge_everything": true })]
+            } else {
+                urls.chunks(CLOUDFLARE_MAX_FILES)
+                    .map(|chunk| json!({ "files": chunk }))
+                    .collect()
+            };
+            for body in bodies {
+                client
+                    .post(&endpoint)
+                    .bearer_auth(&token)
+                    .json(&body)
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .wrap_err("Cloudflare cache purge failed")?;
+            }
+        }
+        PurgeProvider::Netlify => {
+            if !opts.all {
+                eprintln!("warning: Netlify can't purge individual paths, purging the whole site");
+            }
+            let token = secrets.require(NETLIFY_AUTH_TOKEN)?;
+            let site_id = secrets.require(NETLIFY_SITE_ID)?;
+            NetlifyClient::new(&token)?.purge(&site_id).await?;
+        }
+        PurgeProvider::Fastly => {
+            let token = secrets.require(FASTLY_API_TOKEN)?;
+            if opts.all {
+                let service_id = secrets.require(FASTLY_SERVICE_ID)?;
+                client
+                    .post(format!(
+                        "https://api.fastly.com/service/{service_id}/purge_all"
+                    ))
+                    .header("Fastly-Key", &token)
+                    .header(header::ACCEPT, "application/json")
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .wrap_err("Fastly cache purge failed")?;
+            } else {
+                for url in &urls {
+                    let Some((_, rest)) = url.split_once("://") else {
+                        bail!("invalid URL `{url}`: expected a scheme");
+                    };
+                    client
+                        .post(format!("https://api.fastly.com/purge/{rest}"))
+                        .header("Fastly-Key", &token)
+                        .header(header::ACCEPT, "application/json")
+                        .send()
+                        .await?
+                        .error_for_status()
+                        .wrap_err_with(|| format!("Fastly cache purge failed for {url}"))?;
+                }
+            }
+        }
+    }
+
+    if opts.all {
+        eprintln!("purged everything");
+    } else {
+        eprintln!("purged {} URLs", urls.len());
+    }
+    Ok(())
+}
+
+/// Paths whose redirect targets can change when releases are added: `latest`, channels and
+/// version ranges.
+///
+/// Paths for exact versions are left alone, since they always point to the same artifacts.
+fn affected_paths(release_json: &MuktiReleasesJson, opts: &PurgeOptions<'_>) -> Vec<String> {
+    let config = PublishedPathsConfig {
+        prefix: opts.prefix.to_owned(),
+        aliases: opts.aliases.to_vec(),
+        auto_aliases: opts.auto_aliases,
+    };
+    let prefix = opts.prefix.trim_end_matches('/');
+    published_paths(release_json, &config)
+        .into_iter()
+        .filter(|path| {
+            let version = path
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.trim_start_matches('/').split('/').next());
+            version.is_some_and(|version| Version::parse(version).is_err())
+        })
+        .collect()
+}
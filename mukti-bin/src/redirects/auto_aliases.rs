@@ -0,0 +1,31 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Derive friendly aliases like `linux-x64` from the target triples in a version.
+
+use mukti_metadata::{friendly_aliases, PathAlias, ReleaseVersionData};
+
+/// Compute aliases for the targets present in `version_data`.
+///
+/// Aliases that are also passed in explicitly for this version are skipped. See
+/// [`friendly_aliases`] for the aliases generated.
+pub(super) fn auto_aliases(
+    version_data: &ReleaseVersionData,
+    explicit: &[&PathAlias],
+) -> Vec<PathAlias> {
+    friendly_aliases(version_data)
+        .into_iter()
+        .filter(|(alias, _)| {
+            !explicit
+                .iter()
+                .any(|explicit| explicit.is_named(alias, version_data))
+        })
+        .map(|(alias, location)| PathAlias {
+            alias,
+            target: location.target.clone(),
+            format: location.format.to_string(),
+            project: None,
+            version_req: None,
+        })
+        .collect()
+}
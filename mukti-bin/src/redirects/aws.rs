@@ -0,0 +1,164 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Outputs for serving redirects from AWS without a separate web server: routing rules for an S3
+//! static website, or a CloudFront Function that runs at the edge.
+
+use super::{iis::xml_escape, Redirect, RedirectFlavor};
+use color_eyre::eyre::{Result, WrapErr};
+use reqwest::Url;
+use std::{collections::BTreeMap, fmt::Write as _};
+
+/// The maximum size of a CloudFront Function's code.
+const MAX_FUNCTION_SIZE: usize = 10 * 1024;
+
+/// Write the `RoutingRules` element of an S3 website configuration.
+///
+/// S3 only supports matching on key prefixes, and applies the first rule that matches. Rules are
+/// written in reverse order of their paths, so that a path like `p/0.9/linux-arm` comes before
+/// `p/0.9/linux`, which would otherwise match requests for it too.
+pub(super) fn write_routing_rules(
+    redirects: &[Redirect],
+    flavor: RedirectFlavor,
+    out: &mut String,
+) -> Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+        .expect("writing to a string is infallible");
+    writeln!(
+        out,
+        "<!-- Generated by mukti with redirect flavor {:?} -->",
+        flavor
+    )
+    .expect("writing to a string is infallible");
+    out.push_str("<RoutingRules>\n");
+
+    let mut redirects: Vec<_> = redirects.iter().collect();
+    redirects.sort_by(|a, b| b.from.cmp(&a.from));
+    for redirect in redirects {
+        let url = Url::parse(&redirect.to)
+            .wrap_err_with(|| format!("invalid redirect URL {}", redirect.to))?;
+        let mut key = url.path().trim_start_matches('/').to_owned();
+        if let Some(query) = url.query() {
+            write!(key, "?{query}").expect("writing to a string is infallible");
+        }
+        writeln!(
+            out,
+            "  <RoutingRule>
+    <Condition>
+      <KeyPrefixEquals>{}</KeyPrefixEquals>
+    </Condition>
+    <Redirect>
+      <Protocol>{}</Protocol>
+      <HostName>{}</HostName>
+      <ReplaceKeyWith>{}</ReplaceKeyWith>
+      <HttpRedirectCode>{}</HttpRedirectCode>
+    </Redirect>
+  </RoutingRule>",
+            xml_escape(redirect.from.trim_start_matches('/')),
+            xml_escape(url.scheme()),
+            xml_escape(&host_with_port(&url)),
+            xml_escape(&key),
+            redirect.code,
+        )
+        .expect("writing to a string is infallible");
+    }
+
+    out.push_str("</RoutingRules>\n");
+    Ok(())
+}
+
+/// Write a CloudFront Function (for viewer requests) that redirects matching paths.
+pub(super) fn write_cloudfront_function(
+    redirects: &[Redirect],
+    flavor: RedirectFlavor,
+    out: &mut String,
+) -> Result<()> {
+    let map: BTreeMap<_, _> = redirects
+        .iter()
+        .map(|redirect| (&redirect.from, (redirect.code, &redirect.to)))
+        .collect();
+
+    writeln!(
+        out,
+        "// Generated by mukti with redirect flavor {:?}\n",
+        flavor
+    )
+    .expect("writing to a string is infallible");
+    writeln!(out, "var REDIRECTS = {};\n", serde_json::to_string(&map)?)
+        .expect("writing to a string is infallible");
+    out.push_str(
+        r#"var STATUS_DESCRIPTIONS = {
+    301: "Moved Permanently",
+    302: "Found",
+    303: "See Other",
+    307: "Temporary Redirect",
+    308: "Permanent Redirect",
+};
+
+function handler(event) {
+    var request = event.request;
+    var redirect = REDIRECTS[request.uri];
+    if (!redirect) {
+        return request;
+    }
+    return {
+        statusCode: redirect[0],
+        statusDescription: STATUS_DESCRIPTIONS[redirect[0]] || "Found",
+        headers: { location: { value: redirect[1] } },
+    };
+}
+"#,
+    );
+
+    if out.len() > MAX_FUNCTION_SIZE {
+        eprintln!(
+            "warning: generated CloudFront Function is {} bytes, but functions can be at most \
+             {MAX_FUNCTION_SIZE} bytes (consider a Lambda@Edge function with the same code)",
+            out.len()
+        );
+    }
+    Ok(())
+}
+
+fn host_with_port(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redirects::{RedirectKind, RedirectVersion};
+
+    #[test]
+    fn test_routing_rules_prefix_order() {
+        let redirect = |from: &str, to: &str| Redirect {
+            version: RedirectVersion::Latest,
+            kind: RedirectKind::Alias,
+            from: from.to_owned(),
+            to: to.to_owned(),
+            code: 302,
+        };
+        // Sorted the way redirects are generated, with the shorter path first.
+        let redirects = [
+            redirect("/p/0.9/linux", "https://example.com/linux.tar.gz"),
+            redirect("/p/0.9/linux-arm", "https://example.com/linux-arm.tar.gz"),
+        ];
+
+        let mut out = String::new();
+        write_routing_rules(&redirects, RedirectFlavor::S3RoutingRules, &mut out).unwrap();
+        let linux_arm = out
+            .find("<KeyPrefixEquals>p/0.9/linux-arm</KeyPrefixEquals>")
+            .unwrap();
+        let linux = out
+            .find("<KeyPrefixEquals>p/0.9/linux</KeyPrefixEquals>")
+            .unwrap();
+        assert!(
+            linux_arm < linux,
+            "the longer key must come first, since S3 applies the first matching rule"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Redirects rendered from a user-supplied Tera template, for hosts without a built-in flavor.
+//!
+//! Values aren't escaped unless the template's name ends with `.html.tera` or `.xml.tera`; use the
+//! `escape` or `json_encode` filters where the output format needs it.
+//!
+//! The template receives three variables:
+//!
+//! * `redirects`: a list of `{path, url, status, version, kind, project}` objects, in the same
+//!   order as the other flavors. `kind` is one of `root`, `release`, `location`, `alias`,
+//!   `debug_symbols` or `not_found`.
+//! * `projects`: a list of `{name, prefix, latest, versions}` objects, one per mounted prefix.
+//! * `vars`: the variables passed in with `--var NAME=VALUE`, by name.
+
+use super::{Mount, Redirect, RedirectKind};
+use crate::template::Templates;
+use camino::Utf8Path;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// The output file is named after the template, e.g. `_redirects.tera` produces `_redirects`.
+pub(super) fn output_file_name(template: &Utf8Path) -> Result<&str> {
+    let file_name = template
+        .file_name()
+        .ok_or_else(|| eyre!("template path {template} has no file name"))?;
+    Ok(file_name.strip_suffix(".tera").unwrap_or(file_name))
+}
+
+pub(super) fn render_template(
+    template_path: &Utf8Path,
+    redirects: &[Redirect],
+    mounts: &[Mount<'_>],
+    vars: &BTreeMap<String, String>,
+    out: &mut String,
+) -> Result<()> {
+    let source = std::fs::read_to_string(template_path)
+        .wrap_err_with(|| format!("failed to read template {template_path}"))?;
+    let name = template_path.file_name().unwrap_or(template_path.as_str());
+    let templates = Templates::parse([(name, source)])
+        .wrap_err_with(|| format!("failed to parse template {template_path}"))?;
+
+    let redirects: Vec<_> = redirects
+        .iter()
+        .map(|redirect| {
+            let project = mounts
+                .iter()
+                .find(|mount| mount.prefix == redirect.prefix())
+                .map(|mount| mount.project_name);
+            let kind = match redirect.kind {
+                RedirectKind::Root => "root",
+                RedirectKind::Release => "release",
+                RedirectKind::Location => "location",
+                RedirectKind::Alias => "alias",
+                RedirectKind::DebugSymbols => "debug_symbols",
+                RedirectKind::NotFound => "not_found",
+            };
+            json!({
+                "path": redirect.from,
+                "url": redirect.to,
+                "status": redirect.code,
+                "version": redirect.version.to_string(),
+                "kind": kind,
+                "project": project,
+            })
+        })
+        .collect();
+    let projects: Vec<_> = mounts
+        .iter()
+        .map(|mount| {
+            let latest = mount
+                .project
+                .latest
+                .and_then(|range| mount.project.ranges.get(&range))
+                .map(|range_data| range_data.latest.to_string());
+            let versions: Vec<_> = mount
+                .project
+                .all_versions()
+                .map(|(version, _)| version.to_string())
+                .collect();
+            json!({
+                "name": mount.project_name,
+                "prefix": mount.prefix,
+                "latest": latest,
+                "versions": versions,
+            })
+        })
+        .collect();
+    let context: Value = json!({
+        "redirects": redirects,
+        "projects": projects,
+        "vars": vars,
+    });
+
+    let rendered = templates
+        .render(name, &context)
+        .wrap_err_with(|| format!("failed to render template {template_path}"))?;
+    out.push_str(&rendered);
+    Ok(())
+}
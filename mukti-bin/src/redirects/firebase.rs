@@ -0,0 +1,50 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Firebase Hosting firebase.json output, merged into the `hosting.redirects` array.
+
+use super::{json_config, Mount, Redirect};
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use serde_json::{json, Map, Value};
+
+pub(super) fn write_firebase_json(
+    redirects: &[Redirect],
+    mounts: &[Mount<'_>],
+    existing_path: &Utf8Path,
+    out: &mut String,
+) -> Result<()> {
+    let mut config = json_config::read_config(existing_path)?;
+    let hosting = config
+        .entry("hosting")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let hosting = match hosting {
+        Value::Object(hosting) => hosting,
+        Value::Array(_) => bail!(
+            "{existing_path} configures multiple Hosting sites, which isn't supported \
+             (generate into a separate directory and copy the redirects over)"
+        ),
+        _ => bail!("existing `hosting` field in {existing_path} isn't an object"),
+    };
+    json_config::merge_redirects(
+        hosting,
+        "redirects",
+        mounts,
+        redirects.iter().map(firebase_redirect),
+    )
+    .wrap_err_with(|| format!("failed to merge redirects into {existing_path}"))?;
+    json_config::write_config(&config, out)
+}
+
+fn firebase_redirect(redirect: &Redirect) -> Value {
+    // Firebase Hosting only supports 301 and 302 redirects.
+    let code = match redirect.code {
+        301 | 308 => 301,
+        _ => 302,
+    };
+    json!({
+        "source": redirect.from,
+        "destination": redirect.to,
+        "type": code,
+    })
+}
@@ -0,0 +1,41 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Apache .htaccess output, for mod_rewrite.
+//!
+//! Within .htaccess, rules match against the path relative to the directory the file is in, so
+//! the generated file must be placed at the document root.
+
+use super::{iis::regex_escape, Redirect};
+use std::fmt::Write as _;
+
+pub(super) fn write_htaccess(redirects: &[Redirect], out: &mut String) {
+    out.push_str("RewriteEngine On\n\n");
+    for redirect in redirects {
+        // NE stops Apache from escaping the target again, since it's already a valid URL.
+        writeln!(
+            out,
+            "RewriteRule \"^{}$\" \"{}\" [R={},L,NE]",
+            quote_escape(&regex_escape(redirect.from.trim_start_matches('/'))),
+            quote_escape(&substitution_escape(&redirect.to)),
+            redirect.code,
+        )
+        .expect("writing to a string is infallible");
+    }
+}
+
+/// Escape back-references in a substitution string.
+fn substitution_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        if c == '$' || c == '%' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn quote_escape(input: &str) -> String {
+    input.replace('"', "\\\"")
+}
@@ -0,0 +1,85 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! IIS web.config output, for the URL Rewrite module.
+
+use super::{Redirect, RedirectFlavor};
+use std::fmt::Write as _;
+
+pub(super) fn write_web_config(redirects: &[Redirect], flavor: RedirectFlavor, out: &mut String) {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+        .expect("writing to a string is infallible");
+    writeln!(
+        out,
+        "<!-- Generated by mukti with redirect flavor {:?} -->",
+        flavor
+    )
+    .expect("writing to a string is infallible");
+    out.push_str("<configuration>\n");
+    out.push_str("  <system.webServer>\n");
+    out.push_str("    <rewrite>\n");
+    out.push_str("      <rules>\n");
+
+    if redirects.iter().any(|redirect| redirect.code == 308) {
+        eprintln!(
+            "warning: IIS URL Rewrite doesn't support 308 redirects, so they're emitted as 301"
+        );
+    }
+
+    for (idx, redirect) in redirects.iter().enumerate() {
+        // IIS matches against the path without its leading slash.
+        let pattern = format!("^{}$", regex_escape(redirect.from.trim_start_matches('/')));
+        writeln!(
+            out,
+            r#"        <rule name="mukti-{idx}" stopProcessing="true">
+          <match url="{}" />
+          <action type="Redirect" url="{}" redirectType="{}" />
+        </rule>"#,
+            xml_escape(&pattern),
+            xml_escape(&redirect.to),
+            redirect_type(redirect.code),
+        )
+        .expect("writing to a string is infallible");
+    }
+
+    out.push_str("      </rules>\n");
+    out.push_str("    </rewrite>\n");
+    out.push_str("  </system.webServer>\n");
+    out.push_str("</configuration>\n");
+}
+
+fn redirect_type(code: u16) -> &'static str {
+    // The URL Rewrite module has no redirect type for 308, so use the closest one.
+    match code {
+        301 | 308 => "Permanent",
+        303 => "SeeOther",
+        307 => "Temporary",
+        _ => "Found",
+    }
+}
+
+pub(super) fn regex_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+pub(crate) fn xml_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
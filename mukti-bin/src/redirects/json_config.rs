@@ -0,0 +1,86 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Shared support for hosting providers configured through a JSON file that holds other settings
+//! as well, like vercel.json and firebase.json.
+//!
+//! Rather than overwriting the file, the redirects array in any existing file is updated in
+//! place. Redirects previously generated by mukti (the root redirect for one of the prefixes, and
+//! those under it for `latest`, a channel, a version range or a version) are replaced, and
+//! everything else is kept as-is.
+
+use super::Mount;
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::VersionRange;
+use semver::Version;
+use serde_json::{Map, Value};
+
+/// Read the existing config at `path`, or an empty object if there isn't one.
+pub(super) fn read_config(path: &Utf8Path) -> Result<Map<String, Value>> {
+    let config = match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(contents.trim_start_matches('\u{feff}'))
+            .wrap_err_with(|| format!("failed to parse existing {path}"))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Value::Object(Map::new()),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("failed to read {path}"));
+        }
+    };
+    match config {
+        Value::Object(config) => Ok(config),
+        _ => bail!("existing {path} is not a JSON object"),
+    }
+}
+
+/// Replace generated redirects in the array at `config[key]` with `redirects`.
+pub(super) fn merge_redirects(
+    config: &mut Map<String, Value>,
+    key: &str,
+    mounts: &[Mount<'_>],
+    redirects: impl IntoIterator<Item = Value>,
+) -> Result<()> {
+    // Update the array in place, to keep the existing order of keys.
+    let existing = config
+        .entry(key)
+        .or_insert_with(|| Value::Array(Vec::new()));
+    let Value::Array(merged) = existing else {
+        bail!("existing `{key}` field isn't an array");
+    };
+    merged.retain(|redirect| {
+        let source = redirect.get("source").and_then(Value::as_str);
+        !source.is_some_and(|source| {
+            mounts
+                .iter()
+                .any(|mount| is_generated_source(source, mount))
+        })
+    });
+    merged.extend(redirects);
+    Ok(())
+}
+
+/// Write `config` out as pretty-printed JSON.
+pub(super) fn write_config(config: &Map<String, Value>, out: &mut String) -> Result<()> {
+    out.push_str(&serde_json::to_string_pretty(config)?);
+    out.push('\n');
+    Ok(())
+}
+
+/// Whether `source` looks like a path mukti generated for `mount`.
+///
+/// Channels are matched by the names the project has now, so redirects for a channel that was
+/// since removed are kept.
+fn is_generated_source(source: &str, mount: &Mount<'_>) -> bool {
+    let Some(rest) = source
+        .strip_prefix(mount.prefix)
+        .and_then(|rest| rest.strip_prefix('/'))
+    else {
+        return false;
+    };
+    let version = rest.split('/').next().unwrap_or_default();
+    // The bare prefix is the source of `--root-redirect`.
+    rest.is_empty()
+        || version == "latest"
+        || mount.project.channels.contains_key(version)
+        || version.parse::<VersionRange>().is_ok()
+        || version.parse::<Version>().is_ok()
+}
@@ -0,0 +1,64 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Small `latest.json` and `latest-prerelease.json` files under each prefix, so update checks
+//! don't need to download and parse the whole release JSON.
+
+use super::Mount;
+use crate::output::OutputOpts;
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::ReleaseVersionData;
+use semver::Version;
+use serde_json::json;
+
+pub(super) fn write_latest_json_files(
+    mounts: &[Mount<'_>],
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    // JSON parsers don't all accept a BOM, whatever the other outputs use.
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+    for mount in mounts {
+        let project = mount.project;
+        let latest = project.latest.and_then(|range| {
+            let range_data = project.ranges.get(&range)?;
+            let version_data = range_data.versions.get(&range_data.latest)?;
+            Some((&range_data.latest, version_data))
+        });
+        let latest_prerelease = project
+            .all_versions()
+            .find(|(_, version_data)| version_data.status.is_available());
+
+        for (file_name, version) in [
+            ("latest.json", latest),
+            ("latest-prerelease.json", latest_prerelease),
+        ] {
+            let Some((version, version_data)) = version else {
+                continue;
+            };
+            let rel_path = Utf8Path::new(mount.prefix.trim_start_matches('/')).join(file_name);
+            let path = out_dir.join(&rel_path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+            }
+            let contents = latest_json(version, version_data)?;
+            if output_opts.write_file(&path, &contents)? {
+                changed.push(rel_path);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+fn latest_json(version: &Version, version_data: &ReleaseVersionData) -> Result<String> {
+    let value = json!({
+        "version": version,
+        "release_url": version_data.release_url,
+        "locations": version_data.locations,
+    });
+    let mut out = serde_json::to_string(&value)?;
+    out.push('\n');
+    Ok(out)
+}
@@ -0,0 +1,1011 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod auto_aliases;
+mod aws;
+mod custom;
+mod firebase;
+mod htaccess;
+mod iis;
+mod json_config;
+mod latest_json;
+mod netlify_toml;
+mod nginx;
+mod sidecars;
+mod vercel;
+mod worker;
+
+pub(crate) use iis::xml_escape;
+
+use crate::{command::ProjectPrefix, output::OutputOpts, release_json::single_project};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{Args, ValueEnum};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use core::fmt;
+use mukti_metadata::{
+    project_aliases, MuktiProject, MuktiReleasesJson, PathAlias, ReleaseVersionData, VersionRange,
+};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum RedirectFlavor {
+    /// Netlify _redirects: uses :version placeholders along with some static redirects
+    Netlify,
+
+    /// Netlify netlify.toml: merged into any existing file between marker comments
+    NetlifyToml,
+
+    /// Cloudflare _redirects: uses :version splats along with some static redirects
+    Cloudflare,
+
+    /// S3 static website RoutingRules XML: prefix matches, up to 50 rules
+    S3RoutingRules,
+
+    /// CloudFront Function: JavaScript run at the edge for viewer requests
+    CloudfrontFunction,
+
+    /// Apache .htaccess: mod_rewrite rules, for the document root
+    Htaccess,
+
+    /// Cloudflare Worker: a module Worker script, optionally resolving against the live release
+    /// JSON
+    CloudflareWorker,
+
+    /// IIS web.config: URL Rewrite module rules
+    Iis,
+
+    /// nginx location blocks, to include in a server block
+    Nginx,
+
+    /// Vercel vercel.json: merged into the `redirects` array of any existing file
+    Vercel,
+
+    /// Firebase Hosting firebase.json: merged into the `hosting.redirects` array of any existing
+    /// file
+    Firebase,
+
+    /// A user-supplied template passed in with --template, named after the template without its
+    /// .tera extension
+    Custom,
+}
+
+impl RedirectFlavor {
+    /// The name of the file generated for this flavor, or None if it's named after the template.
+    fn file_name(self) -> Option<&'static str> {
+        let file_name = match self {
+            Self::Netlify | Self::Cloudflare => "_redirects",
+            Self::NetlifyToml => "netlify.toml",
+            Self::S3RoutingRules => "routing-rules.xml",
+            Self::CloudfrontFunction => "cloudfront-function.js",
+            Self::CloudflareWorker => "worker.js",
+            Self::Htaccess => ".htaccess",
+            Self::Iis => "web.config",
+            Self::Nginx => "redirects.conf",
+            Self::Vercel => "vercel.json",
+            Self::Firebase => "firebase.json",
+            Self::Custom => return None,
+        };
+        Some(file_name)
+    }
+
+    /// Whether the generated file supports `#` comments.
+    fn uses_hash_comments(self) -> bool {
+        match self {
+            Self::Netlify | Self::Cloudflare | Self::Htaccess | Self::Nginx => true,
+            Self::NetlifyToml
+            | Self::S3RoutingRules
+            | Self::CloudfrontFunction
+            | Self::CloudflareWorker
+            | Self::Iis
+            | Self::Vercel
+            | Self::Firebase
+            | Self::Custom => false,
+        }
+    }
+
+    /// Whether this flavor can express redirects of `kind`.
+    fn supports(self, kind: RedirectKind) -> bool {
+        match kind {
+            // S3 only matches on key prefixes, so a rule for the prefix itself would match every
+            // path under it.
+            RedirectKind::Root => !matches!(self, Self::S3RoutingRules),
+            // Serving a page with a 404 status needs a rewrite rule, which only Netlify supports.
+            RedirectKind::NotFound => {
+                matches!(self, Self::Netlify | Self::NetlifyToml | Self::Custom)
+            }
+            RedirectKind::Release
+            | RedirectKind::Location
+            | RedirectKind::Alias
+            | RedirectKind::DebugSymbols => true,
+        }
+    }
+}
+
+/// Limits a hosting provider places on the number of redirect rules.
+struct RedirectLimits {
+    provider: &'static str,
+    /// The maximum number of static rules, or of all rules if `max_dynamic` is None.
+    max_static: usize,
+    /// The maximum number of rules with placeholders, if they're limited separately.
+    max_dynamic: Option<usize>,
+    /// What to suggest if the limits are exceeded.
+    hint: &'static str,
+}
+
+impl RedirectFlavor {
+    fn limits(self) -> Option<RedirectLimits> {
+        match self {
+            Self::Netlify => Some(RedirectLimits {
+                provider: "Netlify",
+                max_static: 10_000,
+                max_dynamic: None,
+                hint: "remove old versions from the release JSON, or drop --no-compact",
+            }),
+            Self::NetlifyToml => Some(RedirectLimits {
+                provider: "Netlify",
+                max_static: 10_000,
+                max_dynamic: None,
+                hint: "remove old versions from the release JSON, or use the netlify flavor, \
+                       which collapses redirects into placeholders",
+            }),
+            Self::Cloudflare => Some(RedirectLimits {
+                provider: "Cloudflare Pages",
+                max_static: 2000,
+                max_dynamic: Some(100),
+                hint: "remove old versions from the release JSON, drop --no-compact, or use the \
+                       cloudflare-worker flavor",
+            }),
+            Self::S3RoutingRules => Some(RedirectLimits {
+                provider: "S3 websites",
+                max_static: 50,
+                max_dynamic: None,
+                hint: "use the cloudfront-function flavor instead",
+            }),
+            Self::CloudfrontFunction
+            | Self::Htaccess
+            | Self::CloudflareWorker
+            | Self::Iis
+            | Self::Nginx
+            | Self::Vercel
+            | Self::Firebase
+            | Self::Custom => None,
+        }
+    }
+
+    /// Check the number of generated rules against the provider's limits.
+    ///
+    /// Exceeding them is a warning, or an error with `strict`.
+    fn check_limits(self, static_count: usize, dynamic_count: usize, strict: bool) -> Result<()> {
+        let Some(limits) = self.limits() else {
+            return Ok(());
+        };
+        let mut problems = Vec::new();
+        match limits.max_dynamic {
+            Some(max_dynamic) => {
+                if static_count > limits.max_static {
+                    problems.push(format!(
+                        "{static_count} static redirects, over the limit of {}",
+                        limits.max_static
+                    ));
+                }
+                if dynamic_count > max_dynamic {
+                    problems.push(format!(
+                        "{dynamic_count} dynamic redirects, over the limit of {max_dynamic}"
+                    ));
+                }
+            }
+            None => {
+                let count = static_count + dynamic_count;
+                if count > limits.max_static {
+                    problems.push(format!(
+                        "{count} redirects, over the limit of {}",
+                        limits.max_static
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let message = format!(
+            "generated {} for {} (to fix this, {})",
+            problems.join(" and "),
+            limits.provider,
+            limits.hint
+        );
+        if strict {
+            bail!(message);
+        }
+        eprintln!("warning: {message}");
+        Ok(())
+    }
+}
+
+/// An HTTP status code for generated redirects.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(crate) enum StatusCode {
+    /// Moved Permanently
+    #[clap(name = "301")]
+    MovedPermanently,
+
+    /// Found
+    #[clap(name = "302")]
+    Found,
+
+    /// See Other
+    #[clap(name = "303")]
+    SeeOther,
+
+    /// Temporary Redirect
+    #[clap(name = "307")]
+    TemporaryRedirect,
+
+    /// Permanent Redirect
+    #[clap(name = "308")]
+    PermanentRedirect,
+}
+
+impl StatusCode {
+    fn code(self) -> u16 {
+        match self {
+            Self::MovedPermanently => 301,
+            Self::Found => 302,
+            Self::SeeOther => 303,
+            Self::TemporaryRedirect => 307,
+            Self::PermanentRedirect => 308,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Args)]
+pub(crate) struct StatusCodeOpts {
+    /// Status code for redirects
+    #[clap(long, value_enum, default_value = "302")]
+    status_code: StatusCode,
+
+    /// Status code for redirects from exact versions, which always point to the same artifacts
+    /// [default: --status-code]
+    #[clap(long, value_enum, value_name = "STATUS_CODE")]
+    version_status_code: Option<StatusCode>,
+
+    /// Status code for redirects from latest and version ranges, which change as releases are
+    /// added [default: --status-code]
+    #[clap(long, value_enum, value_name = "STATUS_CODE")]
+    range_status_code: Option<StatusCode>,
+}
+
+impl StatusCodeOpts {
+    fn code_for(&self, version: &RedirectVersion) -> u16 {
+        match version {
+            RedirectVersion::Latest | RedirectVersion::Channel(_) | RedirectVersion::Range(_) => {
+                self.range_code()
+            }
+            RedirectVersion::Version(_) => self.version_code(),
+        }
+    }
+
+    /// The status code for redirects from latest and version ranges.
+    fn range_code(&self) -> u16 {
+        self.range_status_code.unwrap_or(self.status_code).code()
+    }
+
+    /// The status code for redirects from exact versions.
+    fn version_code(&self) -> u16 {
+        self.version_status_code.unwrap_or(self.status_code).code()
+    }
+}
+
+/// An independent output written by `generate-redirects`.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+pub(crate) enum GeneratedOutput {
+    /// The redirects file for the selected flavor
+    Redirects,
+
+    /// Checksum files next to each artifact path, e.g. /latest/TARGET.FORMAT.sha256 (only
+    /// generated if selected with --only)
+    Checksums,
+
+    /// latest.json and latest-prerelease.json under each prefix, describing the latest version
+    /// without and with pre-releases (only generated if selected with --only)
+    LatestJson,
+}
+
+impl GeneratedOutput {
+    /// Whether this output is generated if --only isn't passed in.
+    fn is_default(self) -> bool {
+        match self {
+            Self::Redirects => true,
+            Self::Checksums | Self::LatestJson => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct OutputSelection {
+    /// Only generate these outputs (comma-separated)
+    #[clap(long, value_enum, value_delimiter = ',', conflicts_with = "skip")]
+    only: Vec<GeneratedOutput>,
+
+    /// Generate the default outputs except these (comma-separated)
+    #[clap(long, value_enum, value_delimiter = ',')]
+    skip: Vec<GeneratedOutput>,
+}
+
+impl OutputSelection {
+    /// The outputs to generate.
+    pub(crate) fn outputs(&self) -> BTreeSet<GeneratedOutput> {
+        if !self.only.is_empty() {
+            return self.only.iter().copied().collect();
+        }
+        GeneratedOutput::value_variants()
+            .iter()
+            .copied()
+            .filter(|output| output.is_default() && !self.skip.contains(output))
+            .collect()
+    }
+}
+
+pub(crate) struct GenerateOptions<'a> {
+    pub(crate) aliases: &'a [PathAlias],
+    pub(crate) auto_aliases: bool,
+    pub(crate) flavor: RedirectFlavor,
+    pub(crate) prefixes: &'a [String],
+    pub(crate) project_prefixes: &'a [ProjectPrefix],
+    pub(crate) status_codes: &'a StatusCodeOpts,
+    pub(crate) force: bool,
+    pub(crate) compact: bool,
+    pub(crate) strict: bool,
+    pub(crate) root_redirect: bool,
+    pub(crate) not_found: Option<&'a str>,
+    pub(crate) template: Option<&'a Utf8Path>,
+    pub(crate) vars: &'a BTreeMap<String, String>,
+    pub(crate) output_opts: &'a OutputOpts,
+    pub(crate) outputs: BTreeSet<GeneratedOutput>,
+}
+
+/// Generate the selected outputs in `out_dir`, returning the files that changed relative to it.
+///
+/// Outputs are independent of each other, so they're generated in parallel.
+pub(crate) fn generate_redirects(
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+    opts: &GenerateOptions<'_>,
+) -> Result<Vec<Utf8PathBuf>> {
+    let mounts = mounts(release_json, opts)?;
+    let auto_aliases = opts.auto_aliases;
+
+    for alias in opts.aliases {
+        if let Some(project) = &alias.project {
+            if !mounts.iter().any(|mount| mount.project_name == project) {
+                eprintln!(
+                    "warning: alias {} is scoped to project {project}, which redirects aren't \
+                     being generated for",
+                    alias.alias,
+                );
+            }
+        }
+    }
+
+    if opts.force
+        && !matches!(
+            opts.flavor,
+            RedirectFlavor::Netlify | RedirectFlavor::NetlifyToml
+        )
+    {
+        eprintln!("warning: --force only applies to the netlify and netlify-toml flavors");
+    }
+    if opts.root_redirect && !opts.flavor.supports(RedirectKind::Root) {
+        eprintln!(
+            "warning: --root-redirect isn't supported by the {:?} flavor, skipping",
+            opts.flavor
+        );
+    }
+    if opts.not_found.is_some() && !opts.flavor.supports(RedirectKind::NotFound) {
+        eprintln!(
+            "warning: --not-found isn't supported by the {:?} flavor, skipping",
+            opts.flavor
+        );
+    }
+
+    let mut redirects = Vec::new();
+    for mount in &mounts {
+        let project = mount.project;
+        let aliases = project_aliases(opts.aliases, mount.project_name, project);
+        let aliases: Vec<&PathAlias> = aliases.iter().collect();
+
+        if let Some(range) = &project.latest {
+            let latest_range_data = &project.ranges[range];
+            let latest_version_data = &latest_range_data.versions[&latest_range_data.latest];
+            if opts.root_redirect {
+                redirects.push(Redirect {
+                    version: RedirectVersion::Latest,
+                    kind: RedirectKind::Root,
+                    from: format!("{}/", mount.prefix),
+                    to: latest_version_data.release_url.clone(),
+                    code: opts.status_codes.code_for(&RedirectVersion::Latest),
+                });
+            }
+            append_redirect_list(
+                RedirectVersion::Latest,
+                (&latest_range_data.latest, latest_version_data),
+                &aliases,
+                auto_aliases,
+                mount.prefix,
+                opts.status_codes,
+                &mut redirects,
+            );
+        }
+
+        let mut skipped_channels: BTreeSet<&str> =
+            project.channels.keys().map(String::as_str).collect();
+        for (channel, version, version_data) in project.redirect_channels() {
+            skipped_channels.remove(channel);
+            append_redirect_list(
+                RedirectVersion::Channel(channel.to_owned()),
+                (version, version_data),
+                &aliases,
+                auto_aliases,
+                mount.prefix,
+                opts.status_codes,
+                &mut redirects,
+            );
+        }
+        for channel in skipped_channels {
+            eprintln!(
+                "warning: skipping channel {channel}: it points to a missing version, or its name \
+                 clashes with latest or a version"
+            );
+        }
+
+        for (range, data) in &project.ranges {
+            if !data.is_prerelease {
+                let version_data = &data.versions[&data.latest];
+                append_redirect_list(
+                    RedirectVersion::Range(*range),
+                    (&data.latest, version_data),
+                    &aliases,
+                    auto_aliases,
+                    mount.prefix,
+                    opts.status_codes,
+                    &mut redirects,
+                );
+            }
+            for (version, version_data) in &data.versions {
+                append_redirect_list(
+                    RedirectVersion::Version(version.clone()),
+                    (version, version_data),
+                    &aliases,
+                    auto_aliases,
+                    mount.prefix,
+                    opts.status_codes,
+                    &mut redirects,
+                );
+            }
+        }
+    }
+
+    // Catch-all rules go last, longest prefix first, so they only match paths that nothing else
+    // does.
+    if let Some(not_found) = opts.not_found {
+        let mut prefixes: Vec<_> = mounts.iter().map(|mount| mount.prefix).collect();
+        prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.len()));
+        prefixes.dedup();
+        for prefix in prefixes {
+            redirects.push(Redirect {
+                version: RedirectVersion::Latest,
+                kind: RedirectKind::NotFound,
+                from: format!("{prefix}/*"),
+                to: not_found.to_owned(),
+                code: 404,
+            });
+        }
+    }
+
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = opts
+            .outputs
+            .iter()
+            .map(|output| {
+                let mounts = &mounts;
+                let redirects = &redirects;
+                scope.spawn(move || match output {
+                    GeneratedOutput::Redirects => {
+                        write_redirects_file(opts, mounts, redirects, out_dir)
+                    }
+                    GeneratedOutput::Checksums => {
+                        sidecars::write_checksum_files(mounts, redirects, out_dir)
+                    }
+                    GeneratedOutput::LatestJson => {
+                        latest_json::write_latest_json_files(mounts, out_dir)
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("output generation thread panicked"))
+            .collect()
+    });
+
+    let mut changed = Vec::new();
+    for result in results {
+        changed.extend(result?);
+    }
+    Ok(changed)
+}
+
+/// A project that redirects are generated for, under a URL prefix.
+struct Mount<'a> {
+    project_name: &'a str,
+    project: &'a MuktiProject,
+    /// The prefix, without a trailing slash.
+    prefix: &'a str,
+}
+
+fn mounts<'a>(
+    release_json: &'a MuktiReleasesJson,
+    opts: &GenerateOptions<'a>,
+) -> Result<Vec<Mount<'a>>> {
+    let mounts = if opts.project_prefixes.is_empty() {
+        let (project_name, project) = single_project(release_json).wrap_err(
+            "to generate redirects for several projects, pass --project-prefix for each one",
+        )?;
+        opts.prefixes
+            .iter()
+            .map(|prefix| Mount {
+                project_name,
+                project,
+                prefix: prefix.trim_end_matches('/'),
+            })
+            .collect()
+    } else {
+        opts.project_prefixes
+            .iter()
+            .map(|project_prefix| {
+                let (project_name, project) = release_json
+                    .projects
+                    .get_key_value(&project_prefix.project)
+                    .ok_or_else(|| {
+                        eyre!(
+                            "project {} not found in the release JSON",
+                            project_prefix.project
+                        )
+                    })?;
+                Ok(Mount {
+                    project_name,
+                    project,
+                    prefix: project_prefix.prefix.trim_end_matches('/'),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut seen = BTreeSet::new();
+    for mount in &mounts {
+        if !seen.insert(mount.prefix) {
+            bail!(
+                "prefix {}/ is used more than once, which would generate conflicting redirects",
+                mount.prefix
+            );
+        }
+    }
+    Ok(mounts)
+}
+
+fn write_redirects_file(
+    opts: &GenerateOptions<'_>,
+    mounts: &[Mount<'_>],
+    redirects: &[Redirect],
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let flavor = opts.flavor;
+    let file_name = match (flavor.file_name(), opts.template) {
+        (Some(file_name), _) => file_name,
+        (None, Some(template)) => custom::output_file_name(template)?,
+        (None, None) => bail!("--flavor {flavor:?} requires --template"),
+    };
+    let supported: Vec<Redirect>;
+    let redirects = if redirects
+        .iter()
+        .all(|redirect| flavor.supports(redirect.kind))
+    {
+        redirects
+    } else {
+        supported = redirects
+            .iter()
+            .filter(|redirect| flavor.supports(redirect.kind))
+            .cloned()
+            .collect();
+        &supported
+    };
+    let mut out = String::with_capacity(4096);
+    if flavor.uses_hash_comments() {
+        writeln!(
+            &mut out,
+            "# Generated by mukti with redirect flavor {:?}\n",
+            flavor
+        )?;
+    }
+
+    match flavor {
+        RedirectFlavor::Netlify | RedirectFlavor::Cloudflare => {
+            let force = if opts.force && matches!(flavor, RedirectFlavor::Netlify) {
+                "!"
+            } else {
+                ""
+            };
+            // Catch-all rules must never be forced, or they'd shadow every file under the prefix.
+            let (not_found, redirects): (Vec<_>, Vec<_>) = redirects
+                .iter()
+                .cloned()
+                .partition(|redirect| redirect.kind == RedirectKind::NotFound);
+            if opts.compact {
+                // Attempt to derive wildcards from the list of redirects.
+                let wildcards = WildcardStore::build(&redirects);
+
+                // First write unmatched/static redirects.
+                for redirect in &wildcards.unmatched {
+                    writeln!(out, "{}{force}", redirect)
+                        .expect("writing to a string is infallible");
+                }
+
+                // Then write wildcards, since they should match less tightly than static
+                // redirects.
+                for wildcard in &wildcards.wildcards {
+                    writeln!(out, "{}{force}", wildcard)
+                        .expect("writing to a string is infallible");
+                }
+                flavor.check_limits(
+                    wildcards.unmatched.len() + not_found.len(),
+                    wildcards.wildcards.len(),
+                    opts.strict,
+                )?;
+            } else {
+                // Just write out the redirect list.
+                for redirect in &redirects {
+                    writeln!(out, "{}{force}", redirect)
+                        .expect("writing to a string is infallible");
+                }
+                flavor.check_limits(redirects.len() + not_found.len(), 0, opts.strict)?;
+            }
+            for redirect in &not_found {
+                writeln!(out, "{}", redirect).expect("writing to a string is infallible");
+            }
+        }
+        RedirectFlavor::NetlifyToml => {
+            let existing_path = out_dir.join(file_name);
+            netlify_toml::write_netlify_toml(redirects, opts.force, &existing_path, &mut out)?;
+            flavor.check_limits(redirects.len(), 0, opts.strict)?;
+        }
+        RedirectFlavor::S3RoutingRules => {
+            flavor.check_limits(redirects.len(), 0, opts.strict)?;
+            aws::write_routing_rules(redirects, flavor, &mut out)?;
+        }
+        RedirectFlavor::CloudfrontFunction => {
+            aws::write_cloudfront_function(redirects, flavor, &mut out)?;
+        }
+        RedirectFlavor::CloudflareWorker => {
+            worker::write_worker(
+                redirects,
+                flavor,
+                mounts,
+                opts.status_codes,
+                opts.vars,
+                &mut out,
+            )?;
+        }
+        RedirectFlavor::Htaccess => {
+            htaccess::write_htaccess(redirects, &mut out);
+        }
+        RedirectFlavor::Iis => {
+            iis::write_web_config(redirects, flavor, &mut out);
+        }
+        RedirectFlavor::Nginx => {
+            nginx::write_locations(redirects, &mut out);
+        }
+        RedirectFlavor::Vercel => {
+            let existing_path = out_dir.join(file_name);
+            vercel::write_vercel_json(redirects, mounts, &existing_path, &mut out)?;
+        }
+        RedirectFlavor::Firebase => {
+            let existing_path = out_dir.join(file_name);
+            firebase::write_firebase_json(redirects, mounts, &existing_path, &mut out)?;
+        }
+        RedirectFlavor::Custom => {
+            let template = opts.template.expect("custom flavor has a template");
+            custom::render_template(template, redirects, mounts, opts.vars, &mut out)?;
+        }
+    }
+
+    let mut changed = Vec::new();
+    if opts
+        .output_opts
+        .write_file(&out_dir.join(file_name), &out)?
+    {
+        changed.push(file_name.into());
+    }
+
+    Ok(changed)
+}
+
+// In a WildcardStore, wildcards and unmatched together cover the full set of redirects
+#[derive(Debug)]
+struct WildcardStore<'a> {
+    wildcards: Vec<Wildcard<'a>>,
+    unmatched: Vec<Redirect>,
+}
+
+impl<'a> WildcardStore<'a> {
+    fn build(redirects: &'a [Redirect]) -> Self {
+        // from_components -> ((kind, to_components) -> list of redirects)
+        let mut url_matches: HashMap<_, HashMap<_, Vec<_>>> = HashMap::new();
+        let mut unmatched = Vec::new();
+
+        for redirect in redirects {
+            // Only consider full versions.
+            if !matches!(redirect.version, RedirectVersion::Version(_)) {
+                unmatched.push(redirect.clone());
+                continue;
+            }
+
+            let version_str = redirect.version.to_string();
+            let (from_start, from_end) = match redirect.from.split_once(&version_str) {
+                Some((start, end)) => (start, end),
+                None => {
+                    unmatched.push(redirect.clone());
+                    continue;
+                }
+            };
+
+            let to_components: Vec<_> = redirect.to.split(&version_str).collect();
+
+            url_matches
+                .entry((from_start, from_end))
+                .or_default()
+                .entry((redirect.kind, to_components))
+                .or_default()
+                .push(redirect);
+        }
+
+        // For each from key, look through all the to keys and find the most common one.
+        let mut wildcards = Vec::new();
+
+        for ((from_start, from_end), mut to_maps) in url_matches {
+            // (kind, to_components, redirects)
+            let mut best_to: Option<(RedirectKind, &[_], &[_])> = None;
+
+            for ((kind, to_components), redirects) in &to_maps {
+                if let Some((_, _, best_redirects)) = &best_to {
+                    if redirects.len() > best_redirects.len() {
+                        best_to = Some((*kind, to_components, redirects));
+                    }
+                } else {
+                    best_to = Some((*kind, to_components, redirects));
+                }
+            }
+
+            if let Some((kind, to_components, best_redirects)) = best_to {
+                let wildcard = Wildcard {
+                    kind,
+                    // Only exact versions are matched, and they all share a status code.
+                    code: best_redirects[0].code,
+                    from_components: (from_start, from_end),
+                    to_components: to_components.to_vec(),
+                    matching_redirects: best_redirects.to_vec(),
+                };
+                wildcards.push(wildcard);
+
+                // Everything here is covered by the wildcard. (to_vec is required to avoid
+                // borrowing issues.)
+                let ktc = (kind, to_components.to_vec());
+                to_maps.remove(&ktc);
+            }
+
+            // Anything left goes into unmatched.
+            for (_, redirects) in to_maps {
+                unmatched.extend(redirects.into_iter().cloned());
+            }
+        }
+
+        // Sort the wildcard and unmatched lists.
+        wildcards.sort_unstable_by_key(|wildcard| (wildcard.kind, wildcard.from_components));
+        unmatched.sort();
+
+        for wildcard in &wildcards {
+            eprintln!(
+                "found wildcard (matches {} redirects): {wildcard}",
+                wildcard.matching_redirects.len()
+            );
+        }
+
+        Self {
+            wildcards,
+            unmatched,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Wildcard<'a> {
+    // The version can only show up once in the redirect "from", therefore two components
+    kind: RedirectKind,
+    code: u16,
+    from_components: (&'a str, &'a str),
+    to_components: Vec<&'a str>,
+    matching_redirects: Vec<&'a Redirect>,
+}
+
+impl<'a> Wildcard<'a> {
+    const VERSION_PLACEHOLDER: &'static str = ":version";
+}
+
+impl<'a> fmt::Display for Wildcard<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (from_start, from_end) = self.from_components;
+        let to = self.to_components.join(Self::VERSION_PLACEHOLDER);
+
+        write!(
+            f,
+            "{from_start}{}{from_end} {to} {}",
+            Self::VERSION_PLACEHOLDER,
+            self.code,
+        )
+    }
+}
+
+/// Append redirects for `version`, which resolves to the concrete version in `version_data`.
+fn append_redirect_list(
+    version: RedirectVersion,
+    version_data: (&Version, &ReleaseVersionData),
+    aliases: &[&PathAlias],
+    auto_aliases: bool,
+    prefix: &str,
+    status_codes: &StatusCodeOpts,
+    out: &mut Vec<Redirect>,
+) {
+    let code = status_codes.code_for(&version);
+    let (concrete_version, version_data) = version_data;
+    let aliases: Vec<&PathAlias> = aliases
+        .iter()
+        .copied()
+        .filter(|alias| alias.applies_to_version(concrete_version))
+        .collect();
+    let auto_aliases = if auto_aliases {
+        auto_aliases::auto_aliases(version_data, &aliases)
+    } else {
+        Vec::new()
+    };
+
+    // Warn about aliases that would be dead paths, once per concrete version.
+    if matches!(version, RedirectVersion::Version(_)) {
+        for alias in &aliases {
+            let has_location = version_data
+                .redirect_locations()
+                .any(|location| alias.matches_location(location));
+            if !has_location {
+                eprintln!(
+                    "warning: alias {} skipped for version {}, which has no {}:{} artifact \
+                     (scope the alias with @REQ to avoid this)",
+                    alias.alias, concrete_version, alias.target, alias.format,
+                );
+            }
+        }
+    }
+
+    out.push(Redirect {
+        version: version.clone(),
+        kind: RedirectKind::Release,
+        from: format!("{}/{}/release", prefix, version),
+        to: version_data.release_url.clone(),
+        code,
+    });
+
+    for location in version_data.redirect_locations() {
+        out.push(Redirect {
+            version: version.clone(),
+            kind: RedirectKind::Location,
+            from: format!(
+                "{}/{}/{}.{}",
+                prefix, version, location.target, location.format
+            ),
+            to: location.url.clone(),
+            code,
+        });
+        for alias in aliases
+            .iter()
+            .copied()
+            .chain(&auto_aliases)
+            .filter(|alias| alias.matches_location(location))
+        {
+            out.push(Redirect {
+                version: version.clone(),
+                kind: RedirectKind::Alias,
+                from: format!("{}/{}/{}", prefix, version, alias.name_for(location)),
+                to: location.url.clone(),
+                code,
+            });
+        }
+    }
+
+    for location in version_data.debug_symbols() {
+        out.push(Redirect {
+            version: version.clone(),
+            kind: RedirectKind::DebugSymbols,
+            from: format!("{}/{}/{}-debug-symbols", prefix, version, location.target),
+            to: location.url.clone(),
+            code,
+        });
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+struct Redirect {
+    version: RedirectVersion,
+    kind: RedirectKind,
+    from: String,
+    to: String,
+    code: u16,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+enum RedirectKind {
+    // Order here determines sort order for `Redirect`.
+    Root,
+    Release,
+    Location,
+    Alias,
+    DebugSymbols,
+    NotFound,
+}
+
+impl Redirect {
+    /// The prefix this redirect was generated under, without a trailing slash.
+    fn prefix(&self) -> &str {
+        match self.kind {
+            RedirectKind::Root => self.from.trim_end_matches('/'),
+            RedirectKind::NotFound => self.from.trim_end_matches("/*"),
+            RedirectKind::Release
+            | RedirectKind::Location
+            | RedirectKind::Alias
+            | RedirectKind::DebugSymbols => self.split_from().0,
+        }
+    }
+
+    /// Split the path being redirected from into the prefix and the part after the version, e.g.
+    /// `x86_64-unknown-linux-gnu.tar.gz`.
+    fn split_from(&self) -> (&str, &str) {
+        self.from
+            .rsplit_once(&format!("/{}/", self.version))
+            .expect("redirect paths are of the form PREFIX/VERSION/FILE")
+    }
+}
+
+impl fmt::Display for Redirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.from, self.to, self.code)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+enum RedirectVersion {
+    Latest,
+    Channel(String),
+    Range(VersionRange),
+    Version(Version),
+}
+
+impl fmt::Display for RedirectVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => write!(f, "latest"),
+            Self::Channel(channel) => write!(f, "{}", channel),
+            Self::Range(range) => write!(f, "{}", range),
+            Self::Version(version) => write!(f, "{}", version),
+        }
+    }
+}
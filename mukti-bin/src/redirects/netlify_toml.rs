@@ -0,0 +1,97 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Netlify netlify.toml output.
+//!
+//! netlify.toml usually holds build settings and hand-written rules as well, so generated
+//! `[[redirects]]` tables are written between marker comments. An existing file keeps everything
+//! outside the markers, with the markers appended at the end the first time. Since a TOML table
+//! continues until the next header, any keys added by hand after the end marker must be under a
+//! table header of their own.
+
+use super::{Redirect, RedirectKind};
+use camino::Utf8Path;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use std::fmt::Write as _;
+
+const BEGIN_MARKER: &str = "# BEGIN mukti-generated redirects";
+const END_MARKER: &str = "# END mukti-generated redirects";
+
+pub(super) fn write_netlify_toml(
+    redirects: &[Redirect],
+    force: bool,
+    existing_path: &Utf8Path,
+    out: &mut String,
+) -> Result<()> {
+    let existing = match std::fs::read_to_string(existing_path) {
+        Ok(contents) => contents
+            .trim_start_matches('\u{feff}')
+            .replace("\r\n", "\n"),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("failed to read {existing_path}"));
+        }
+    };
+
+    let (before, after) = match existing.find(BEGIN_MARKER) {
+        Some(begin) => {
+            let Some(end) = existing[begin..].find(END_MARKER) else {
+                bail!("{existing_path} has a `{BEGIN_MARKER}` line without a matching end");
+            };
+            let after = &existing[begin + end + END_MARKER.len()..];
+            (
+                &existing[..begin],
+                after.strip_prefix('\n').unwrap_or(after),
+            )
+        }
+        None => (&existing[..], ""),
+    };
+
+    out.push_str(before);
+    // Separate generated tables from existing content with a blank line.
+    if !before.is_empty() && !before.ends_with("\n\n") {
+        out.push_str(if before.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    writeln!(out, "{BEGIN_MARKER}").expect("writing to a string is infallible");
+    for redirect in redirects {
+        writeln!(
+            out,
+            "[[redirects]]\n  from = {}\n  to = {}\n  status = {}",
+            basic_string(&redirect.from),
+            basic_string(&redirect.to),
+            redirect.code,
+        )
+        .expect("writing to a string is infallible");
+        // Catch-all rules must never be forced, or they'd shadow every file under the prefix.
+        if force && redirect.kind != RedirectKind::NotFound {
+            out.push_str("  force = true\n");
+        }
+        out.push('\n');
+    }
+    writeln!(out, "{END_MARKER}").expect("writing to a string is infallible");
+    out.push_str(after);
+
+    // Catch anything the merge broke, so Netlify doesn't fail the deploy instead.
+    toml::from_str::<toml::Table>(out).wrap_err_with(|| {
+        format!("merging redirects into {existing_path} produced invalid TOML")
+    })?;
+    Ok(())
+}
+
+/// Quote `input` as a TOML basic string.
+fn basic_string(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 2);
+    out.push('"');
+    for c in input.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                write!(out, "\\u{:04X}", c as u32).expect("writing to a string is infallible")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,38 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! nginx configuration output.
+//!
+//! The generated file only contains `location` blocks, so it can be `include`d into an existing
+//! `server` block.
+
+use super::Redirect;
+use std::fmt::Write as _;
+
+pub(super) fn write_locations(redirects: &[Redirect], out: &mut String) {
+    for redirect in redirects {
+        // Exact-match locations take precedence over prefix and regex locations in the rest of
+        // the server block.
+        writeln!(
+            out,
+            "location = {} {{\n    return {} {};\n}}",
+            quote(&redirect.from),
+            redirect.code,
+            quote(&redirect.to),
+        )
+        .expect("writing to a string is infallible");
+    }
+}
+
+fn quote(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 2);
+    out.push('"');
+    for c in input.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
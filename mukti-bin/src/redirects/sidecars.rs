@@ -0,0 +1,67 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checksum sidecar files, e.g. `/{version}/{target}.{format}.sha256`, so installers can fetch
+//! checksums from the same stable paths as the artifacts they're for.
+//!
+//! Sidecars are static files rather than redirects, and are written in the format `sha256sum`
+//! and friends produce, so they can be checked with e.g. `sha256sum --check`.
+
+use super::{Mount, Redirect, RedirectKind};
+use crate::output::OutputOpts;
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::ReleaseLocation;
+use std::collections::HashMap;
+
+pub(super) fn write_checksum_files(
+    mounts: &[Mount<'_>],
+    redirects: &[Redirect],
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let locations: HashMap<&str, &ReleaseLocation> = mounts
+        .iter()
+        .flat_map(|mount| mount.project.all_versions())
+        .flat_map(|(_, version_data)| &version_data.locations)
+        .map(|location| (location.url.as_str(), location))
+        .collect();
+
+    // Checksum tools expect LF line endings and no BOM, whatever the other outputs use.
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+    for redirect in redirects {
+        if !matches!(
+            redirect.kind,
+            RedirectKind::Location | RedirectKind::Alias | RedirectKind::DebugSymbols
+        ) {
+            continue;
+        }
+        let Some(location) = locations.get(redirect.to.as_str()) else {
+            continue;
+        };
+        let file_name = location
+            .url
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&location.target);
+
+        for (algorithm, digest) in &location.checksums {
+            let rel_path = Utf8PathBuf::from(format!(
+                "{}.{algorithm}",
+                redirect.from.trim_start_matches('/')
+            ));
+            let path = out_dir.join(&rel_path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+            }
+            let contents = format!("{}  {file_name}\n", digest.0);
+            if output_opts.write_file(&path, &contents)? {
+                changed.push(rel_path);
+            }
+        }
+    }
+
+    Ok(changed)
+}
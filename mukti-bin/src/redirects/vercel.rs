@@ -0,0 +1,48 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Vercel vercel.json output, merged into the top-level `redirects` array.
+
+use super::{json_config, Mount, Redirect};
+use camino::Utf8Path;
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::{json, Value};
+
+pub(super) fn write_vercel_json(
+    redirects: &[Redirect],
+    mounts: &[Mount<'_>],
+    existing_path: &Utf8Path,
+    out: &mut String,
+) -> Result<()> {
+    let mut config = json_config::read_config(existing_path)?;
+    json_config::merge_redirects(
+        &mut config,
+        "redirects",
+        mounts,
+        redirects.iter().map(vercel_redirect),
+    )
+    .wrap_err_with(|| format!("failed to merge redirects into {existing_path}"))?;
+    json_config::write_config(&config, out)
+}
+
+fn vercel_redirect(redirect: &Redirect) -> Value {
+    // Vercel only supports 307 and 308 through `permanent`, so other codes are passed through as
+    // `statusCode`.
+    match redirect.code {
+        302 | 307 => json!({
+            "source": redirect.from,
+            "destination": redirect.to,
+            "permanent": false,
+        }),
+        301 | 308 => json!({
+            "source": redirect.from,
+            "destination": redirect.to,
+            "permanent": true,
+        }),
+        code => json!({
+            "source": redirect.from,
+            "destination": redirect.to,
+            "statusCode": code,
+        }),
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Cloudflare Worker output: a self-contained module Worker implementing the redirects.
+//!
+//! The redirects known at generation time are embedded in the script. If the Worker has a
+//! `RELEASES_JSON_URL` variable, it also fetches the release JSON (cached at the edge for a few
+//! minutes) and resolves paths against it at request time, so `latest`, version ranges and new
+//! versions work without redeploying the Worker. Aliases are resolved to the `TARGET.FORMAT`
+//! they pointed to for the newest version that had them, separately for each prefix.
+//!
+//! Variables passed in with `--var` are embedded as the `VARS` object, for code added to the
+//! Worker after it's generated.
+
+use super::{Mount, Redirect, RedirectFlavor, RedirectKind, StatusCodeOpts};
+use color_eyre::eyre::Result;
+use serde_json::json;
+use std::{collections::BTreeMap, fmt::Write as _};
+
+pub(super) fn write_worker(
+    redirects: &[Redirect],
+    flavor: RedirectFlavor,
+    mounts: &[Mount<'_>],
+    status_codes: &StatusCodeOpts,
+    vars: &BTreeMap<String, String>,
+    out: &mut String,
+) -> Result<()> {
+    let map: BTreeMap<_, _> = redirects
+        .iter()
+        .map(|redirect| (&redirect.from, (redirect.code, &redirect.to)))
+        .collect();
+
+    // Map each alias to the file name of the location it redirects to, per prefix. Redirects are
+    // ordered oldest version first, so newer versions win.
+    let mut location_files = BTreeMap::new();
+    for redirect in redirects {
+        if redirect.kind == RedirectKind::Location {
+            let (_, file) = redirect.split_from();
+            location_files.insert((&redirect.version, &redirect.to), file);
+        }
+    }
+    let mut aliases: BTreeMap<&str, BTreeMap<&str, &str>> = BTreeMap::new();
+    for redirect in redirects {
+        if redirect.kind == RedirectKind::Alias {
+            if let Some(file) = location_files.get(&(&redirect.version, &redirect.to)) {
+                let (prefix, alias) = redirect.split_from();
+                aliases.entry(prefix).or_default().insert(alias, *file);
+            }
+        }
+    }
+
+    // Longer prefixes are checked first, so nested prefixes match correctly.
+    let mut mounts: Vec<_> = mounts.iter().collect();
+    mounts.sort_by_key(|mount| std::cmp::Reverse(mount.prefix.len()));
+    let mounts: Vec<_> = mounts
+        .into_iter()
+        .map(|mount| {
+            json!({
+                "prefix": mount.prefix,
+                "project": mount.project_name,
+                "aliases": aliases.get(mount.prefix).cloned().unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    writeln!(
+        out,
+        "// Generated by mukti with redirect flavor {:?}\n",
+        flavor
+    )
+    .expect("writing to a string is infallible");
+    writeln!(
+        out,
+        "const MOUNTS = {};\nconst RANGE_STATUS = {};\nconst VERSION_STATUS = {};\n\
+         const REDIRECTS = {};\nconst VARS = {};",
+        serde_json::to_string(&mounts)?,
+        status_codes.range_code(),
+        status_codes.version_code(),
+        serde_json::to_string(&map)?,
+        serde_json::to_string(vars)?,
+    )
+    .expect("writing to a string is infallible");
+    out.push_str(WORKER_JS);
+    Ok(())
+}
+
+const WORKER_JS: &str = r#"
+// How long the edge caches the release JSON for, in seconds.
+const RELEASES_JSON_TTL = 300;
+
+// Artifact kinds that get redirects. Locations without a kind are archives, and artifacts for the
+// "source" pseudo-target get redirects whatever their kind.
+const INSTALLABLE_KINDS = ["archive", "installer"];
+const DEBUG_SYMBOLS_SUFFIX = "-debug-symbols";
+
+export default {
+  async fetch(request, env) {
+    const { pathname } = new URL(request.url);
+    let redirect = null;
+    if (env.RELEASES_JSON_URL) {
+      try {
+        redirect = await resolve(pathname, env.RELEASES_JSON_URL);
+      } catch (err) {
+        console.error(`failed to resolve ${pathname}, using embedded redirects: ${err}`);
+      }
+    }
+    redirect = redirect ?? REDIRECTS[pathname] ?? null;
+    if (redirect === null) {
+      return new Response("Not Found", { status: 404 });
+    }
+    const [status, location] = redirect;
+    return Response.redirect(location, status);
+  },
+};
+
+async function resolve(pathname, releasesJsonUrl) {
+  const mount = MOUNTS.find((mount) => pathname.startsWith(`${mount.prefix}/`));
+  if (!mount) {
+    return null;
+  }
+  const [version, ...rest] = pathname.slice(mount.prefix.length + 1).split("/");
+  const file = rest.join("/");
+
+  const response = await fetch(releasesJsonUrl, {
+    cf: { cacheTtl: RELEASES_JSON_TTL, cacheEverything: true },
+  });
+  if (!response.ok) {
+    throw new Error(`fetching ${releasesJsonUrl} failed with ${response.status}`);
+  }
+  const project = (await response.json()).projects?.[mount.project];
+  const found = project ? findVersion(project, version) : null;
+  if (!found) {
+    return null;
+  }
+  const [foundVersion, versionData] = found;
+  const urlFor = (location) => locationUrl(project, mount.project, foundVersion, location);
+
+  const status = version === "latest" || Object.hasOwn(project.ranges, version) ||
+      Object.hasOwn(project.channels ?? {}, version)
+    ? RANGE_STATUS
+    : VERSION_STATUS;
+  if (file === "release") {
+    return [status, versionData.release_url];
+  }
+  if (file.endsWith(DEBUG_SYMBOLS_SUFFIX)) {
+    const target = file.slice(0, -DEBUG_SYMBOLS_SUFFIX.length);
+    const location = versionData.locations.find(
+      (location) => location.kind === "debug-symbols" && location.target === target,
+    );
+    return location ? [status, urlFor(location)] : null;
+  }
+  const name = mount.aliases[file] ?? file;
+  const location = versionData.locations.find(
+    (location) =>
+      (INSTALLABLE_KINDS.includes(location.kind ?? "archive") || location.target === "source") &&
+      `${location.target}.${location.format}` === name,
+  );
+  return location ? [status, urlFor(location)] : null;
+}
+
+// Locations without a URL use the project's URL template, and URLs can be relative to the
+// project's base URL.
+function locationUrl(project, name, version, location) {
+  const url = location.url ||
+    (project.url_template ?? "")
+      .replaceAll("{name}", name)
+      .replaceAll("{version}", version)
+      .replaceAll("{target}", location.target)
+      .replaceAll("{format}", location.format);
+  if (!project.base_url || /^[A-Za-z0-9+.-]+:/.test(url)) {
+    return url;
+  }
+  return `${project.base_url.replace(/\/+$/, "")}/${url.replace(/^\/+/, "")}`;
+}
+
+function findVersion(project, version) {
+  if (version === "latest") {
+    const range = project.latest ? project.ranges[project.latest] : null;
+    return range ? [range.latest, range.versions[range.latest]] : null;
+  }
+  const range = project.ranges[version];
+  if (range) {
+    return range.is_prerelease ? null : [range.latest, range.versions[range.latest]];
+  }
+  if (Object.hasOwn(project.channels ?? {}, version)) {
+    version = project.channels[version];
+  }
+  for (const range of Object.values(project.ranges)) {
+    if (Object.hasOwn(range.versions, version)) {
+      return [version, range.versions[version]];
+    }
+  }
+  return null;
+}
+"#;
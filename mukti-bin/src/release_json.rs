@@ -3,26 +3,34 @@
 
 //! Add and update to release JSON.
 
-use crate::checksums::ArchiveWithChecksums;
-use atomicwrites::{AtomicFile, OverwriteBehavior};
+use crate::{
+    checksums::ArchiveWithChecksums,
+    command::{Archive, AttestationSuffix, MinPlatform, SignatureSuffix},
+};
 use camino::Utf8Path;
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+use clap::ValueEnum;
 use color_eyre::eyre::{bail, Result, WrapErr};
 use mukti_metadata::{
-    MuktiReleasesJson, ReleaseLocation, ReleaseRangeData, ReleaseStatus, ReleaseVersionData,
-    VersionRange,
+    ArtifactKind, Format, MuktiProject, MuktiReleasesJson, ReleaseLocation, ReleaseRangeData,
+    ReleaseStatus, ReleaseVersionData, VersionRange, WriteReleasesJsonError,
+    CURRENT_SCHEMA_VERSION,
 };
 use semver::Version;
-use std::{collections::BTreeMap, io::BufWriter};
+use std::collections::BTreeMap;
 
 /// Read the releases.json file.
+///
+/// If the file is the index of a sharded layout, the shards it points to are read as well.
 pub(crate) fn read_release_json(path: &Utf8Path, allow_missing: bool) -> Result<MuktiReleasesJson> {
     let release_json: MuktiReleasesJson = if path.exists() {
-        let json = std::fs::read_to_string(path)
-            .wrap_err_with(|| format!("failed to read releases JSON file at {}", path))?;
-        serde_json::from_str(&json)
-            .wrap_err_with(|| format!("failed to deserialize releases JSON at {}", path))?
+        MuktiReleasesJson::from_path(path)
+            .wrap_err_with(|| format!("failed to read releases JSON at {}", path))?
     } else if allow_missing {
-        MuktiReleasesJson::default()
+        MuktiReleasesJson {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            ..Default::default()
+        }
     } else {
         bail!("releases JSON not found at {}", path);
     };
@@ -30,11 +38,91 @@ pub(crate) fn read_release_json(path: &Utf8Path, allow_missing: bool) -> Result<
     Ok(release_json)
 }
 
+/// What `add-release` does if the version being added already exists.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum OnConflict {
+    /// Fail without changing anything
+    Error,
+
+    /// Replace the existing version's locations and release URL, keeping its status, release date
+    /// and metadata, and any notes, changelog URL, commit or license that aren't passed in again
+    #[default]
+    Replace,
+
+    /// Keep the existing version's data, adding locations for new targets and formats only
+    MergeLocations,
+}
+
+/// Check whether adding `version` conflicts with an existing version, before any archives are
+/// fetched.
+///
+/// With [`OnConflict::MergeLocations`], archives whose target and format already exist are
+/// removed from `archives`.
+pub(crate) fn check_conflict(
+    release_json: &MuktiReleasesJson,
+    version: &Version,
+    on_conflict: OnConflict,
+    archives: &mut Vec<Archive>,
+) -> Result<()> {
+    // A missing or empty release JSON can't conflict with anything.
+    let Some(project) = release_json.projects.values().next() else {
+        return Ok(());
+    };
+    let Some((existing_version, version_data)) = project.get_version_data(version) else {
+        return Ok(());
+    };
+
+    match on_conflict {
+        OnConflict::Error => bail!(
+            "version {existing_version} already exists in the releases JSON \
+             (pass in --on-conflict replace or merge-locations to update it)"
+        ),
+        OnConflict::Replace => {
+            eprintln!("replacing existing version {existing_version}");
+            if version_data.status.is_yanked() {
+                eprintln!("version {existing_version} is yanked, and stays yanked");
+            }
+        }
+        OnConflict::MergeLocations => {
+            archives.retain(|archive| {
+                let exists = version_data.locations.iter().any(|location| {
+                    location.target == archive.target_format.target
+                        && location.format.as_str() == archive.target_format.format
+                        && location.kind == archive.kind
+                });
+                if exists {
+                    eprintln!(
+                        "skipping {}: version {existing_version} already has {}:{}",
+                        archive.name, archive.target_format.target, archive.target_format.format
+                    );
+                }
+                !exists
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Information about a version being added, other than its archives.
+pub(crate) struct ReleaseDetails {
+    pub(crate) release_url: String,
+    pub(crate) release_date: DateTime<FixedOffset>,
+    pub(crate) notes: Option<String>,
+    pub(crate) changelog_url: Option<String>,
+    pub(crate) commit: Option<String>,
+    pub(crate) license: Option<String>,
+    pub(crate) signatures: Vec<SignatureSuffix>,
+    pub(crate) attestations: Vec<AttestationSuffix>,
+    pub(crate) min_platforms: Vec<MinPlatform>,
+}
+
 pub(crate) fn update_release_json(
     release_json: &mut MuktiReleasesJson,
-    release_url: &str,
     version: &Version,
+    details: ReleaseDetails,
     archives: Vec<ArchiveWithChecksums>,
+    on_conflict: OnConflict,
     path: &Utf8Path,
 ) -> Result<()> {
     if archives.is_empty() {
@@ -42,21 +130,12 @@ pub(crate) fn update_release_json(
         return Ok(());
     }
 
-    if release_json.projects.len() != 1 {
-        bail!(
-            "mukti-bin currently only supports one project, {} found",
-            release_json.projects.len()
-        );
-    }
-
-    let project = release_json
-        .projects
-        .values_mut()
-        .next()
-        .expect("release_json has one project");
+    let (_, project) = single_project_mut(release_json)?;
 
     // Read the release JSON file.
     let range = VersionRange::from_version(version);
+    // If latest is pinned, don't advance the latest version in the pinned range.
+    let latest_pinned_range = project.latest.filter(|_| project.latest_pinned);
     {
         let data = project
             .ranges
@@ -64,82 +143,276 @@ pub(crate) fn update_release_json(
             .or_insert_with(|| ReleaseRangeData {
                 latest: version.clone(),
                 is_prerelease: !version.pre.is_empty(),
+                eol_date: None,
                 versions: BTreeMap::new(),
+                extra: BTreeMap::new(),
             });
 
         let locations: Vec<_> = archives
             .into_iter()
             .map(|archive| {
-                let checksums = match archive.checksums {
-                    Ok(checksums) => checksums.to_checksum_map(),
+                let (checksums, size) = match archive.checksums {
+                    Ok(checksums) => (checksums.to_checksum_map(), checksums.size),
                     Err(e) => {
                         eprintln!(
                             "failed to compute checksums for {}: {}",
                             archive.archive.name, e
                         );
-                        BTreeMap::new()
+                        (BTreeMap::new(), None)
                     }
                 };
 
-                ReleaseLocation {
+                let mut location = ReleaseLocation {
                     target: archive.archive.target_format.target.clone(),
-                    format: archive.archive.target_format.format.clone(),
-                    url: archive.url,
+                    format: Format::from(archive.archive.target_format.format.as_str()),
+                    kind: archive.archive.kind.clone(),
+                    url: archive.url.clone(),
+                    mirror_urls: Vec::new(),
                     checksums,
+                    size,
+                    // Signatures aren't themselves signed.
+                    signatures: if archive.archive.kind == ArtifactKind::Signature {
+                        Vec::new()
+                    } else {
+                        details
+                            .signatures
+                            .iter()
+                            .map(|signature| signature.entry_for(&archive.url))
+                            .collect()
+                    },
+                    // Attestations are only published for the artifacts users install.
+                    attestations: if archive.archive.kind.is_installable() {
+                        details
+                            .attestations
+                            .iter()
+                            .map(|attestation| attestation.entry_for(&archive.url))
+                            .collect()
+                    } else {
+                        Vec::new()
+                    },
+                    min_glibc: None,
+                    min_macos: None,
+                    min_windows: None,
+                    extra: BTreeMap::new(),
+                };
+                for min_platform in &details.min_platforms {
+                    min_platform.apply(&mut location);
                 }
+                location
             })
             .collect();
-        data.versions.insert(
-            version.clone(),
-            ReleaseVersionData {
-                release_url: release_url.to_owned(),
-                status: ReleaseStatus::Active,
-                locations,
-                metadata: serde_json::Value::Null,
-            },
-        );
-
-        // Look for the latest release that isn't a pre-release.
-        // TODO: also consider yanked versions here.
-        let latest_non_prerelease = data
+        // The existing version may only match up to build metadata, e.g. 1.2.3 when adding
+        // 1.2.3+b, so find it by precedence rather than by key.
+        let existing_version = data
             .versions
             .keys()
-            .rev()
-            .find(|version| version.pre.is_empty());
-        match latest_non_prerelease {
-            Some(version) => {
-                data.latest = version.clone();
-                data.is_prerelease = false;
+            .find(|existing| existing.cmp_precedence(version).is_eq())
+            .cloned();
+        let mut replaced = None;
+        let existing = match existing_version {
+            Some(existing_version) if on_conflict == OnConflict::MergeLocations => {
+                data.versions.get_mut(&existing_version)
+            }
+            Some(existing_version) => {
+                replaced = data.versions.remove(&existing_version);
+                // Keep a pinned latest pointing at this version.
+                if data.latest == existing_version {
+                    data.latest = version.clone();
+                }
+                None
+            }
+            None => None,
+        };
+        match existing {
+            Some(existing) => {
+                existing.locations.extend(locations);
+                if details.notes.is_some() {
+                    existing.notes = details.notes;
+                }
+                if details.changelog_url.is_some() {
+                    existing.changelog_url = details.changelog_url;
+                }
+                if details.commit.is_some() {
+                    existing.commit = details.commit;
+                }
+                if details.license.is_some() {
+                    existing.license = details.license;
+                }
             }
             None => {
-                data.latest = data
-                    .versions
-                    .keys()
-                    .next_back()
-                    .expect("we just added a release so this can't be empty")
-                    .clone();
-                data.is_prerelease = true;
+                let mut version_data = ReleaseVersionData {
+                    release_url: details.release_url,
+                    status: ReleaseStatus::Active,
+                    locations,
+                    release_date: Some(
+                        details
+                            .release_date
+                            .to_rfc3339_opts(SecondsFormat::Secs, true),
+                    ),
+                    notes: details.notes,
+                    changelog_url: details.changelog_url,
+                    commit: details.commit,
+                    license: details.license,
+                    metadata: serde_json::Value::Null,
+                    extra: BTreeMap::new(),
+                };
+                // Replacing a version only replaces its artifacts: a yanked version stays yanked,
+                // and it keeps the date it was first released.
+                if let Some(replaced) = replaced {
+                    version_data.status = replaced.status;
+                    version_data.release_date = replaced.release_date.or(version_data.release_date);
+                    version_data.notes = version_data.notes.or(replaced.notes);
+                    version_data.changelog_url =
+                        version_data.changelog_url.or(replaced.changelog_url);
+                    version_data.commit = version_data.commit.or(replaced.commit);
+                    version_data.license = version_data.license.or(replaced.license);
+                    version_data.metadata = replaced.metadata;
+                    version_data.extra = replaced.extra;
+                }
+                data.versions.insert(version.clone(), version_data);
             }
         }
+
+        let new_latest = compute_range_latest(data);
+        if latest_pinned_range == Some(range) {
+            eprintln!(
+                "latest is pinned to {}, not advancing it to {}",
+                data.latest, new_latest
+            );
+        } else {
+            data.latest = new_latest;
+        }
     }
 
     // Check if there's a newer release.
-    let latest_range = project
+    if !project.latest_pinned {
+        project.latest = compute_project_latest(project);
+    }
+
+    write_releases_json(release_json, path)?;
+
+    Ok(())
+}
+
+/// Compute the latest version in a range, updating `is_prerelease` in the process.
+///
+/// Panics if the range has no versions.
+pub(crate) fn compute_range_latest(data: &mut ReleaseRangeData) -> Version {
+    // Look for the latest release that isn't a pre-release, preferring ones that aren't yanked.
+    let non_prereleases = || data.versions.iter().rev().filter(|(v, _)| v.pre.is_empty());
+    let latest_non_prerelease = non_prereleases()
+        .find(|(_, version_data)| !version_data.status.is_yanked())
+        .or_else(|| non_prereleases().next());
+    match latest_non_prerelease {
+        Some((version, _)) => {
+            data.is_prerelease = false;
+            version.clone()
+        }
+        None => {
+            data.is_prerelease = true;
+            data.versions
+                .keys()
+                .next_back()
+                .expect("range must have at least one version")
+                .clone()
+        }
+    }
+}
+
+/// Compute the latest range in the project that isn't a pre-release.
+pub(crate) fn compute_project_latest(project: &MuktiProject) -> Option<VersionRange> {
+    project
         .ranges
         .iter()
         .filter_map(|(range, data)| (!data.is_prerelease).then_some(*range))
-        .max();
-    project.latest = latest_range;
+        .max()
+}
 
-    write_releases_json(release_json, path)?;
+/// Manually point `latest` at a particular version, optionally pinning it there.
+pub(crate) fn set_latest(
+    release_json: &mut MuktiReleasesJson,
+    version: &Version,
+    pin: bool,
+) -> Result<()> {
+    let (_, project) = single_project_mut(release_json)?;
+
+    let range = VersionRange::from_version(version);
+    let Some(data) = project.ranges.get_mut(&range) else {
+        bail!("version {version} not found in releases JSON");
+    };
+    let Some((version, version_data)) = data
+        .versions
+        .iter()
+        .find(|(v, _)| v.cmp_precedence(version).is_eq())
+    else {
+        bail!("version {version} not found in releases JSON");
+    };
+    if version_data.status.is_yanked() {
+        bail!("version {version} is yanked and can't be made the latest version");
+    }
+    if !version.pre.is_empty() {
+        bail!("version {version} is a pre-release and can't be made the latest version");
+    }
+
+    eprintln!(
+        "setting latest to {version}{}",
+        if pin { " (pinned)" } else { "" }
+    );
+    data.latest = version.clone();
+    data.is_prerelease = false;
+    project.latest = Some(range);
+    project.latest_pinned = pin;
 
     Ok(())
 }
 
+/// Return the only project in the release JSON, or an error if there isn't exactly one.
+pub(crate) fn single_project(release_json: &MuktiReleasesJson) -> Result<(&str, &MuktiProject)> {
+    if release_json.projects.len() != 1 {
+        bail!(
+            "mukti-bin currently only supports one project, {} found",
+            release_json.projects.len()
+        );
+    }
+
+    let (name, project) = release_json
+        .projects
+        .iter()
+        .next()
+        .expect("release_json has one project");
+    Ok((name, project))
+}
+
+/// Mutable version of [`single_project`].
+pub(crate) fn single_project_mut(
+    release_json: &mut MuktiReleasesJson,
+) -> Result<(&str, &mut MuktiProject)> {
+    if release_json.projects.len() != 1 {
+        bail!(
+            "mukti-bin currently only supports one project, {} found",
+            release_json.projects.len()
+        );
+    }
+
+    let (name, project) = release_json
+        .projects
+        .iter_mut()
+        .next()
+        .expect("release_json has one project");
+    Ok((name, project))
+}
+
 pub(crate) fn write_releases_json(release_json: &MuktiReleasesJson, path: &Utf8Path) -> Result<()> {
-    let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
-    file.write(|f| serde_json::to_writer_pretty(BufWriter::new(f), &release_json))
-        .wrap_err_with(|| format!("failed to serialize releases JSON to {}", path))?;
+    match release_json.write_atomic(path) {
+        Ok(()) => Ok(()),
+        Err(error @ WriteReleasesJsonError::ShardedIndex { .. }) => {
+            bail!("{error} (modify the original file and run `mukti shard` again)")
+        }
+        Err(error) => Err(error).wrap_err("failed to write releases JSON"),
+    }
+}
 
-    Ok(())
+/// Serialize the release JSON into the exact form [`write_releases_json`] writes out.
+pub(crate) fn serialize_releases_json(release_json: &MuktiReleasesJson) -> Result<String> {
+    serde_json::to_string_pretty(release_json).wrap_err("failed to serialize releases JSON")
 }
@@ -0,0 +1,207 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Summarize the release history in the release JSON.
+
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, ReleaseStatus, VersionRange};
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum ReportFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+
+    /// JSON, for tracking over time
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    projects: BTreeMap<String, ProjectReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectReport {
+    latest: Option<Version>,
+    versions: usize,
+    yanked: usize,
+    prereleases: usize,
+    first_version: Option<Version>,
+    last_version: Option<Version>,
+    ranges: Vec<RangeReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct RangeReport {
+    range: VersionRange,
+    latest: Version,
+    versions: usize,
+    yanked: usize,
+    releases: Vec<ReleaseReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReleaseReport {
+    version: Version,
+    status: ReleaseStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    yank_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    targets: usize,
+    artifacts: usize,
+    artifacts_without_checksums: usize,
+}
+
+pub(crate) fn report(release_json: &MuktiReleasesJson, format: ReportFormat) -> Result<()> {
+    let report = build_report(release_json);
+    match format {
+        ReportFormat::Text => print_text(&report),
+        ReportFormat::Json => {
+            let json =
+                serde_json::to_string_pretty(&report).wrap_err("failed to serialize report")?;
+            println!("{json}");
+        }
+    }
+    Ok(())
+}
+
+fn build_report(release_json: &MuktiReleasesJson) -> Report {
+    let projects = release_json
+        .projects
+        .iter()
+        .map(|(name, project)| {
+            let ranges: Vec<_> = project
+                .ranges
+                .iter()
+                .rev()
+                .map(|(range, range_data)| {
+                    let releases: Vec<_> = range_data
+                        .versions
+                        .iter()
+                        .rev()
+                        .map(|(version, version_data)| {
+                            let targets: BTreeSet<_> = version_data
+                                .locations
+                                .iter()
+                                .map(|location| &location.target)
+                                .collect();
+                            ReleaseReport {
+                                version: version.clone(),
+                                status: version_data.status.clone(),
+                                yank_reason: match &version_data.status {
+                                    ReleaseStatus::Yanked { reason, .. } => reason.clone(),
+                                    _ => None,
+                                },
+                                release_date: version_data.release_date.clone(),
+                                commit: version_data.commit.clone(),
+                                targets: targets.len(),
+                                artifacts: version_data.locations.len(),
+                                artifacts_without_checksums: version_data
+                                    .locations
+                                    .iter()
+                                    .filter(|location| location.checksums.is_empty())
+                                    .count(),
+                            }
+                        })
+                        .collect();
+                    RangeReport {
+                        range: *range,
+                        latest: range_data.latest.clone(),
+                        versions: releases.len(),
+                        yanked: count_yanked(&releases),
+                        releases,
+                    }
+                })
+                .collect();
+
+            let all_versions: Vec<_> = project.all_versions().map(|(version, _)| version).collect();
+            let project_report = ProjectReport {
+                latest: project
+                    .latest
+                    .and_then(|range| project.ranges.get(&range))
+                    .map(|range_data| range_data.latest.clone()),
+                versions: all_versions.len(),
+                yanked: ranges.iter().map(|range| range.yanked).sum(),
+                prereleases: all_versions
+                    .iter()
+                    .filter(|version| !version.pre.is_empty())
+                    .count(),
+                first_version: all_versions.last().map(|&version| version.clone()),
+                last_version: all_versions.first().map(|&version| version.clone()),
+                ranges,
+            };
+            (name.clone(), project_report)
+        })
+        .collect();
+
+    Report { projects }
+}
+
+fn count_yanked(releases: &[ReleaseReport]) -> usize {
+    releases
+        .iter()
+        .filter(|release| release.status.is_yanked())
+        .count()
+}
+
+fn print_text(report: &Report) {
+    for (name, project) in &report.projects {
+        println!("{name}:");
+        match &project.latest {
+            Some(latest) => println!("  latest: {latest}"),
+            None => println!("  latest: (none)"),
+        }
+        println!(
+            "  {} versions ({} yanked, {} pre-releases)",
+            project.versions, project.yanked, project.prereleases
+        );
+        if let (Some(first), Some(last)) = (&project.first_version, &project.last_version) {
+            println!("  first version: {first}, last version: {last}");
+        }
+
+        for range in &project.ranges {
+            println!(
+                "  range {}: {} versions ({} yanked), latest {}",
+                range.range, range.versions, range.yanked, range.latest
+            );
+            for release in &range.releases {
+                let mut notes = Vec::new();
+                match &release.status {
+                    ReleaseStatus::Active => {}
+                    ReleaseStatus::Yanked {
+                        reason: Some(reason),
+                        ..
+                    } => notes.push(format!("yanked: {reason}")),
+                    status => notes.push(status.to_string()),
+                }
+                if release.artifacts_without_checksums > 0 {
+                    notes.push(format!(
+                        "{} without checksums",
+                        release.artifacts_without_checksums
+                    ));
+                }
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", notes.join(", "))
+                };
+                let released = match &release.release_date {
+                    Some(date) => format!(" (released {date})"),
+                    None => String::new(),
+                };
+                println!(
+                    "    {}{released}: {} targets, {} artifacts{notes}",
+                    release.version, release.targets, release.artifacts
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Run batches of tasks (downloads, uploads) concurrently.
+
+use futures_util::{
+    future::Either,
+    stream::{self, BufferUnordered, Buffered, Iter},
+    Future, StreamExt,
+};
+
+/// The stream returned by [`Scheduler::run`].
+pub(crate) type Scheduled<I> = Either<Buffered<Iter<I>>, BufferUnordered<Iter<I>>>;
+
+/// Decides how many tasks run at once, and the order their results are returned in.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Scheduler {
+    jobs: usize,
+    ordered: bool,
+}
+
+impl Scheduler {
+    /// Run up to `jobs` tasks at once, returning results as they complete.
+    ///
+    /// With `deterministic`, tasks run one at a time and results are returned in order, so that
+    /// progress output is the same from run to run.
+    pub(crate) fn new(jobs: usize, deterministic: bool) -> Self {
+        if deterministic {
+            Self {
+                jobs: 1,
+                ordered: true,
+            }
+        } else {
+            Self {
+                jobs: jobs.max(1),
+                ordered: false,
+            }
+        }
+    }
+
+    /// Return results in the order tasks were passed in, even if they complete out of order.
+    pub(crate) fn ordered(self) -> Self {
+        Self {
+            ordered: true,
+            ..self
+        }
+    }
+
+    /// Run `tasks`, returning a stream of their results.
+    ///
+    /// Tasks are only started as they're pulled off the queue, so any work spawned within a task
+    /// counts towards the concurrency limit.
+    pub(crate) fn run<I>(self, tasks: I) -> Scheduled<I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Future,
+    {
+        let tasks = stream::iter(tasks);
+        if self.ordered {
+            Either::Left(tasks.buffered(self.jobs))
+        } else {
+            Either::Right(tasks.buffer_unordered(self.jobs))
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! JSON schemas for the files mukti writes.
+
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, ReleaseDiff, ShardedIndex};
+use schemars::schema_for;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub(crate) enum SchemaKind {
+    /// The release JSON, or one of the shards of a sharded layout
+    #[default]
+    Releases,
+
+    /// The index of a sharded layout
+    ShardedIndex,
+
+    /// Diffs, as printed by `diff --format json` and sent to webhooks
+    Diff,
+}
+
+pub(crate) fn print_schema(kind: SchemaKind) -> Result<()> {
+    let schema = match kind {
+        SchemaKind::Releases => schema_for!(MuktiReleasesJson),
+        SchemaKind::ShardedIndex => schema_for!(ShardedIndex),
+        SchemaKind::Diff => schema_for!(ReleaseDiff),
+    };
+    let json = serde_json::to_string_pretty(&schema).wrap_err("failed to serialize schema")?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,227 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Resolve credentials without requiring them to be passed in on the command line.
+//!
+//! Secrets are looked up by name (e.g. `GITHUB_TOKEN`), trying each of these in order:
+//!
+//! 1. The environment variable `NAME`.
+//! 2. The file pointed to by the environment variable `NAME_FILE`.
+//! 3. The file `NAME` within `--secrets-dir`, if specified.
+//! 4. The standard output of `--secret-cmd`, if specified, run with `NAME` as its argument.
+
+use camino::Utf8PathBuf;
+use clap::Args;
+use color_eyre::eyre::{bail, Result, WrapErr};
+use std::process::{Command, Stdio};
+
+pub(crate) const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+
+#[derive(Clone, Debug, Args)]
+pub(crate) struct SecretOpts {
+    /// Directory containing secrets, one per file named after the secret
+    #[clap(long, global = true, value_name = "DIR")]
+    secrets_dir: Option<Utf8PathBuf>,
+
+    /// Command to resolve secrets, run through the shell with the secret name as its argument
+    #[clap(long, global = true, value_name = "CMD")]
+    secret_cmd: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SecretResolver {
+    opts: SecretOpts,
+}
+
+impl SecretResolver {
+    pub(crate) fn new(opts: &SecretOpts) -> Self {
+        Self { opts: opts.clone() }
+    }
+
+    /// Resolve a secret, returning an error if it isn't available from any provider.
+    pub(crate) fn require(&self, name: &str) -> Result<String> {
+        match self.lookup(name)? {
+            Lookup::Found(secret) => Ok(secret),
+            Lookup::Missing(Some(reason)) => bail!("secret {name} not found: {reason}"),
+            Lookup::Missing(None) => bail!(
+                "secret {name} not found (set {name} or {name}_FILE in the environment, \
+                 or pass in --secrets-dir or --secret-cmd)"
+            ),
+        }
+    }
+
+    /// Resolve a secret, returning `None` if it isn't available from any provider.
+    ///
+    /// `--secret-cmd` failing or printing nothing counts as the secret not being available, so
+    /// that optional secrets don't have to be provided by it.
+    pub(crate) fn resolve(&self, name: &str) -> Result<Option<String>> {
+        match self.lookup(name)? {
+            Lookup::Found(secret) => Ok(Some(secret)),
+            Lookup::Missing(_) => Ok(None),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<Lookup> {
+        if let Some(secret) = std::env::var(name).ok().filter(|s| !s.is_empty()) {
+            return Ok(Lookup::Found(secret));
+        }
+
+        if let Some(path) = std::env::var_os(format!("{name}_FILE")) {
+            let secret = std::fs::read_to_string(&path).wrap_err_with(|| {
+                format!("failed to read {name} from {}", path.to_string_lossy())
+            })?;
+            return Ok(Lookup::Found(trim_secret(secret)));
+        }
+
+        if let Some(dir) = &self.opts.secrets_dir {
+            let path = dir.join(name);
+            if path.exists() {
+                let secret = std::fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("failed to read {name} from {path}"))?;
+                return Ok(Lookup::Found(trim_secret(secret)));
+            }
+        }
+
+        if let Some(cmd) = &self.opts.secret_cmd {
+            return run_secret_cmd(cmd, name);
+        }
+
+        Ok(Lookup::Missing(None))
+    }
+}
+
+enum Lookup {
+    Found(String),
+    /// The secret isn't available from any provider. If `--secret-cmd` was run, this is why it
+    /// didn't provide the secret.
+    Missing(Option<String>),
+}
+
+fn run_secret_cmd(cmd: &str, name: &str) -> Result<Lookup> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(format!("{cmd} {name}"));
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{cmd} \"$1\""))
+            .arg("sh")
+            .arg(name);
+        command
+    };
+
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .wrap_err_with(|| format!("failed to run secret command `{cmd}` for {name}"))?;
+    if !output.status.success() {
+        return Ok(Lookup::Missing(Some(format!(
+            "secret command `{cmd}` failed with {}",
+            output.status
+        ))));
+    }
+
+    let secret = String::from_utf8(output.stdout)
+        .wrap_err_with(|| format!("secret command `{cmd}` produced invalid UTF-8 for {name}"))?;
+    let secret = trim_secret(secret);
+    if secret.is_empty() {
+        return Ok(Lookup::Missing(Some(format!(
+            "secret command `{cmd}` produced no output"
+        ))));
+    }
+    Ok(Lookup::Found(secret))
+}
+
+fn trim_secret(mut secret: String) -> String {
+    let len = secret.trim_end_matches(['\r', '\n']).len();
+    secret.truncate(len);
+    secret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(secrets_dir: Option<&Utf8PathBuf>, secret_cmd: Option<&str>) -> SecretResolver {
+        SecretResolver::new(&SecretOpts {
+            secrets_dir: secrets_dir.cloned(),
+            secret_cmd: secret_cmd.map(str::to_owned),
+        })
+    }
+
+    /// A directory for a test's secrets. Secret names are unique to each test, since the
+    /// environment is shared between tests running in parallel.
+    fn test_dir(test: &str) -> Utf8PathBuf {
+        let dir = Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("mukti-secrets-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lookup_order() {
+        const NAME: &str = "MUKTI_TEST_LOOKUP_ORDER";
+        let dir = test_dir("lookup-order");
+        let file = dir.join("file");
+        std::fs::write(&file, "from-file\n").unwrap();
+        std::fs::write(dir.join(NAME), "from-dir\n").unwrap();
+        // The command prints the secret name.
+        let secrets = resolver(Some(&dir), Some("echo"));
+
+        std::env::set_var(NAME, "from-env");
+        std::env::set_var(format!("{NAME}_FILE"), &file);
+        assert_eq!(secrets.require(NAME).unwrap(), "from-env");
+        // An empty variable is treated as unset.
+        std::env::set_var(NAME, "");
+        assert_eq!(secrets.require(NAME).unwrap(), "from-file");
+        std::env::remove_var(format!("{NAME}_FILE"));
+        assert_eq!(secrets.require(NAME).unwrap(), "from-dir");
+        std::fs::remove_file(dir.join(NAME)).unwrap();
+        assert_eq!(secrets.require(NAME).unwrap(), NAME);
+        assert_eq!(resolver(Some(&dir), None).resolve(NAME).unwrap(), None);
+
+        std::env::remove_var(NAME);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_secret_cmd() {
+        const NAME: &str = "MUKTI_TEST_FAILING_SECRET_CMD";
+        for cmd in ["false", "true"] {
+            let secrets = resolver(None, Some(cmd));
+            assert_eq!(
+                secrets.resolve(NAME).unwrap(),
+                None,
+                "`{cmd}` means the secret isn't available"
+            );
+            let err = secrets.require(NAME).unwrap_err().to_string();
+            assert!(
+                err.starts_with(&format!("secret {NAME} not found: secret command `{cmd}`")),
+                "`{cmd}` is reported as the reason: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_trim_secret() {
+        const NAME: &str = "MUKTI_TEST_TRIM_SECRET";
+        let dir = test_dir("trim");
+        let secrets = resolver(Some(&dir), None);
+        for (contents, expected) in [
+            ("secret\n", "secret"),
+            ("secret\r\n\n", "secret"),
+            (" secret \n", " secret "),
+            ("line 1\nline 2\n", "line 1\nline 2"),
+        ] {
+            std::fs::write(dir.join(NAME), contents).unwrap();
+            assert_eq!(secrets.require(NAME).unwrap(), expected, "for {contents:?}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate static files in the layout the `self_update` crate's GitHub backend expects.
+//!
+//! `self_update` can be pointed at a GitHub Enterprise API with `with_url`. Serving these files
+//! from that URL lets binaries update from mukti-hosted artifacts without talking to the GitHub
+//! API. The files are:
+//!
+//! * `repos/{owner}/{repo}/releases/latest`: the latest non-prerelease version, used by
+//!   `Update::get_latest_release`.
+//! * `repos/{owner}/{repo}/releases/tags/{version}`: each active version, used when a specific
+//!   version is requested.
+//! * `repos/{owner}/{repo}/releases/index.json`: every active version, newest first, for
+//!   `ReleaseList`. Since `releases` is also a directory, this needs a rewrite from `releases` on
+//!   the host.
+
+use crate::{output::OutputOpts, publish::file_name_from_url, release_json::single_project};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{bail, Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, ReleaseVersionData};
+use semver::Version;
+use serde_json::{json, Value};
+
+pub(crate) fn generate_self_update(
+    release_json: &MuktiReleasesJson,
+    repo: &str,
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let Some((owner, name)) = repo.split_once('/') else {
+        bail!("repository {repo} isn't in the form OWNER/NAME");
+    };
+    let (project_name, project) = single_project(release_json)?;
+
+    let releases_dir = Utf8Path::new("repos")
+        .join(owner)
+        .join(name)
+        .join("releases");
+    // Paths relative to out_dir, and their contents.
+    let mut files = Vec::new();
+    let mut list = Vec::new();
+    for (version, version_data) in project.all_versions() {
+        if !version_data.status.is_available() {
+            continue;
+        }
+        let release = release(project_name, version, version_data);
+        files.push((
+            releases_dir.join("tags").join(version.to_string()),
+            release.clone(),
+        ));
+        list.push(release);
+    }
+    if let Some(range) = &project.latest {
+        let range_data = &project.ranges[range];
+        let version_data = &range_data.versions[&range_data.latest];
+        files.push((
+            releases_dir.join("latest"),
+            release(project_name, &range_data.latest, version_data),
+        ));
+    }
+    files.push((releases_dir.join("index.json"), Value::Array(list)));
+
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+    for (rel_path, value) in &files {
+        let path = out_dir.join(rel_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+        }
+        let mut contents = serde_json::to_string(value)?;
+        contents.push('\n');
+        if output_opts.write_file(&path, &contents)? {
+            changed.push(rel_path.clone());
+        }
+    }
+    eprintln!(
+        "wrote {} files for {project_name} to {} ({} changed)",
+        files.len(),
+        out_dir.join(&releases_dir),
+        changed.len()
+    );
+    Ok(changed)
+}
+
+/// A release in the shape of the GitHub releases API, with only the fields `self_update` reads.
+fn release(project_name: &str, version: &Version, version_data: &ReleaseVersionData) -> Value {
+    let assets: Vec<_> = version_data
+        .installable_locations()
+        .filter(|location| !location.is_target_independent())
+        .map(|location| {
+            // self_update picks the asset whose name contains the target.
+            let name = file_name_from_url(&location.url)
+                .filter(|name| name.contains(&location.target))
+                .map_or_else(
+                    || {
+                        format!(
+                            "{project_name}-{version}-{}.{}",
+                            location.target,
+                            location.format.normalized()
+                        )
+                    },
+                    str::to_owned,
+                );
+            // self_update downloads from `url`, which is the API URL for GitHub-hosted assets.
+            json!({
+                "name": name,
+                "url": location.url,
+                "browser_download_url": location.url,
+                "size": location.size,
+            })
+        })
+        .collect();
+    json!({
+        "tag_name": version.to_string(),
+        "name": version.to_string(),
+        "html_url": version_data.release_url,
+        "target_commitish": version_data.commit,
+        "prerelease": !version.pre.is_empty(),
+        // self_update requires this field, even for releases without a recorded date.
+        "created_at": version_data.release_date.as_deref().unwrap_or_default(),
+        "body": version_data.notes,
+        "assets": assets,
+    })
+}
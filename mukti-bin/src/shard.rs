@@ -0,0 +1,54 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Split the release JSON into an index and one file per version range.
+
+use crate::release_json::write_releases_json;
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::MuktiReleasesJson;
+use std::io::BufWriter;
+
+/// The name of the index file within the output directory.
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Write the shards and index to `out_dir`, returning the files that changed relative to it.
+pub(crate) fn shard_release_json(
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>> {
+    let (index, shards) = release_json.to_sharded()?;
+    let mut changed = Vec::new();
+
+    for (shard_path, shard) in &shards {
+        let path = out_dir.join(shard_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {}", parent))?;
+        }
+        let existing = std::fs::read(&path).ok();
+        write_releases_json(shard, &path)?;
+        if std::fs::read(&path).ok() != existing {
+            changed.push(shard_path.into());
+        }
+    }
+
+    // Write the index last, so it never points to shards that don't exist yet.
+    let index_path = out_dir.join(INDEX_FILE_NAME);
+    let existing = std::fs::read(&index_path).ok();
+    let file = AtomicFile::new(&index_path, OverwriteBehavior::AllowOverwrite);
+    file.write(|f| serde_json::to_writer_pretty(BufWriter::new(f), &index))
+        .wrap_err_with(|| format!("failed to write shard index to {}", index_path))?;
+    if std::fs::read(&index_path).ok() != existing {
+        changed.push(INDEX_FILE_NAME.into());
+    }
+
+    eprintln!(
+        "wrote {} shards and index to {} ({} changed)",
+        shards.len(),
+        out_dir,
+        changed.len()
+    );
+    Ok(changed)
+}
@@ -0,0 +1,393 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Render a download site from a directory of user-supplied templates and assets.
+//!
+//! Without a template directory, a built-in `index.html` listing each project's latest downloads
+//! and version history is rendered instead.
+//!
+//! Files ending in `.tera` are rendered as Tera templates and written without that suffix, e.g.
+//! `index.html.tera` produces `index.html`. Templates can extend or include each other by their
+//! path relative to the template directory. Templates whose file name starts with `_`, like
+//! `_base.html.tera`, are only used that way and aren't written. Every other file, such as
+//! stylesheets and images, is copied as is. The directory layout is preserved.
+//!
+//! With a base URL, a `sitemap.xml` listing the rendered HTML pages is written too. The pages
+//! show release data, so each page's `lastmod` is the date of the most recent release.
+//!
+//! Templates receive a `projects` variable: a list of `{name, info, latest, versions}` objects.
+//! `info` holds the project's display name, description, homepage and repository, where set.
+//! `latest` is the latest non-prerelease version, or null, and `versions` lists every version,
+//! newest first. Each version has the fields of a version in the release JSON, along with
+//! `version`, `prerelease`, its range's `eol_date` if set, and `end_of_life`, which is true once
+//! that date has passed. Templates also receive `vars`: the variables passed in with
+//! `--var NAME=VALUE`, by name.
+
+use crate::{
+    eol::is_past_eol,
+    output::{write_bytes, OutputOpts},
+    redirects::xml_escape,
+    template::Templates,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use color_eyre::eyre::{Result, WrapErr};
+use mukti_metadata::{MuktiReleasesJson, ReleaseRangeData, ReleaseVersionData};
+use semver::Version;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fmt::Write as _};
+
+#[derive(Debug, Serialize)]
+struct SiteVersion<'a> {
+    version: &'a Version,
+    prerelease: bool,
+    eol_date: Option<&'a str>,
+    end_of_life: bool,
+    #[serde(flatten)]
+    data: &'a ReleaseVersionData,
+}
+
+impl<'a> SiteVersion<'a> {
+    fn new(
+        version: &'a Version,
+        range_data: &'a ReleaseRangeData,
+        data: &'a ReleaseVersionData,
+        today: NaiveDate,
+    ) -> Self {
+        Self {
+            version,
+            prerelease: !version.pre.is_empty(),
+            eol_date: range_data.eol_date.as_deref(),
+            end_of_life: is_past_eol(range_data, today),
+            data,
+        }
+    }
+}
+
+/// The template rendered when no template directory is passed in.
+const BUILTIN_INDEX: &str = include_str!("../templates/index.html.tera");
+
+/// Render `template_dir`, or the built-in page if it's `None`, into `out_dir`, returning the files
+/// that changed relative to it.
+///
+/// If `base_url` is set, a sitemap of the rendered HTML pages under that URL is written as well.
+pub(crate) fn generate_site(
+    release_json: &MuktiReleasesJson,
+    template_dir: Option<&Utf8Path>,
+    out_dir: &Utf8Path,
+    base_url: Option<&str>,
+    vars: &BTreeMap<String, String>,
+) -> Result<Vec<Utf8PathBuf>> {
+    let context = site_context(release_json, vars, Utc::now().date_naive());
+
+    let mut files = Vec::new();
+    let mut sources = Vec::new();
+    let template_dir = match template_dir {
+        Some(template_dir) => {
+            collect_files(template_dir, Utf8Path::new(""), &mut files)?;
+            for rel_path in &files {
+                if rel_path.as_str().ends_with(".tera") {
+                    let source_path = template_dir.join(rel_path);
+                    let source = std::fs::read_to_string(&source_path)
+                        .wrap_err_with(|| format!("failed to read template {source_path}"))?;
+                    sources.push((template_name(rel_path), source));
+                }
+            }
+            template_dir
+        }
+        None => {
+            files.push(Utf8PathBuf::from("index.html.tera"));
+            sources.push(("index.html.tera".to_owned(), BUILTIN_INDEX.to_owned()));
+            // Only used in error messages, since there are no assets to copy.
+            Utf8Path::new("<built-in>")
+        }
+    };
+    let templates = Templates::parse(sources)
+        .wrap_err_with(|| format!("failed to parse templates in {template_dir}"))?;
+
+    let output_opts = OutputOpts::default();
+    let mut changed = Vec::new();
+    let mut rendered = 0;
+    let mut partials = 0;
+    let mut pages = Vec::new();
+    for rel_path in &files {
+        let source_path = template_dir.join(rel_path);
+        let (out_rel_path, written) = match rel_path.as_str().strip_suffix(".tera") {
+            Some(_)
+                if rel_path
+                    .file_name()
+                    .is_some_and(|name| name.starts_with('_')) =>
+            {
+                partials += 1;
+                continue;
+            }
+            Some(out_rel_path) => {
+                let out_rel_path = Utf8PathBuf::from(out_rel_path);
+                let contents = templates
+                    .render(&template_name(rel_path), &context)
+                    .wrap_err_with(|| format!("failed to render template {source_path}"))?;
+                let path = create_parent(out_dir, &out_rel_path)?;
+                rendered += 1;
+                if matches!(out_rel_path.extension(), Some("html" | "htm")) {
+                    pages.push(out_rel_path.clone());
+                }
+                let written = output_opts.write_file(&path, &contents)?;
+                (out_rel_path, written)
+            }
+            None => {
+                let contents = std::fs::read(&source_path)
+                    .wrap_err_with(|| format!("failed to read {source_path}"))?;
+                let path = create_parent(out_dir, rel_path)?;
+                (rel_path.clone(), write_bytes(&path, &contents)?)
+            }
+        };
+        if written {
+            changed.push(out_rel_path);
+        }
+    }
+
+    if let Some(base_url) = base_url {
+        let sitemap_path = Utf8PathBuf::from("sitemap.xml");
+        if files
+            .iter()
+            .any(|rel_path| rel_path.as_str().trim_end_matches(".tera") == sitemap_path)
+        {
+            eprintln!("not writing sitemap.xml: the template directory already provides one");
+        } else {
+            let contents = sitemap(base_url, &pages, last_release_date(release_json).as_deref());
+            if output_opts.write_file(&out_dir.join(&sitemap_path), &contents)? {
+                changed.push(sitemap_path);
+            }
+        }
+    }
+
+    eprintln!(
+        "rendered {rendered} templates and copied {} assets to {out_dir} ({} changed)",
+        files.len() - rendered - partials,
+        changed.len()
+    );
+    Ok(changed)
+}
+
+/// The variables templates are rendered with.
+fn site_context(
+    release_json: &MuktiReleasesJson,
+    vars: &BTreeMap<String, String>,
+    today: NaiveDate,
+) -> Value {
+    let projects: Vec<_> = release_json
+        .projects
+        .iter()
+        .map(|(name, project)| {
+            let latest = project.latest_version_data().and_then(|(version, data)| {
+                let range_data = project.ranges.get(&project.latest?)?;
+                Some(SiteVersion::new(version, range_data, data, today))
+            });
+            let versions: Vec<_> = project
+                .ranges
+                .values()
+                .rev()
+                .flat_map(|range_data| {
+                    range_data
+                        .versions
+                        .iter()
+                        .rev()
+                        .map(move |(version, data)| {
+                            SiteVersion::new(version, range_data, data, today)
+                        })
+                })
+                .collect();
+            json!({
+                "name": name,
+                "info": project.info,
+                "latest": latest,
+                "versions": versions,
+            })
+        })
+        .collect();
+    json!({ "projects": projects, "vars": vars })
+}
+
+/// The date of the most recent release in the release JSON, in the W3C format sitemaps use.
+fn last_release_date(release_json: &MuktiReleasesJson) -> Option<String> {
+    release_json
+        .projects
+        .values()
+        .flat_map(|project| project.all_versions())
+        .filter_map(|(_, version_data)| {
+            DateTime::parse_from_rfc3339(version_data.release_date.as_deref()?).ok()
+        })
+        .max()
+        .map(|date| {
+            date.with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        })
+}
+
+/// Build a sitemap listing `pages`, which are relative to `base_url`.
+///
+/// Pages named `index.html` are listed by their directory's URL.
+fn sitemap(base_url: &str, pages: &[Utf8PathBuf], lastmod: Option<&str>) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for page in pages {
+        let path = template_name(page);
+        let path = match path.strip_suffix("index.html") {
+            Some(dir) if dir.is_empty() || dir.ends_with('/') => dir,
+            _ => &path,
+        };
+        writeln!(
+            out,
+            "  <url>\n    <loc>{}</loc>",
+            xml_escape(&format!("{base_url}/{path}"))
+        )
+        .expect("writing to a string is infallible");
+        if let Some(lastmod) = lastmod {
+            writeln!(out, "    <lastmod>{lastmod}</lastmod>")
+                .expect("writing to a string is infallible");
+        }
+        out.push_str("  </url>\n");
+    }
+    out.push_str("</urlset>\n");
+    out
+}
+
+/// Collect the paths of files under `root.join(rel_dir)`, relative to `root`, in sorted order.
+fn collect_files(root: &Utf8Path, rel_dir: &Utf8Path, out: &mut Vec<Utf8PathBuf>) -> Result<()> {
+    let dir = root.join(rel_dir);
+    let mut entries = dir
+        .read_dir_utf8()
+        .wrap_err_with(|| format!("failed to read directory {dir}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err_with(|| format!("failed to read directory {dir}"))?;
+    entries.sort_by(|a, b| a.file_name().cmp(b.file_name()));
+    for entry in entries {
+        let rel_path = rel_dir.join(entry.file_name());
+        let file_type = entry
+            .file_type()
+            .wrap_err_with(|| format!("failed to read file type of {}", entry.path()))?;
+        if file_type.is_dir() {
+            collect_files(root, &rel_path, out)?;
+        } else {
+            out.push(rel_path);
+        }
+    }
+    Ok(())
+}
+
+/// The name a template is referred to by from other templates.
+fn template_name(rel_path: &Utf8Path) -> String {
+    // Use forward slashes on every platform, so templates work the same everywhere.
+    rel_path.as_str().replace('\\', "/")
+}
+
+fn create_parent(out_dir: &Utf8Path, rel_path: &Utf8Path) -> Result<Utf8PathBuf> {
+    let path = out_dir.join(rel_path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("failed to create directory {parent}"))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sitemap() {
+        let pages = [
+            "index.html",
+            "docs/index.html",
+            "docs/a&b.html",
+            "myindex.html",
+        ]
+        .map(Utf8PathBuf::from);
+        assert_eq!(
+            sitemap(
+                "https://example.com/downloads/",
+                &pages,
+                Some("2024-05-01T12:00:00Z")
+            ),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url>
+    <loc>https://example.com/downloads/</loc>
+    <lastmod>2024-05-01T12:00:00Z</lastmod>
+  </url>
+  <url>
+    <loc>https://example.com/downloads/docs/</loc>
+    <lastmod>2024-05-01T12:00:00Z</lastmod>
+  </url>
+  <url>
+    <loc>https://example.com/downloads/docs/a&amp;b.html</loc>
+    <lastmod>2024-05-01T12:00:00Z</lastmod>
+  </url>
+  <url>
+    <loc>https://example.com/downloads/myindex.html</loc>
+    <lastmod>2024-05-01T12:00:00Z</lastmod>
+  </url>
+</urlset>
+"#
+        );
+    }
+
+    #[test]
+    fn test_builtin_index() {
+        let release_json = MuktiReleasesJson::from_reader(
+            include_str!("../../fixtures/mukti-releases.json").as_bytes(),
+        )
+        .unwrap();
+        let vars = BTreeMap::new();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let templates = Templates::parse([("index.html.tera", BUILTIN_INDEX)]).unwrap();
+        let html = templates
+            .render(
+                "index.html.tera",
+                &site_context(&release_json, &vars, today),
+            )
+            .unwrap();
+
+        assert!(html.contains("<h2>Latest: 0.5.3</h2>"), "{html}");
+        assert!(
+            html.contains(
+                "<code>9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08</code>"
+            ),
+            "{html}"
+        );
+        assert!(html.contains("yanked"), "{html}");
+        assert!(html.contains("pre-release"), "{html}");
+    }
+
+    #[test]
+    fn test_last_release_date() {
+        let mut release_json = MuktiReleasesJson::from_reader(
+            include_str!("../../fixtures/mukti-releases.json").as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(last_release_date(&release_json), None);
+
+        let dates = [
+            "2024-05-01T12:00:00Z",
+            // Later than the first date once converted to UTC.
+            "2024-05-01T10:00:00-03:00",
+            "not a date",
+        ];
+        let versions = release_json
+            .projects
+            .values_mut()
+            .flat_map(|project| project.ranges.values_mut())
+            .flat_map(|range_data| range_data.versions.values_mut());
+        for (version_data, date) in versions.zip(dates) {
+            version_data.release_date = Some(date.to_owned());
+        }
+        assert_eq!(
+            last_release_date(&release_json).as_deref(),
+            Some("2024-05-01T13:00:00Z")
+        );
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A staging file recording checksums fetched so far by `add-release`, so that an interrupted run
+//! can be resumed without refetching completed artifacts.
+//!
+//! The file is in JSON Lines format, with one entry appended per fetched artifact.
+
+use crate::checksums::Checksums;
+use camino::{Utf8Path, Utf8PathBuf};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+};
+
+pub(crate) struct StagingFile {
+    path: Utf8PathBuf,
+    file: File,
+    completed: HashMap<String, Checksums>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct StagingEntry {
+    url: String,
+    sha256: String,
+    blake2b: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+impl StagingFile {
+    /// Open the staging file at `path`.
+    ///
+    /// If `resume` is true, entries from an existing staging file are loaded. Otherwise, any
+    /// existing staging file is discarded.
+    pub(crate) fn open(path: &Utf8Path, resume: bool) -> Result<Self> {
+        let mut completed = HashMap::new();
+        if resume && path.exists() {
+            let file = File::open(path)
+                .wrap_err_with(|| format!("failed to open staging file at {path}"))?;
+            for (line_no, line) in BufReader::new(file).lines().enumerate() {
+                let line =
+                    line.wrap_err_with(|| format!("failed to read staging file at {path}"))?;
+                // A partially written last line is expected if the run was interrupted.
+                let Ok(entry) = serde_json::from_str::<StagingEntry>(&line) else {
+                    eprintln!("ignoring malformed line {} in {path}", line_no + 1);
+                    continue;
+                };
+                match Checksums::from_hex(&entry.sha256, &entry.blake2b) {
+                    Ok(mut checksums) => {
+                        checksums.size = entry.size;
+                        completed.insert(entry.url, checksums);
+                    }
+                    Err(err) => eprintln!("ignoring line {} in {path}: {err}", line_no + 1),
+                }
+            }
+            eprintln!(
+                "resuming with {} checksums from staging file {path}",
+                completed.len()
+            );
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .truncate(false)
+            .open(path)
+            .wrap_err_with(|| format!("failed to open staging file at {path}"))?;
+        if !resume {
+            file.set_len(0)
+                .wrap_err_with(|| format!("failed to truncate staging file at {path}"))?;
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            file,
+            completed,
+        })
+    }
+
+    /// Return checksums recorded by a previous run for this URL.
+    pub(crate) fn get(&self, url: &str) -> Option<&Checksums> {
+        self.completed.get(url)
+    }
+
+    /// Record the checksums for a URL.
+    pub(crate) fn record(&mut self, url: &str, checksums: &Checksums) -> Result<()> {
+        let entry = StagingEntry {
+            url: url.to_owned(),
+            sha256: hex::encode(checksums.sha256),
+            blake2b: hex::encode(checksums.blake2b),
+            size: checksums.size,
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.flush())
+            .wrap_err_with(|| format!("failed to write to staging file at {}", self.path))?;
+        self.completed.insert(entry.url, checksums.clone());
+        Ok(())
+    }
+
+    /// Remove the staging file, once its contents have been committed to the release JSON.
+    pub(crate) fn remove(self) -> Result<()> {
+        drop(self.file);
+        std::fs::remove_file(&self.path)
+            .wrap_err_with(|| format!("failed to remove staging file at {}", self.path))
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Check target strings against the target triples Rust knows about, to catch typos like
+//! `x86_64-unkown-linux-gnu` before they end up in the release JSON.
+
+use clap::Args;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::SOURCE_TARGET;
+use platforms::Platform;
+
+/// Matches any target in an alias.
+const ANY_TARGET: &str = "*";
+
+#[derive(Clone, Debug, Default, Args)]
+pub(crate) struct TargetOpts {
+    /// Accept TARGET even though it isn't a known Rust target triple (can be repeated)
+    #[clap(long = "custom-target", value_name = "TARGET")]
+    custom_targets: Vec<String>,
+}
+
+impl TargetOpts {
+    /// Returns a description of the problem if `target` isn't a known target triple, the source
+    /// pseudo-target, or one of the custom targets.
+    pub(crate) fn unknown_target_message(&self, target: &str) -> Option<String> {
+        if target == SOURCE_TARGET
+            || target == ANY_TARGET
+            || Platform::find(target).is_some()
+            || self.custom_targets.iter().any(|custom| custom == target)
+        {
+            return None;
+        }
+
+        Some(match closest_target(target) {
+            Some(suggestion) => format!("unknown target `{target}` (did you mean `{suggestion}`?)"),
+            None => format!("unknown target `{target}`"),
+        })
+    }
+
+    /// Fail if any of `targets` is unknown.
+    pub(crate) fn check<'a>(&self, targets: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let messages: Vec<_> = targets
+            .into_iter()
+            .filter_map(|target| self.unknown_target_message(target))
+            .collect();
+        if !messages.is_empty() {
+            bail!(
+                "{} (pass in --custom-target to allow targets that aren't Rust target triples)",
+                messages.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The known target triple closest to `target`, if it's close enough to be a likely typo.
+fn closest_target(target: &str) -> Option<&'static str> {
+    let max_distance = (target.len() / 5).max(2);
+    Platform::ALL
+        .iter()
+        .map(|platform| (edit_distance(target, platform.target_triple), platform))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, platform)| platform.target_triple)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(a_char != *b_char);
+            curr.push(substitute.min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_target_message() {
+        let opts = TargetOpts {
+            custom_targets: vec!["my-custom-target".to_owned()],
+        };
+        for target in [
+            "x86_64-unknown-linux-gnu",
+            "aarch64-apple-darwin",
+            SOURCE_TARGET,
+            ANY_TARGET,
+            "my-custom-target",
+        ] {
+            assert_eq!(
+                opts.unknown_target_message(target),
+                None,
+                "{target} is known"
+            );
+        }
+
+        assert_eq!(
+            opts.unknown_target_message("x86_64-unkown-linux-gnu")
+                .as_deref(),
+            Some("unknown target `x86_64-unkown-linux-gnu` (did you mean `x86_64-unknown-linux-gnu`?)"),
+        );
+        assert_eq!(
+            opts.unknown_target_message("windows").as_deref(),
+            Some("unknown target `windows`"),
+        );
+    }
+}
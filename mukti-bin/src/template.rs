@@ -0,0 +1,165 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! User-supplied templates, rendered with [Tera](https://keats.github.io/tera/docs/).
+//!
+//! Tera is built without its optional builtins, so the filters and functions that need extra
+//! dependencies (such as `date`, `slugify`, `filesizeformat` and `get_random`) aren't available.
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::Value;
+use tera::{Context, Tera};
+
+/// Templates whose output is HTML-escaped by default, like Tera does for `.html` files.
+const AUTOESCAPE_SUFFIXES: &[&str] = &[".html.tera", ".htm.tera", ".xml.tera"];
+
+/// A set of templates, which can refer to each other by name with `extends`, `include` and
+/// `import`.
+#[derive(Debug)]
+pub(crate) struct Templates {
+    tera: Tera,
+}
+
+impl Templates {
+    /// Parse templates from `(name, source)` pairs.
+    ///
+    /// Expressions are HTML-escaped in templates whose names end with `.html.tera`, `.htm.tera`
+    /// or `.xml.tera`. Use the `safe` filter to output a value as is.
+    pub(crate) fn parse<N, S>(templates: impl IntoIterator<Item = (N, S)>) -> Result<Self>
+    where
+        N: AsRef<str>,
+        S: AsRef<str>,
+    {
+        let mut tera = Tera::default();
+        tera.autoescape_on(AUTOESCAPE_SUFFIXES.to_vec());
+        tera.add_raw_templates(templates)
+            .wrap_err("failed to parse templates")?;
+        Ok(Self { tera })
+    }
+
+    /// Render the template `name`, with the top-level keys of `context` available as variables.
+    pub(crate) fn render(&self, name: &str, context: &Value) -> Result<String> {
+        let context =
+            Context::from_value(context.clone()).wrap_err("template context must be an object")?;
+        self.tera
+            .render(name, &context)
+            .wrap_err_with(|| format!("failed to render template {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(source: &str, context: Value) -> String {
+        Templates::parse([("test.tera", source)])
+            .expect("template parses")
+            .render("test.tera", &context)
+            .expect("template renders")
+    }
+
+    #[test]
+    fn test_render() {
+        let context = json!({
+            "project": "mukti",
+            "redirects": [
+                { "path": "/latest/release", "url": "https://example.com/a", "status": 302 },
+                { "path": "/0.5.3/release", "url": "https://example.com/b", "status": 301 },
+            ],
+        });
+
+        assert_eq!(render("{{ project | upper }}!", context.clone()), "MUKTI!");
+        assert_eq!(
+            render(
+                "{% for r in redirects -%}\n{{ r.path }} {{ r.url }} {{ r.status }}\n{% endfor %}",
+                context.clone()
+            ),
+            "/latest/release https://example.com/a 302\n/0.5.3/release https://example.com/b 301\n"
+        );
+        assert_eq!(
+            render(
+                "[{% for r in redirects %}{{ r.path | json_encode() }}{% if not loop.last %}, \
+                 {% endif %}{% endfor %}]",
+                context.clone()
+            ),
+            r#"["/latest/release", "/0.5.3/release"]"#
+        );
+        assert_eq!(
+            render(
+                "{% for r in redirects %}{% if r.status == 301 %}P{% elif r.status != 1 %}T\
+                 {% else %}?{% endif %}{% endfor %}",
+                context.clone()
+            ),
+            "TP"
+        );
+        assert_eq!(
+            render(
+                "{# comment #}{% if missing %}yes{% else %}{{ redirects | length }}{% endif %}",
+                context.clone()
+            ),
+            "2"
+        );
+
+        let templates = Templates::parse([("test.tera", "{{ missing }}")]).unwrap();
+        assert!(
+            templates.render("test.tera", &context).is_err(),
+            "undefined variable"
+        );
+        let templates = Templates::parse([("test.tera", "{{ project | nope }}")]).unwrap();
+        assert!(
+            templates.render("test.tera", &context).is_err(),
+            "unknown filter"
+        );
+        assert!(Templates::parse([("test.tera", "{% for r in redirects %}")]).is_err());
+        assert!(Templates::parse([("test.tera", "{{ project")]).is_err());
+    }
+
+    #[test]
+    fn test_autoescape() {
+        let context = json!({ "name": "<a href='/'>&</a>" });
+        let templates = Templates::parse([
+            ("index.html.tera", "{{ name }}|{{ name | safe }}"),
+            ("feed.xml.tera", "{{ name }}"),
+            ("_redirects.tera", "{{ name }}|{{ name | escape }}"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            templates.render("index.html.tera", &context).unwrap(),
+            "&lt;a href=&#x27;&#x2F;&#x27;&gt;&amp;&lt;&#x2F;a&gt;|<a href='/'>&</a>"
+        );
+        assert_eq!(
+            templates.render("feed.xml.tera", &context).unwrap(),
+            "&lt;a href=&#x27;&#x2F;&#x27;&gt;&amp;&lt;&#x2F;a&gt;"
+        );
+        assert_eq!(
+            templates.render("_redirects.tera", &context).unwrap(),
+            "<a href='/'>&</a>|&lt;a href=&#x27;&#x2F;&#x27;&gt;&amp;&lt;&#x2F;a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_inheritance() {
+        // The child is listed before its parent, as it may be when read from a directory.
+        let templates = Templates::parse([
+            (
+                "index.html.tera",
+                r#"{% extends "base.html.tera" %}{% block title %}{{ project }}{% endblock %}"#,
+            ),
+            (
+                "base.html.tera",
+                r#"<title>{% block title %}{% endblock %}</title>{% include "footer.html.tera" %}"#,
+            ),
+            ("footer.html.tera", "<footer>{{ project }}</footer>"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            templates
+                .render("index.html.tera", &json!({ "project": "mukti" }))
+                .unwrap(),
+            "<title>mukti</title><footer>mukti</footer>"
+        );
+    }
+}
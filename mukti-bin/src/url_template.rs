@@ -0,0 +1,74 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Templates that artifact URLs are generated from, so they don't have to be stored for every
+//! location.
+
+use crate::release_json::single_project_mut;
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::MuktiReleasesJson;
+
+/// The placeholders a URL template can contain.
+const PLACEHOLDERS: &[&str] = &["{name}", "{version}", "{target}", "{format}"];
+
+/// Set or clear the project's URL template.
+///
+/// Locations whose URLs match the template stop being stored once the release JSON is written
+/// back out.
+pub(crate) fn set_url_template(
+    release_json: &mut MuktiReleasesJson,
+    template: Option<String>,
+) -> Result<()> {
+    if let Some(template) = &template {
+        check_placeholders(template)?;
+    }
+
+    let (project_name, project) = single_project_mut(release_json)?;
+    let Some(template) = template else {
+        project.url_template = None;
+        eprintln!("cleared URL template for {project_name}");
+        return Ok(());
+    };
+
+    project.url_template = Some(template);
+    let (mut matching, mut total) = (0, 0);
+    for (version, location) in project.locations() {
+        total += 1;
+        if project
+            .template_url(project_name, version, location)
+            .as_ref()
+            == Some(&location.url)
+        {
+            matching += 1;
+        }
+    }
+    eprintln!(
+        "set URL template for {project_name}: {matching} of {total} locations match it, the \
+         rest keep their own URLs"
+    );
+    // Readers that predate the template would see empty URLs for the matching locations.
+    release_json.schema_version = release_json
+        .schema_version
+        .max(release_json.required_schema_version());
+
+    Ok(())
+}
+
+fn check_placeholders(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            bail!("unclosed `{{` in URL template `{template}`");
+        };
+        let placeholder = &rest[start..=start + len];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            bail!(
+                "unknown placeholder `{placeholder}` in URL template `{template}` (expected one \
+                 of {})",
+                PLACEHOLDERS.join(", ")
+            );
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Yank versions, recording why and when.
+
+use crate::release_json::{compute_project_latest, compute_range_latest, single_project_mut};
+use chrono::{SecondsFormat, Utc};
+use color_eyre::eyre::{bail, Result};
+use mukti_metadata::{MuktiReleasesJson, ReleaseStatus, VersionRange};
+use semver::Version;
+
+/// Mark `version` as yanked, and move `latest` off it if necessary.
+pub(crate) fn yank(
+    release_json: &mut MuktiReleasesJson,
+    version: &Version,
+    reason: Option<String>,
+) -> Result<()> {
+    let (_, project) = single_project_mut(release_json)?;
+
+    let range = VersionRange::from_version(version);
+    let Some(data) = project.ranges.get_mut(&range) else {
+        bail!("version {version} not found in releases JSON");
+    };
+    let Some(version_data) = data.versions.get_mut(version) else {
+        bail!("version {version} not found in releases JSON");
+    };
+    if version_data.status.is_yanked() {
+        bail!("version {version} is already yanked");
+    }
+    version_data.status = ReleaseStatus::Yanked {
+        reason,
+        date: Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+    };
+    eprintln!("yanked version {version}");
+
+    if data.latest == *version {
+        if project.latest_pinned && project.latest == Some(range) {
+            eprintln!("warning: latest was pinned to {version}, unpinning it");
+            project.latest_pinned = false;
+        }
+        data.latest = compute_range_latest(data);
+        eprintln!("latest version in range {range} is now {}", data.latest);
+    }
+    if !project.latest_pinned {
+        project.latest = compute_project_latest(project);
+    }
+
+    for (channel, channel_version) in &project.channels {
+        if channel_version == version {
+            eprintln!("warning: channel {channel} still points to {version}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,579 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generate metadata for a YUM/DNF repository from the `.rpm` artifacts in the release JSON.
+//!
+//! Only `repodata/repomd.xml` and the primary metadata it points to are written. That's enough for
+//! dnf to resolve and install packages by name. Each package's location uses `xml:base`, so the
+//! `.rpm` files can stay wherever they're hosted.
+//!
+//! Each `.rpm` is downloaded so that the metadata lists the name, version, sizes and dependencies
+//! from its header, rather than ones derived from the release JSON.
+//!
+//! `repomd.xml` isn't signed: run e.g. `gpg --detach-sign --armor repodata/repomd.xml` afterwards.
+
+use crate::{
+    fetch::Fetcher,
+    output::{write_bytes, OutputOpts},
+    packages::fetch_packages,
+    redirects::xml_escape,
+    release_json::single_project,
+    scheduler::Scheduler,
+};
+use camino::Utf8Path;
+use chrono::Utc;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use flate2::{write::GzEncoder, Compression};
+use mukti_metadata::{Format, MuktiReleasesJson};
+use sha2::{Digest as _, Sha256};
+use std::{fmt::Write as _, io::Write as _};
+
+const COMMON_NAMESPACE: &str = "http://linux.duke.edu/metadata/common";
+const REPO_NAMESPACE: &str = "http://linux.duke.edu/metadata/repo";
+const RPM_NAMESPACE: &str = "http://linux.duke.edu/metadata/rpm";
+
+pub(crate) async fn generate_yum(
+    release_json: &MuktiReleasesJson,
+    out_dir: &Utf8Path,
+    scheduler: Scheduler,
+    fetcher: &Fetcher,
+) -> Result<()> {
+    let (project_name, project) = single_project(release_json)?;
+    let rpms = fetch_packages(project, Format::Rpm, RpmHeader::read, scheduler, fetcher).await?;
+    if rpms.is_empty() {
+        bail!("no active versions have .rpm artifacts");
+    }
+
+    let mut packages = String::new();
+    for rpm in &rpms {
+        let header = &rpm.metadata;
+        let (base, href) = match rpm.location.url.rsplit_once('/') {
+            Some((base, href)) => (format!("{base}/"), href),
+            None => (String::new(), rpm.location.url.as_str()),
+        };
+        let (name, ver, rel) = (
+            xml_escape(&header.name),
+            xml_escape(&header.version),
+            xml_escape(&header.release),
+        );
+        let epoch = header.epoch;
+
+        // There's no file modification time to list, so the build time is used for both.
+        write!(
+            packages,
+            r#"<package type="rpm">
+  <name>{name}</name>
+  <arch>{}</arch>
+  <version epoch="{epoch}" ver="{ver}" rel="{rel}"/>
+  <checksum type="sha256" pkgid="YES">{}</checksum>
+  <summary>{}</summary>
+  <description>{}</description>
+  <packager>{}</packager>
+  <url>{}</url>
+  <time file="{build_time}" build="{build_time}"/>
+  <size package="{}" installed="{}" archive="{}"/>
+  <location xml:base="{}" href="{}"/>
+  <format>
+"#,
+            xml_escape(&header.arch),
+            rpm.sha256,
+            xml_escape(&header.summary),
+            xml_escape(&header.description),
+            xml_escape(header.packager.as_deref().unwrap_or_default()),
+            xml_escape(header.url.as_deref().unwrap_or_default()),
+            rpm.size,
+            header.installed_size,
+            header.archive_size,
+            xml_escape(&base),
+            xml_escape(href),
+            build_time = header.build_time,
+        )
+        .expect("writing to a string is infallible");
+        for (element, value) in [
+            ("license", &header.license),
+            ("vendor", &header.vendor),
+            ("group", &header.group),
+            ("buildhost", &header.build_host),
+            ("sourcerpm", &header.source_rpm),
+        ] {
+            if let Some(value) = value {
+                writeln!(
+                    packages,
+                    "    <rpm:{element}>{}</rpm:{element}>",
+                    xml_escape(value)
+                )
+                .expect("writing to a string is infallible");
+            }
+        }
+        writeln!(
+            packages,
+            r#"    <rpm:header-range start="{}" end="{}"/>"#,
+            header.header_range.0, header.header_range.1
+        )
+        .expect("writing to a string is infallible");
+        for (element, dependencies) in [
+            ("provides", &header.provides),
+            ("requires", &header.requires),
+            ("conflicts", &header.conflicts),
+            ("obsoletes", &header.obsoletes),
+        ] {
+            write_dependencies(&mut packages, element, dependencies);
+        }
+        packages.push_str("  </format>\n</package>\n");
+    }
+    let count = rpms.len();
+
+    let primary = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata xmlns="{COMMON_NAMESPACE}" xmlns:rpm="{RPM_NAMESPACE}" packages="{count}">
+{packages}</metadata>
+"#
+    );
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    let primary_gz = encoder
+        .write_all(primary.as_bytes())
+        .and_then(|()| encoder.finish())
+        .wrap_err("failed to compress primary metadata")?;
+
+    let repodata_dir = out_dir.join("repodata");
+    std::fs::create_dir_all(&repodata_dir)
+        .wrap_err_with(|| format!("failed to create directory {repodata_dir}"))?;
+    let changed = write_bytes(&repodata_dir.join("primary.xml.gz"), &primary_gz)?;
+
+    // The timestamp changes on every run, so only rewrite repomd.xml if the metadata did.
+    let repomd_path = repodata_dir.join("repomd.xml");
+    if changed || !repomd_path.exists() {
+        let timestamp = Utc::now().timestamp();
+        let repomd = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<repomd xmlns="{REPO_NAMESPACE}" xmlns:rpm="{RPM_NAMESPACE}">
+  <revision>{timestamp}</revision>
+  <data type="primary">
+    <checksum type="sha256">{}</checksum>
+    <open-checksum type="sha256">{}</open-checksum>
+    <location href="repodata/primary.xml.gz"/>
+    <timestamp>{timestamp}</timestamp>
+    <size>{}</size>
+    <open-size>{}</open-size>
+  </data>
+</repomd>
+"#,
+            hex::encode(Sha256::digest(&primary_gz)),
+            hex::encode(Sha256::digest(primary.as_bytes())),
+            primary_gz.len(),
+            primary.len(),
+        );
+        OutputOpts::default().write_file(&repomd_path, &repomd)?;
+    }
+
+    eprintln!(
+        "wrote {count} packages for {project_name} to {repodata_dir}{}",
+        if changed { "" } else { " (unchanged)" }
+    );
+    Ok(())
+}
+
+fn write_dependencies(out: &mut String, element: &str, dependencies: &[Dependency]) {
+    if dependencies.is_empty() {
+        return;
+    }
+    writeln!(out, "    <rpm:{element}>").expect("writing to a string is infallible");
+    for dependency in dependencies {
+        write!(
+            out,
+            r#"      <rpm:entry name="{}""#,
+            xml_escape(&dependency.name)
+        )
+        .expect("writing to a string is infallible");
+        if let Some(flags) = dependency.flags {
+            let (epoch, ver, rel) = split_evr(&dependency.version);
+            write!(
+                out,
+                r#" flags="{flags}" epoch="{}" ver="{}""#,
+                xml_escape(epoch),
+                xml_escape(ver)
+            )
+            .expect("writing to a string is infallible");
+            if let Some(rel) = rel {
+                write!(out, r#" rel="{}""#, xml_escape(rel))
+                    .expect("writing to a string is infallible");
+            }
+        }
+        out.push_str("/>\n");
+    }
+    writeln!(out, "    </rpm:{element}>").expect("writing to a string is infallible");
+}
+
+/// Split a dependency version like `1:2.3-4` into its epoch, version and release.
+fn split_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let (epoch, rest) = match evr.split_once(':') {
+        Some((epoch, rest)) if epoch.bytes().all(|b| b.is_ascii_digit()) => (epoch, rest),
+        _ => ("0", evr),
+    };
+    match rest.rsplit_once('-') {
+        Some((ver, rel)) => (epoch, ver, Some(rel)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// The fields of an `.rpm`'s header that are listed in the primary metadata.
+#[derive(Debug)]
+pub(crate) struct RpmHeader {
+    name: String,
+    epoch: u64,
+    version: String,
+    release: String,
+    arch: String,
+    summary: String,
+    description: String,
+    packager: Option<String>,
+    url: Option<String>,
+    license: Option<String>,
+    vendor: Option<String>,
+    group: Option<String>,
+    build_host: Option<String>,
+    source_rpm: Option<String>,
+    build_time: u64,
+    installed_size: u64,
+    archive_size: u64,
+    /// The byte range of the main header within the file.
+    header_range: (usize, usize),
+    provides: Vec<Dependency>,
+    requires: Vec<Dependency>,
+    conflicts: Vec<Dependency>,
+    obsoletes: Vec<Dependency>,
+}
+
+/// A capability that a package provides, requires, conflicts with or obsoletes.
+#[derive(Debug, PartialEq, Eq)]
+struct Dependency {
+    name: String,
+    /// The comparison against `version`, or `None` if any version matches.
+    flags: Option<&'static str>,
+    version: String,
+}
+
+const RPM_LEAD_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
+const RPM_LEAD_LEN: usize = 96;
+
+// Signature header tags.
+const SIGTAG_PAYLOADSIZE: u32 = 1007;
+
+// Main header tags.
+const RPMTAG_NAME: u32 = 1000;
+const RPMTAG_VERSION: u32 = 1001;
+const RPMTAG_RELEASE: u32 = 1002;
+const RPMTAG_EPOCH: u32 = 1003;
+const RPMTAG_SUMMARY: u32 = 1004;
+const RPMTAG_DESCRIPTION: u32 = 1005;
+const RPMTAG_BUILDTIME: u32 = 1006;
+const RPMTAG_BUILDHOST: u32 = 1007;
+const RPMTAG_SIZE: u32 = 1009;
+const RPMTAG_VENDOR: u32 = 1011;
+const RPMTAG_LICENSE: u32 = 1014;
+const RPMTAG_PACKAGER: u32 = 1015;
+const RPMTAG_GROUP: u32 = 1016;
+const RPMTAG_URL: u32 = 1020;
+const RPMTAG_ARCH: u32 = 1022;
+const RPMTAG_SOURCERPM: u32 = 1044;
+const RPMTAG_ARCHIVESIZE: u32 = 1046;
+const RPMTAG_PROVIDE: [u32; 3] = [1047, 1112, 1113];
+const RPMTAG_REQUIRE: [u32; 3] = [1049, 1048, 1050];
+const RPMTAG_CONFLICT: [u32; 3] = [1054, 1053, 1055];
+const RPMTAG_OBSOLETE: [u32; 3] = [1090, 1114, 1115];
+const RPMTAG_LONGSIZE: u32 = 5009;
+
+impl RpmHeader {
+    /// Read the header of an `.rpm`, failing if any of the fields dnf needs are missing.
+    pub(crate) fn read(rpm: &[u8]) -> Result<Self> {
+        if !rpm.starts_with(&RPM_LEAD_MAGIC) {
+            bail!("not an RPM package");
+        }
+        let (signature, signature_end) = Header::parse(rpm, RPM_LEAD_LEN)?;
+        // The main header is aligned to 8 bytes after the signature.
+        let header_start = (signature_end + 7) & !7;
+        let (header, header_end) = Header::parse(rpm, header_start)?;
+
+        let required = |tag| {
+            header
+                .string(tag)?
+                .map(str::to_owned)
+                .ok_or_else(|| eyre!("RPM header is missing tag {tag}"))
+        };
+        let optional = |tag| Ok::<_, color_eyre::Report>(header.string(tag)?.map(str::to_owned));
+        let installed_size = match header.int(RPMTAG_LONGSIZE)? {
+            Some(size) => Some(size),
+            None => header.int(RPMTAG_SIZE)?,
+        };
+        let archive_size = match signature.int(SIGTAG_PAYLOADSIZE)? {
+            Some(size) => Some(size),
+            None => header.int(RPMTAG_ARCHIVESIZE)?,
+        };
+
+        let mut requires = header.dependencies(RPMTAG_REQUIRE)?;
+        // Requirements on rpm's own features are satisfied by rpm, not by other packages.
+        requires.retain(|dependency| !dependency.name.starts_with("rpmlib("));
+
+        Ok(Self {
+            name: required(RPMTAG_NAME)?,
+            epoch: header.int(RPMTAG_EPOCH)?.unwrap_or(0),
+            version: required(RPMTAG_VERSION)?,
+            release: required(RPMTAG_RELEASE)?,
+            arch: required(RPMTAG_ARCH)?,
+            summary: optional(RPMTAG_SUMMARY)?.unwrap_or_default(),
+            description: optional(RPMTAG_DESCRIPTION)?.unwrap_or_default(),
+            packager: optional(RPMTAG_PACKAGER)?,
+            url: optional(RPMTAG_URL)?,
+            license: optional(RPMTAG_LICENSE)?,
+            vendor: optional(RPMTAG_VENDOR)?,
+            group: optional(RPMTAG_GROUP)?,
+            build_host: optional(RPMTAG_BUILDHOST)?,
+            source_rpm: optional(RPMTAG_SOURCERPM)?,
+            build_time: header
+                .int(RPMTAG_BUILDTIME)?
+                .ok_or_else(|| eyre!("RPM header is missing the build time"))?,
+            installed_size: installed_size
+                .ok_or_else(|| eyre!("RPM header is missing the installed size"))?,
+            archive_size: archive_size
+                .ok_or_else(|| eyre!("RPM header is missing the payload size"))?,
+            header_range: (header_start, header_end),
+            provides: header.dependencies(RPMTAG_PROVIDE)?,
+            requires,
+            conflicts: header.dependencies(RPMTAG_CONFLICT)?,
+            obsoletes: header.dependencies(RPMTAG_OBSOLETE)?,
+        })
+    }
+}
+
+const RPM_HEADER_MAGIC: [u8; 3] = [0x8e, 0xad, 0xe8];
+const RPM_INT32_TYPE: u64 = 4;
+const RPM_INT64_TYPE: u64 = 5;
+const RPM_STRING_TYPE: u64 = 6;
+const RPM_STRING_ARRAY_TYPE: u64 = 8;
+const RPM_I18NSTRING_TYPE: u64 = 9;
+
+/// A signature or main header: an index of 16-byte entries, followed by the data they point into.
+struct Header<'a> {
+    index: &'a [u8],
+    store: &'a [u8],
+}
+
+impl<'a> Header<'a> {
+    /// Parse the header at `offset`, returning it along with the offset just past its end.
+    fn parse(rpm: &'a [u8], offset: usize) -> Result<(Self, usize)> {
+        let truncated = || eyre!("RPM header at offset {offset} is truncated");
+        let intro = rpm.get(offset..offset + 16).ok_or_else(truncated)?;
+        if !intro.starts_with(&RPM_HEADER_MAGIC) {
+            bail!("invalid RPM header at offset {offset}");
+        }
+        let index_len = (be(&intro[8..12]) as usize)
+            .checked_mul(16)
+            .ok_or_else(truncated)?;
+        let store_start = offset + 16 + index_len;
+        let end = store_start
+            .checked_add(be(&intro[12..16]) as usize)
+            .ok_or_else(truncated)?;
+        let header = Self {
+            index: rpm.get(offset + 16..store_start).ok_or_else(truncated)?,
+            store: rpm.get(store_start..end).ok_or_else(truncated)?,
+        };
+        Ok((header, end))
+    }
+
+    /// Returns the type, count and data of the entry for `tag`, if there is one.
+    fn entry(&self, tag: u32) -> Result<Option<(u64, usize, &'a [u8])>> {
+        let Some(entry) = self
+            .index
+            .chunks_exact(16)
+            .find(|entry| be(&entry[..4]) == u64::from(tag))
+        else {
+            return Ok(None);
+        };
+        let data = self
+            .store
+            .get(be(&entry[8..12]) as usize..)
+            .ok_or_else(|| eyre!("RPM tag {tag} points past the end of the header"))?;
+        Ok(Some((be(&entry[4..8]), be(&entry[12..16]) as usize, data)))
+    }
+
+    fn strings(&self, tag: u32) -> Result<Vec<&'a str>> {
+        let Some((kind, count, mut data)) = self.entry(tag)? else {
+            return Ok(Vec::new());
+        };
+        if !matches!(
+            kind,
+            RPM_STRING_TYPE | RPM_STRING_ARRAY_TYPE | RPM_I18NSTRING_TYPE
+        ) {
+            bail!("RPM tag {tag} isn't a string");
+        }
+        (0..count)
+            .map(|_| {
+                let len = data
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or_else(|| eyre!("RPM tag {tag} has an unterminated string"))?;
+                let string = std::str::from_utf8(&data[..len])
+                    .wrap_err_with(|| format!("RPM tag {tag} isn't valid UTF-8"))?;
+                data = &data[len + 1..];
+                Ok(string)
+            })
+            .collect()
+    }
+
+    /// Returns the first string for `tag`, which for translated strings is the untranslated one.
+    fn string(&self, tag: u32) -> Result<Option<&'a str>> {
+        Ok(self.strings(tag)?.first().copied())
+    }
+
+    fn ints(&self, tag: u32) -> Result<Vec<u64>> {
+        let Some((kind, count, data)) = self.entry(tag)? else {
+            return Ok(Vec::new());
+        };
+        let width = match kind {
+            RPM_INT32_TYPE => 4,
+            RPM_INT64_TYPE => 8,
+            _ => bail!("RPM tag {tag} isn't an integer"),
+        };
+        let data = count
+            .checked_mul(width)
+            .and_then(|len| data.get(..len))
+            .ok_or_else(|| eyre!("RPM tag {tag} points past the end of the header"))?;
+        Ok(data.chunks_exact(width).map(be).collect())
+    }
+
+    fn int(&self, tag: u32) -> Result<Option<u64>> {
+        Ok(self.ints(tag)?.first().copied())
+    }
+
+    /// Read the dependencies from parallel name, flags and version tags.
+    fn dependencies(
+        &self,
+        [name_tag, flags_tag, version_tag]: [u32; 3],
+    ) -> Result<Vec<Dependency>> {
+        let names = self.strings(name_tag)?;
+        let flags = self.ints(flags_tag)?;
+        let versions = self.strings(version_tag)?;
+        if flags.len() != names.len() || versions.len() != names.len() {
+            bail!("RPM tags {name_tag}, {flags_tag} and {version_tag} have different lengths");
+        }
+        Ok(names
+            .into_iter()
+            .zip(flags)
+            .zip(versions)
+            .map(|((name, flags), version)| Dependency {
+                name: name.to_owned(),
+                // RPMSENSE_LESS, RPMSENSE_GREATER and RPMSENSE_EQUAL.
+                flags: match flags & 0b1110 {
+                    0b0010 => Some("LT"),
+                    0b0100 => Some("GT"),
+                    0b1000 => Some("EQ"),
+                    0b1010 => Some("LE"),
+                    0b1100 => Some("GE"),
+                    _ => None,
+                },
+                version: version.to_owned(),
+            })
+            .collect())
+    }
+}
+
+/// Read a big-endian integer.
+fn be(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |acc, &byte| (acc << 8) | u64::from(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FIXTURE_RPM: &[u8] = include_bytes!("../../fixtures/hello-1.2.3-1.fc40.x86_64.rpm");
+
+    #[test]
+    fn test_read_rpm_header() {
+        let header = RpmHeader::read(FIXTURE_RPM).unwrap();
+        assert_eq!(header.name, "hello");
+        assert_eq!(header.epoch, 2);
+        assert_eq!(header.version, "1.2.3");
+        assert_eq!(header.release, "1.fc40");
+        assert_eq!(header.arch, "x86_64");
+        assert_eq!(header.summary, "Say hello");
+        assert_eq!(header.group.as_deref(), Some("Unspecified"));
+        assert_eq!(header.license.as_deref(), Some("MIT OR Apache-2.0"));
+        assert_eq!(header.packager, None);
+        assert_eq!(header.build_time, 1_700_000_000);
+        assert_eq!(header.installed_size, 4096);
+        assert_eq!(header.archive_size, 512);
+        assert_eq!(header.header_range, (152, 898));
+        assert_eq!(
+            header.provides[1],
+            Dependency {
+                name: "hello(x86-64)".to_owned(),
+                flags: Some("EQ"),
+                version: "2:1.2.3-1.fc40".to_owned(),
+            }
+        );
+        // The rpmlib() requirement is left out.
+        assert_eq!(
+            header.requires,
+            [
+                Dependency {
+                    name: "/bin/sh".to_owned(),
+                    flags: None,
+                    version: String::new(),
+                },
+                Dependency {
+                    name: "libc.so.6(GLIBC_2.34)(64bit)".to_owned(),
+                    flags: Some("GE"),
+                    version: "2.34".to_owned(),
+                },
+            ]
+        );
+        assert!(header.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_read_rpm_header_errors() {
+        assert!(RpmHeader::read(b"not an rpm").is_err());
+        // Truncating the file anywhere within the headers is an error, not a panic.
+        for len in [4, 100, 200, 897] {
+            assert!(RpmHeader::read(&FIXTURE_RPM[..len]).is_err(), "len {len}");
+        }
+    }
+
+    #[test]
+    fn test_write_dependencies() {
+        let mut out = String::new();
+        write_dependencies(
+            &mut out,
+            "requires",
+            &[
+                Dependency {
+                    name: "a".to_owned(),
+                    flags: None,
+                    version: String::new(),
+                },
+                Dependency {
+                    name: "b<c>".to_owned(),
+                    flags: Some("LE"),
+                    version: "1:2.0-3".to_owned(),
+                },
+                Dependency {
+                    name: "d".to_owned(),
+                    flags: Some("GT"),
+                    version: "4.5".to_owned(),
+                },
+            ],
+        );
+        assert_eq!(
+            out,
+            r#"    <rpm:requires>
+      <rpm:entry name="a"/>
+      <rpm:entry name="b&lt;c&gt;" flags="LE" epoch="1" ver="2.0" rel="3"/>
+      <rpm:entry name="d" flags="GT" epoch="0" ver="4.5"/>
+    </rpm:requires>
+"#
+        );
+    }
+}
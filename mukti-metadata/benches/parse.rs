@@ -0,0 +1,143 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Benchmarks for parsing, serializing and resolving versions in large release JSON files.
+
+// Benchmarks are built with a recent toolchain, so the library MSRV doesn't apply here.
+#![allow(clippy::incompatible_msrv)]
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mukti_metadata::{
+    ArtifactKind, Digest, DigestAlgorithm, Format, MuktiProject, MuktiReleasesJson,
+    ReleaseLocation, ReleaseRangeData, ReleaseStatus, ReleaseVersionData, VersionRange,
+};
+use semver::{Version, VersionReq};
+use std::{collections::BTreeMap, hint::black_box};
+
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "x86_64-unknown-linux-musl",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "aarch64-apple-darwin",
+    "universal-apple-darwin",
+    "x86_64-pc-windows-msvc",
+    "aarch64-pc-windows-msvc",
+];
+
+/// Generate a release JSON with `minors * patches` versions, each with an artifact for every
+/// target.
+fn synthetic_release_json(minors: u64, patches: u64) -> MuktiReleasesJson {
+    let mut ranges = BTreeMap::new();
+    for minor in 0..minors {
+        let mut versions = BTreeMap::new();
+        for patch in 0..patches {
+            let version = Version::new(1, minor, patch);
+            let locations = TARGETS
+                .iter()
+                .map(|target| ReleaseLocation {
+                    target: (*target).to_owned(),
+                    format: Format::TarGz,
+                    kind: ArtifactKind::Archive,
+                    url: format!(
+                        "https://github.com/example/example/releases/download/{version}/\
+                         example-{version}-{target}.tar.gz"
+                    ),
+                    mirror_urls: Vec::new(),
+                    checksums: [
+                        (DigestAlgorithm::Sha256, Digest("ab".repeat(32))),
+                        (DigestAlgorithm::Blake2b, Digest("cd".repeat(64))),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    size: None,
+                    signatures: Vec::new(),
+                    attestations: Vec::new(),
+                    min_glibc: None,
+                    min_macos: None,
+                    min_windows: None,
+                    extra: BTreeMap::new(),
+                })
+                .collect();
+            versions.insert(
+                version.clone(),
+                ReleaseVersionData {
+                    release_url: format!("https://github.com/example/example/releases/{version}"),
+                    status: ReleaseStatus::Active,
+                    locations,
+                    release_date: None,
+                    notes: None,
+                    changelog_url: None,
+                    commit: None,
+                    license: None,
+                    metadata: serde_json::Value::Null,
+                    extra: BTreeMap::new(),
+                },
+            );
+        }
+        ranges.insert(
+            VersionRange::Minor(minor),
+            ReleaseRangeData {
+                latest: Version::new(1, minor, patches - 1),
+                is_prerelease: false,
+                eol_date: None,
+                versions,
+                extra: BTreeMap::new(),
+            },
+        );
+    }
+
+    let project = MuktiProject {
+        info: Default::default(),
+        base_url: None,
+        url_template: None,
+        latest: ranges.keys().next_back().copied(),
+        latest_pinned: false,
+        ranges,
+        channels: BTreeMap::new(),
+        aliases: BTreeMap::new(),
+        extra: BTreeMap::new(),
+    };
+    MuktiReleasesJson {
+        schema_version: mukti_metadata::CURRENT_SCHEMA_VERSION,
+        digest_encoding: Default::default(),
+        projects: [("example".to_owned(), project)].into_iter().collect(),
+        extra: BTreeMap::new(),
+    }
+}
+
+fn bench_release_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("release_json");
+    for (minors, patches) in [(10, 10), (50, 20)] {
+        let release_json = synthetic_release_json(minors, patches);
+        let serialized = serde_json::to_string_pretty(&release_json).unwrap();
+        let versions = minors * patches;
+
+        group.throughput(Throughput::Bytes(serialized.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("parse", versions),
+            &serialized,
+            |b, serialized| {
+                b.iter(|| serde_json::from_str::<MuktiReleasesJson>(black_box(serialized)).unwrap())
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("serialize", versions),
+            &release_json,
+            |b, release_json| b.iter(|| serde_json::to_string_pretty(black_box(release_json))),
+        );
+
+        let project = &release_json.projects["example"];
+        let req = VersionReq::parse("^1.5").unwrap();
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(
+            BenchmarkId::new("resolve", versions),
+            project,
+            |b, project| b.iter(|| project.get_latest_matching(black_box(&req))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_release_json);
+criterion_main!(benches);
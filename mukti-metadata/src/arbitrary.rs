@@ -0,0 +1,409 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! [`Arbitrary`] implementations, for property-testing code that reads and writes release
+//! metadata.
+//!
+//! Generated values are valid in the same way mukti keeps them: every version is stored under
+//! the range it belongs to, each range's `latest` and `is_prerelease` agree with its versions,
+//! and a project's `latest` points at its newest range that isn't a pre-release.
+
+use crate::{
+    ArtifactKind, AttestationEntry, Digest, DigestAlgorithm, DigestEncoding, Format, MuktiProject,
+    MuktiReleasesJson, ProjectAlias, ProjectInfo, ReleaseLocation, ReleaseRangeData, ReleaseStatus,
+    ReleaseVersionData, SignatureEntry, VersionRange, CURRENT_SCHEMA_VERSION, SOURCE_TARGET,
+};
+use proptest::{
+    collection::{btree_map, vec},
+    option,
+    prelude::*,
+    sample::select,
+};
+use semver::{Prerelease, Version};
+use std::collections::BTreeMap;
+
+const PROJECT_NAMES: &[&str] = &["mukti", "cargo-nextest"];
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-apple-darwin",
+    "x86_64-pc-windows-msvc",
+    SOURCE_TARGET,
+];
+const PRERELEASES: &[&str] = &["alpha.1", "beta.2", "rc.1"];
+const DATES: &[&str] = &["2022-06-12T00:00:00Z", "2024-11-24T12:30:00Z"];
+const BASE_URL: &str = "https://example.com/releases";
+
+impl Arbitrary for MuktiReleasesJson {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            any::<DigestEncoding>(),
+            btree_map(
+                select(PROJECT_NAMES).prop_map(str::to_owned),
+                any::<MuktiProject>(),
+                1..=PROJECT_NAMES.len(),
+            ),
+        )
+            .prop_map(|(digest_encoding, projects)| MuktiReleasesJson {
+                schema_version: CURRENT_SCHEMA_VERSION,
+                digest_encoding,
+                projects,
+                extra: BTreeMap::new(),
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for DigestEncoding {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![Just(DigestEncoding::Hex), Just(DigestEncoding::Base64)].boxed()
+    }
+}
+
+impl Arbitrary for MuktiProject {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            option::of(Just(BASE_URL.to_owned())),
+            option::of(Just("Release metadata manager".to_owned())),
+            btree_map(version(), any::<ReleaseVersionData>(), 1..8),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(base_url, description, versions, stable_channel, linux_alias)| {
+                    let mut ranges: BTreeMap<VersionRange, ReleaseRangeData> = BTreeMap::new();
+                    for (version, version_data) in versions {
+                        ranges
+                            .entry(VersionRange::from_version(&version))
+                            .or_insert_with(|| ReleaseRangeData {
+                                latest: version.clone(),
+                                is_prerelease: false,
+                                eol_date: None,
+                                versions: BTreeMap::new(),
+                                extra: BTreeMap::new(),
+                            })
+                            .versions
+                            .insert(version, version_data);
+                    }
+                    for data in ranges.values_mut() {
+                        update_range_latest(data);
+                    }
+                    let latest = ranges
+                        .iter()
+                        .filter(|(_, data)| !data.is_prerelease)
+                        .map(|(range, _)| *range)
+                        .max();
+
+                    let mut channels = BTreeMap::new();
+                    if let (true, Some(range)) = (stable_channel, latest) {
+                        channels.insert("stable".to_owned(), ranges[&range].latest.clone());
+                    }
+                    let mut aliases = BTreeMap::new();
+                    if linux_alias {
+                        aliases.insert(
+                            "linux".to_owned(),
+                            ProjectAlias {
+                                target: "x86_64-unknown-linux-gnu".to_owned(),
+                                format: "tar.gz".to_owned(),
+                                version_req: None,
+                                extra: BTreeMap::new(),
+                            },
+                        );
+                    }
+
+                    MuktiProject {
+                        info: ProjectInfo {
+                            description,
+                            ..ProjectInfo::default()
+                        },
+                        base_url,
+                        url_template: None,
+                        latest,
+                        latest_pinned: false,
+                        ranges,
+                        channels,
+                        aliases,
+                        extra: BTreeMap::new(),
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for ReleaseVersionData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            any::<ReleaseStatus>(),
+            vec(any::<ReleaseLocation>(), 0..4),
+            option::of(select(DATES).prop_map(str::to_owned)),
+            option::of(Just("Bug fixes and performance improvements.".to_owned())),
+            option::of(any::<[u8; 20]>().prop_map(hex::encode)),
+            option::of(Just("MIT OR Apache-2.0".to_owned())),
+            prop_oneof![
+                Just(serde_json::Value::Null),
+                Just(serde_json::json!({ "installer": { "min-version": "0.1.0" } })),
+            ],
+        )
+            .prop_map(
+                |(status, locations, release_date, notes, commit, license, metadata)| {
+                    ReleaseVersionData {
+                        release_url: "https://example.com/changelog".to_owned(),
+                        status,
+                        locations,
+                        release_date,
+                        notes,
+                        changelog_url: None,
+                        commit,
+                        license,
+                        metadata,
+                        extra: BTreeMap::new(),
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for ReleaseStatus {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(ReleaseStatus::Active),
+            (
+                option::of(Just("miscompiled on Windows".to_owned())),
+                option::of(select(DATES).prop_map(str::to_owned)),
+            )
+                .prop_map(|(reason, date)| ReleaseStatus::Yanked { reason, date }),
+            Just(ReleaseStatus::Deprecated),
+            Just(ReleaseStatus::EndOfLife),
+            Just(ReleaseStatus::Unknown("withdrawn".to_owned())),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for ReleaseLocation {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            (
+                select(TARGETS).prop_map(str::to_owned),
+                any::<Format>(),
+                any::<ArtifactKind>(),
+                url(),
+                vec(url(), 0..2),
+            ),
+            (
+                vec(any::<u8>(), 32),
+                option::of(vec(any::<u8>(), 64)),
+                option::of(any::<u64>()),
+            ),
+            (
+                vec(signature(), 0..2),
+                vec(attestation(), 0..2),
+                option::of(Just("2.17".to_owned())),
+                option::of(Just("11.0".to_owned())),
+                option::of(Just("10.0.17763".to_owned())),
+            ),
+        )
+            .prop_map(
+                |(
+                    (target, format, kind, url, mirror_urls),
+                    (sha256, blake2b, size),
+                    (signatures, attestations, min_glibc, min_macos, min_windows),
+                )| {
+                    let mut checksums = BTreeMap::new();
+                    checksums.insert(DigestAlgorithm::Sha256, Digest(hex::encode(sha256)));
+                    if let Some(blake2b) = blake2b {
+                        checksums.insert(DigestAlgorithm::Blake2b, Digest(hex::encode(blake2b)));
+                    }
+                    ReleaseLocation {
+                        target,
+                        format,
+                        kind,
+                        url,
+                        mirror_urls,
+                        checksums,
+                        size,
+                        signatures,
+                        attestations,
+                        min_glibc,
+                        min_macos,
+                        min_windows,
+                        extra: BTreeMap::new(),
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Format {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(vec![
+            Format::TarGz,
+            Format::TarXz,
+            Format::TarZst,
+            Format::Zip,
+            Format::Msi,
+            Format::Dmg,
+            Format::Deb,
+            Format::Rpm,
+            Format::Other("tar.bz2".to_owned()),
+        ])
+        .boxed()
+    }
+}
+
+impl Arbitrary for ArtifactKind {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        select(vec![
+            ArtifactKind::Archive,
+            ArtifactKind::Installer,
+            ArtifactKind::ChecksumFile,
+            ArtifactKind::Signature,
+            ArtifactKind::Sbom,
+            ArtifactKind::DebugSymbols,
+            ArtifactKind::Other("provenance".to_owned()),
+        ])
+        .boxed()
+    }
+}
+
+/// Versions spread over a few major, minor and patch ranges, some of them pre-releases.
+fn version() -> impl Strategy<Value = Version> {
+    (0..3u64, 0..3u64, 0..4u64, option::of(select(PRERELEASES))).prop_map(
+        |(major, minor, patch, pre)| {
+            let mut version = Version::new(major, minor, patch);
+            if let Some(pre) = pre {
+                version.pre = Prerelease::new(pre).expect("pre-release is valid");
+            }
+            version
+        },
+    )
+}
+
+/// URLs, some of them under [`BASE_URL`] and some of them elsewhere.
+fn url() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<u16>().prop_map(|n| format!("{BASE_URL}/artifact-{n}")),
+        any::<u16>().prop_map(|n| format!("https://mirror.example.org/artifact-{n}")),
+    ]
+}
+
+fn signature() -> impl Strategy<Value = SignatureEntry> {
+    (option::of(Just("RWT1".to_owned())), url()).prop_map(|(key_id, url)| SignatureEntry {
+        scheme: "minisign".to_owned(),
+        key_id,
+        url: Some(url),
+        value: None,
+        extra: BTreeMap::new(),
+    })
+}
+
+fn attestation() -> impl Strategy<Value = AttestationEntry> {
+    url().prop_map(|url| AttestationEntry {
+        predicate_type: "https://slsa.dev/provenance/v1".to_owned(),
+        url,
+        extra: BTreeMap::new(),
+    })
+}
+
+/// Sets `latest` and `is_prerelease` the way mukti does when a version is added: the newest
+/// version that isn't a pre-release, preferring ones that aren't yanked.
+fn update_range_latest(data: &mut ReleaseRangeData) {
+    let non_prereleases = || {
+        data.versions
+            .iter()
+            .rev()
+            .filter(|(version, _)| version.pre.is_empty())
+    };
+    let (latest, is_prerelease) = match non_prereleases()
+        .find(|(_, version_data)| !version_data.status.is_yanked())
+        .or_else(|| non_prereleases().next())
+    {
+        Some((version, _)) => (version.clone(), false),
+        None => (
+            data.versions
+                .keys()
+                .next_back()
+                .expect("range has at least one version")
+                .clone(),
+            true,
+        ),
+    };
+    data.latest = latest;
+    data.is_prerelease = is_prerelease;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, ShardedIndex};
+
+    proptest! {
+        #[test]
+        fn serde_round_trip(json in any::<MuktiReleasesJson>()) {
+            let serialized = serde_json::to_string(&json).unwrap();
+            let json2: MuktiReleasesJson = serde_json::from_str(&serialized).unwrap();
+            prop_assert_eq!(
+                serde_json::to_value(&json2).unwrap(),
+                serde_json::to_value(&json).unwrap()
+            );
+            prop_assert!(diff(&json, &json2).is_empty());
+        }
+
+        #[test]
+        fn sharded_round_trip(json in any::<MuktiReleasesJson>()) {
+            let (index, shards) = json.to_sharded().unwrap();
+            let index: ShardedIndex =
+                serde_json::from_str(&serde_json::to_string(&index).unwrap()).unwrap();
+            let shards: BTreeMap<_, _> = shards
+                .into_iter()
+                .map(|(path, shard)| (path, serde_json::to_string(&shard).unwrap()))
+                .collect();
+            let assembled = index
+                .assemble(|path| serde_json::from_str(&shards[path]))
+                .unwrap();
+            prop_assert_eq!(
+                serde_json::to_value(&assembled).unwrap(),
+                serde_json::to_value(&json).unwrap()
+            );
+        }
+
+        #[test]
+        fn versions_in_ranges(project in any::<MuktiProject>()) {
+            for (range, data) in &project.ranges {
+                for version in data.versions.keys() {
+                    prop_assert_eq!(VersionRange::from_version(version), *range);
+                }
+                prop_assert!(data.versions.contains_key(&data.latest));
+            }
+            if let Some(latest) = project.latest {
+                prop_assert!(!project.ranges[&latest].is_prerelease);
+            }
+        }
+    }
+}
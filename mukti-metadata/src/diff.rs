@@ -0,0 +1,384 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    ArtifactKind, Digest, DigestAlgorithm, Format, MuktiProject, MuktiReleasesJson,
+    ReleaseLocation, ReleaseStatus, ReleaseVersionData,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// The differences between two releases.json files, as returned by [`diff`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReleaseDiff {
+    /// Map of project name to what changed in it. Projects that didn't change aren't included.
+    pub projects: BTreeMap<String, ProjectDiff>,
+}
+
+impl ReleaseDiff {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty()
+    }
+}
+
+/// What changed in a project, as part of a [`ReleaseDiff`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProjectDiff {
+    /// Versions that are only in the new file, in ascending order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_versions: Vec<Version>,
+
+    /// Versions that are only in the old file, in ascending order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_versions: Vec<Version>,
+
+    /// Map of versions in both files to what changed in them. Versions that didn't change aren't
+    /// included.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub changed_versions: BTreeMap<Version, VersionDiff>,
+
+    /// The old and new latest version, if it changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest: Option<Change<Option<Version>>>,
+}
+
+impl ProjectDiff {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added_versions.is_empty()
+            && self.removed_versions.is_empty()
+            && self.changed_versions.is_empty()
+            && self.latest.is_none()
+    }
+}
+
+/// What changed in a version, as part of a [`ProjectDiff`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VersionDiff {
+    /// The old and new status, if it changed, e.g. when a version is yanked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Change<ReleaseStatus>>,
+
+    /// Locations that are only in the new file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_locations: Vec<LocationId>,
+
+    /// Locations that are only in the old file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_locations: Vec<LocationId>,
+
+    /// Checksums that were added, removed or changed for locations in both files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksum_changes: Vec<ChecksumChange>,
+
+    /// Other fields that changed, like `notes` for the version, or
+    /// `locations[x86_64-unknown-linux-gnu:tar.gz].url` for one of its locations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed_fields: Vec<String>,
+}
+
+impl VersionDiff {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.added_locations.is_empty()
+            && self.removed_locations.is_empty()
+            && self.checksum_changes.is_empty()
+            && self.changed_fields.is_empty()
+    }
+}
+
+/// An old and a new value.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Change<T> {
+    /// The value in the old file.
+    pub old: T,
+
+    /// The value in the new file.
+    pub new: T,
+}
+
+/// Identifies a location within a version by its target, format and kind.
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LocationId {
+    /// See [`ReleaseLocation::target`].
+    pub target: String,
+
+    /// See [`ReleaseLocation::format`].
+    pub format: Format,
+
+    /// See [`ReleaseLocation::kind`].
+    #[serde(default, skip_serializing_if = "ArtifactKind::is_archive")]
+    pub kind: ArtifactKind,
+}
+
+impl LocationId {
+    /// Returns the ID of `location`.
+    pub fn new(location: &ReleaseLocation) -> Self {
+        Self {
+            target: location.target.clone(),
+            format: location.format.clone(),
+            kind: location.kind.clone(),
+        }
+    }
+}
+
+impl fmt::Display for LocationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.target, self.format)?;
+        if !self.kind.is_archive() {
+            write!(f, " ({})", self.kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// A checksum that was added, removed or changed, as part of a [`VersionDiff`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ChecksumChange {
+    /// The location the checksum is for.
+    pub location: LocationId,
+
+    /// The checksum algorithm.
+    pub algorithm: DigestAlgorithm,
+
+    /// The checksum in the old file, if it had one.
+    pub old: Option<Digest>,
+
+    /// The checksum in the new file, if it has one.
+    pub new: Option<Digest>,
+}
+
+/// Compare two releases.json files, returning what changed from `old` to `new`.
+///
+/// Versions are matched up by their version number, and locations within a version by their
+/// target, format and kind.
+pub fn diff(old: &MuktiReleasesJson, new: &MuktiReleasesJson) -> ReleaseDiff {
+    let names: BTreeSet<_> = old.projects.keys().chain(new.projects.keys()).collect();
+    let projects = names
+        .into_iter()
+        .filter_map(|name| {
+            let project_diff = diff_project(old.projects.get(name), new.projects.get(name));
+            (!project_diff.is_empty()).then(|| (name.clone(), project_diff))
+        })
+        .collect();
+    ReleaseDiff { projects }
+}
+
+fn diff_project(old: Option<&MuktiProject>, new: Option<&MuktiProject>) -> ProjectDiff {
+    let old_versions = versions_of(old);
+    let new_versions = versions_of(new);
+
+    let mut project_diff = ProjectDiff::default();
+    for (version, new_data) in &new_versions {
+        match old_versions.get(version) {
+            Some(old_data) => {
+                let version_diff = diff_version(old_data, new_data);
+                if !version_diff.is_empty() {
+                    project_diff
+                        .changed_versions
+                        .insert((*version).clone(), version_diff);
+                }
+            }
+            None => project_diff.added_versions.push((*version).clone()),
+        }
+    }
+    project_diff.removed_versions = old_versions
+        .keys()
+        .filter(|version| !new_versions.contains_key(*version))
+        .map(|version| (*version).clone())
+        .collect();
+
+    let latest_of = |project: Option<&MuktiProject>| {
+        let (version, _) = project?.latest_version_data()?;
+        Some(version.clone())
+    };
+    let (old_latest, new_latest) = (latest_of(old), latest_of(new));
+    if old_latest != new_latest {
+        project_diff.latest = Some(Change {
+            old: old_latest,
+            new: new_latest,
+        });
+    }
+
+    project_diff
+}
+
+fn versions_of(project: Option<&MuktiProject>) -> BTreeMap<&Version, &ReleaseVersionData> {
+    project
+        .map(|project| project.all_versions().collect())
+        .unwrap_or_default()
+}
+
+fn diff_version(old: &ReleaseVersionData, new: &ReleaseVersionData) -> VersionDiff {
+    let mut version_diff = VersionDiff::default();
+    // The yank reason and date are stored next to the status. They're part of a status change if
+    // there is one, and show up in changed_fields otherwise.
+    let skip: &[&str] = if old.status.as_str() != new.status.as_str() {
+        version_diff.status = Some(Change {
+            old: old.status.clone(),
+            new: new.status.clone(),
+        });
+        &["status", "yank_reason", "yank_date", "locations"]
+    } else {
+        &["status", "locations"]
+    };
+    version_diff.changed_fields = changed_fields(old, new, skip);
+
+    let old_locations = locations_of(old);
+    let new_locations = locations_of(new);
+    for (id, new_location) in &new_locations {
+        let old_location = match old_locations.get(id) {
+            Some(old_location) => old_location,
+            None => {
+                version_diff.added_locations.push(id.clone());
+                continue;
+            }
+        };
+
+        let algorithms: BTreeSet<_> = old_location
+            .checksums
+            .keys()
+            .chain(new_location.checksums.keys())
+            .collect();
+        for algorithm in algorithms {
+            let old_digest = old_location.checksums.get(algorithm);
+            let new_digest = new_location.checksums.get(algorithm);
+            if old_digest.map(|digest| &digest.0) != new_digest.map(|digest| &digest.0) {
+                version_diff.checksum_changes.push(ChecksumChange {
+                    location: id.clone(),
+                    algorithm: algorithm.clone(),
+                    old: old_digest.cloned(),
+                    new: new_digest.cloned(),
+                });
+            }
+        }
+
+        version_diff.changed_fields.extend(
+            changed_fields(old_location, new_location, &["checksums"])
+                .into_iter()
+                .map(|field| format!("locations[{}:{}].{}", id.target, id.format, field)),
+        );
+    }
+    version_diff.removed_locations = old_locations
+        .keys()
+        .filter(|id| !new_locations.contains_key(*id))
+        .cloned()
+        .collect();
+
+    version_diff
+}
+
+fn locations_of(version_data: &ReleaseVersionData) -> BTreeMap<LocationId, &ReleaseLocation> {
+    version_data
+        .locations
+        .iter()
+        .map(|location| (LocationId::new(location), location))
+        .collect()
+}
+
+/// Returns the names of serialized fields that differ between `old` and `new`, other than `skip`.
+fn changed_fields<T: Serialize>(old: &T, new: &T, skip: &[&str]) -> Vec<String> {
+    let to_map = |value: &T| match serde_json::to_value(value) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let (old, new) = (to_map(old), to_map(new));
+    let fields: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| !skip.contains(&field.as_str()) && old.get(*field) != new.get(*field))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VersionRange;
+
+    static FIXTURE_JSON: &str = include_str!("../../fixtures/mukti-releases.json");
+
+    #[test]
+    fn test_diff() {
+        let old: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        assert!(diff(&old, &old).is_empty(), "no changes");
+
+        let mut new = old.clone();
+        let project = new.projects.get_mut("mukti").unwrap();
+        project.latest = Some(VersionRange::Minor(6));
+        let range_data = project.ranges.get_mut(&VersionRange::Minor(5)).unwrap();
+        let removed = version("0.5.1");
+        range_data.versions.remove(&removed).unwrap();
+        let changed = version("0.5.3");
+        let version_data = range_data.versions.get_mut(&changed).unwrap();
+        version_data.status = ReleaseStatus::Yanked {
+            reason: None,
+            date: None,
+        };
+        version_data.notes = Some("notes".to_owned());
+        let location = &mut version_data.locations[0];
+        location.url = "https://example.com/new.tar.gz".to_owned();
+        location
+            .checksums
+            .insert(DigestAlgorithm::Blake2b, Digest("ab".repeat(64)));
+        let location_id = LocationId::new(location);
+        version_data.locations.pop();
+
+        let release_diff = diff(&old, &new);
+        let project_diff = &release_diff.projects["mukti"];
+        assert_eq!(project_diff.added_versions, Vec::<Version>::new());
+        assert_eq!(project_diff.removed_versions, vec![removed]);
+        let latest = project_diff.latest.as_ref().unwrap();
+        assert_eq!(
+            (latest.old.as_ref(), latest.new.as_ref()),
+            (Some(&changed), Some(&version("0.6.0-alpha.1")))
+        );
+
+        let version_diff = &project_diff.changed_versions[&changed];
+        let status = version_diff.status.as_ref().unwrap();
+        assert_eq!(
+            (status.old.as_str(), status.new.as_str()),
+            ("active", "yanked")
+        );
+        assert_eq!(version_diff.removed_locations.len(), 1);
+        assert_eq!(version_diff.checksum_changes.len(), 1);
+        assert_eq!(version_diff.checksum_changes[0].location, location_id);
+        assert_eq!(
+            version_diff.changed_fields,
+            vec![
+                "notes".to_owned(),
+                format!(
+                    "locations[{}:{}].url",
+                    location_id.target, location_id.format
+                ),
+            ],
+        );
+
+        let reversed = diff(&new, &old);
+        assert_eq!(
+            reversed.projects["mukti"].added_versions,
+            project_diff.removed_versions
+        );
+
+        // The diff round-trips through JSON.
+        let value = serde_json::to_value(&release_diff).unwrap();
+        let release_diff2: ReleaseDiff = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&release_diff2).unwrap(), value);
+    }
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+}
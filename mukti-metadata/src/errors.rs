@@ -1,8 +1,8 @@
 // Copyright (c) The mukti Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::VersionRangeKind;
-use std::num::ParseIntError;
+use crate::{DigestAlgorithm, VersionRange, VersionRangeKind};
+use std::{io, num::ParseIntError, path::PathBuf};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -29,3 +29,238 @@ impl VersionRangeParseError {
         }
     }
 }
+
+/// An error that occurred while decoding a digest from its serialized form.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+#[error("error decoding digest `{input}`")]
+pub struct DigestDecodeError {
+    /// The input that failed to decode.
+    pub input: String,
+
+    /// The error that occurred.
+    #[source]
+    pub error: base64::DecodeError,
+}
+
+impl DigestDecodeError {
+    pub(crate) fn new(input: &str, error: base64::DecodeError) -> Self {
+        Self {
+            input: input.to_owned(),
+            error,
+        }
+    }
+}
+
+/// An error that occurred while parsing a digest.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DigestParseError {
+    /// The digest contains characters that aren't hex digits.
+    #[error("{algorithm} digest `{input}` isn't a hex string")]
+    InvalidHex {
+        /// The algorithm the digest is for.
+        algorithm: DigestAlgorithm,
+
+        /// The input that failed to parse.
+        input: String,
+    },
+
+    /// The digest has the wrong length for its algorithm.
+    #[error("{algorithm} digest `{input}` has {actual} hex digits, expected {expected}")]
+    InvalidLength {
+        /// The algorithm the digest is for.
+        algorithm: DigestAlgorithm,
+
+        /// The input that failed to parse.
+        input: String,
+
+        /// The number of hex digits in the input.
+        actual: usize,
+
+        /// The number of hex digits digests for this algorithm have.
+        expected: usize,
+    },
+
+    /// A prefixed digest didn't start with an algorithm.
+    #[error("digest `{input}` isn't in the form ALGORITHM:HEX")]
+    MissingAlgorithm {
+        /// The input that failed to parse.
+        input: String,
+    },
+}
+
+/// An error that occurred while reading or writing typed metadata for a version.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TypedMetadataError {
+    /// The version's metadata isn't a JSON object, so it can't be split up by key.
+    #[error("metadata is {kind}, not an object")]
+    NotAnObject {
+        /// The kind of JSON value the metadata is, e.g. "an array".
+        kind: &'static str,
+    },
+
+    /// The value under a key couldn't be converted to or from JSON.
+    #[error("error converting metadata under key `{key}`")]
+    Convert {
+        /// The key the value is stored under.
+        key: String,
+
+        /// The error that occurred.
+        #[source]
+        error: serde_json::Error,
+    },
+}
+
+/// An error that occurred while reassembling a sharded releases.json.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ShardAssembleError<E> {
+    /// A shard could not be loaded.
+    #[error("error loading shard `{path}`")]
+    Load {
+        /// The path to the shard, relative to the index.
+        path: String,
+
+        /// The error that occurred.
+        #[source]
+        error: E,
+    },
+
+    /// A shard did not contain the range the index points to it for.
+    #[error("shard `{path}` does not contain range {range} of project `{project}`")]
+    MissingRange {
+        /// The path to the shard, relative to the index.
+        path: String,
+
+        /// The project the range belongs to.
+        project: String,
+
+        /// The missing range.
+        range: VersionRange,
+    },
+
+    /// The index points to a shard outside the index's directory.
+    #[error("shard path `{path}` isn't a relative path within the index's directory")]
+    InvalidPath {
+        /// The path to the shard, relative to the index.
+        path: String,
+    },
+}
+
+/// An error that occurred while splitting a releases.json into shards.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ToShardedError {
+    /// A project name can't be used as the name of the directory its shards are written to.
+    #[error("project name `{name}` can't be used as a directory name for its shards")]
+    InvalidProjectName {
+        /// The project name.
+        name: String,
+    },
+}
+
+/// An error that occurred while writing a releases.json to disk.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WriteReleasesJsonError {
+    /// The path is the index of a sharded layout, which can't be overwritten.
+    #[error(
+        "`{}` is the index of a sharded releases.json, which can't be overwritten",
+        .path.display()
+    )]
+    ShardedIndex {
+        /// The path to the index.
+        path: PathBuf,
+    },
+
+    /// The releases.json could not be serialized.
+    #[error("error serializing releases.json to `{}`", .path.display())]
+    Serialize {
+        /// The path being written to.
+        path: PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// The file could not be written.
+    #[error("error writing `{}`", .path.display())]
+    Write {
+        /// The path being written to.
+        path: PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: io::Error,
+    },
+}
+
+/// An error that occurred while reading a releases.json from disk.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ReadReleasesJsonError {
+    /// A file could not be read.
+    #[error("error reading `{}`", .path.display())]
+    Read {
+        /// The path to the file.
+        path: PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: io::Error,
+    },
+
+    /// A file could not be parsed.
+    #[error("error parsing `{}`", .path.display())]
+    Parse {
+        /// The path to the file.
+        path: PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// A file has a newer schema version than this crate understands.
+    #[error(
+        "`{}` has schema version {schema_version}, but only versions up to {} are supported",
+        .path.display(),
+        crate::CURRENT_SCHEMA_VERSION
+    )]
+    UnsupportedSchema {
+        /// The path to the file.
+        path: PathBuf,
+
+        /// The schema version of the file.
+        schema_version: u32,
+    },
+
+    /// A shard did not contain the range the index points to it for.
+    #[error("shard `{}` does not contain range {range} of project `{project}`", .path.display())]
+    MissingRange {
+        /// The path to the shard.
+        path: PathBuf,
+
+        /// The project the range belongs to.
+        project: String,
+
+        /// The missing range.
+        range: VersionRange,
+    },
+
+    /// An index points to a shard outside the index's directory.
+    #[error(
+        "`{}` points to shard `{shard_path}`, which isn't a relative path within its directory",
+        .path.display()
+    )]
+    InvalidShardPath {
+        /// The path to the index.
+        path: PathBuf,
+
+        /// The path to the shard, as written in the index.
+        shard_path: String,
+    },
+}
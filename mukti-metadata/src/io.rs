@@ -0,0 +1,96 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{read_releases_json, MuktiReleasesJson, ReadReleasesJsonError};
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+impl MuktiReleasesJson {
+    /// Reads a releases.json from disk.
+    ///
+    /// This is the same as [`read_releases_json`]: if the file is the index of a sharded layout,
+    /// the shards it points to are read as well, and files with newer schemas are rejected.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ReadReleasesJsonError> {
+        read_releases_json(path.as_ref())
+    }
+
+    /// Parses a releases.json from a reader.
+    ///
+    /// Unlike [`Self::from_path`], this can't follow a sharded index to its shards, and doesn't
+    /// check the schema version. Use [`crate::detect_schema_version`] on the contents first if
+    /// that matters.
+    pub fn from_reader(reader: impl io::Read) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(reader)
+    }
+
+    /// Serializes this releases.json to a writer, in the pretty-printed form mukti writes to
+    /// disk.
+    pub fn to_writer_pretty(&self, writer: impl io::Write) -> Result<(), serde_json::Error> {
+        let mut writer = BufWriter::new(writer);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush().map_err(serde_json::Error::io)
+    }
+
+    /// Writes this releases.json to `path` atomically, so readers never see a partially-written
+    /// file.
+    ///
+    /// Fails without writing anything if `path` is the index of a sharded layout, since
+    /// overwriting it would leave its shards orphaned.
+    #[cfg(feature = "atomicwrites")]
+    pub fn write_atomic(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), crate::WriteReleasesJsonError> {
+        use crate::{detect_layout, Layout, WriteReleasesJsonError};
+        use atomicwrites::{AtomicFile, OverwriteBehavior};
+
+        let path = path.as_ref();
+        if let Ok(existing) = std::fs::read_to_string(path) {
+            if detect_layout(&existing).ok() == Some(Layout::Sharded) {
+                return Err(WriteReleasesJsonError::ShardedIndex {
+                    path: path.to_owned(),
+                });
+            }
+        }
+
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| self.to_writer_pretty(f))
+            .map_err(|error| match error {
+                atomicwrites::Error::Internal(error) => WriteReleasesJsonError::Write {
+                    path: path.to_owned(),
+                    error,
+                },
+                atomicwrites::Error::User(error) => WriteReleasesJsonError::Serialize {
+                    path: path.to_owned(),
+                    error,
+                },
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FIXTURE_JSON: &str = include_str!("../../fixtures/mukti-releases.json");
+
+    #[test]
+    fn test_reader_writer_round_trip() {
+        let json = MuktiReleasesJson::from_reader(FIXTURE_JSON.as_bytes()).unwrap();
+
+        let mut buf = Vec::new();
+        json.to_writer_pretty(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf.clone()).unwrap(),
+            serde_json::to_string_pretty(&json).unwrap(),
+        );
+
+        let json2 = MuktiReleasesJson::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&json2).unwrap(),
+            serde_json::to_value(&json).unwrap()
+        );
+    }
+}
@@ -1,8 +1,17 @@
 // Copyright (c) The mukti Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+#[cfg(any(test, feature = "proptest"))]
+mod arbitrary;
+mod diff;
 mod errors;
+mod io;
 mod models;
+mod paths;
+mod sharded;
 
+pub use diff::*;
 pub use errors::*;
 pub use models::*;
+pub use paths::*;
+pub use sharded::*;
@@ -1,25 +1,247 @@
 // Copyright (c) The mukti Contributors
 // SPDX-License-Identifier: MIT or Apache-2.0
 
-use crate::VersionRangeParseError;
-use semver::{Version, VersionReq};
-use serde::{de::Visitor, ser::SerializeMap, Deserialize, Serialize, Serializer};
-use std::{borrow::Cow, collections::BTreeMap, fmt, str::FromStr};
+use crate::{DigestDecodeError, DigestParseError, TypedMetadataError, VersionRangeParseError};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use semver::{BuildMetadata, Comparator, Op, Version, VersionReq};
+use serde::{
+    de::{DeserializeOwned, Visitor},
+    ser::SerializeMap,
+    Deserialize, Serialize, Serializer,
+};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// The newest schema version understood by this crate.
+///
+/// * Version 0: the original schema, without a `schema_version` field.
+/// * Version 1: adds `digest_encoding`, which older readers would silently misinterpret.
+/// * Version 2: adds `base_url`, which makes location URLs relative, and `url_template`, which
+///   leaves them empty. Older readers would treat them as absolute URLs.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// The pseudo-target for artifacts that aren't specific to a platform, like source tarballs,
+/// SBOMs and shell completions.
+pub const SOURCE_TARGET: &str = "source";
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(try_from = "MuktiReleasesJsonRepr", into = "MuktiReleasesJsonRepr")]
 pub struct MuktiReleasesJson {
+    /// The version of the schema this file follows.
+    ///
+    /// Files without this field have schema version 0. See [`CURRENT_SCHEMA_VERSION`].
+    pub schema_version: u32,
+
+    /// How digests are encoded when serialized.
+    ///
+    /// In memory, digests are always hex-encoded: conversion to and from other encodings happens
+    /// during serialization and deserialization.
+    pub digest_encoding: DigestEncoding,
+
     /// The projects that are part of this releases.json.
     pub projects: BTreeMap<String, MuktiProject>,
+
+    /// Fields not known to this version of the crate.
+    ///
+    /// These are kept so that editing a file written by a newer version doesn't drop them. Every
+    /// other type in the file has an `extra` field for the same purpose.
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The serialized form of [`MuktiReleasesJson`], with digests in their on-disk encoding.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(description = "A releases.json file."))]
+struct MuktiReleasesJsonRepr {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    schema_version: u32,
+
+    #[serde(default, skip_serializing_if = "DigestEncoding::is_hex")]
+    digest_encoding: DigestEncoding,
+
+    projects: BTreeMap<String, MuktiProject>,
+
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for MuktiReleasesJson {
+    fn schema_name() -> String {
+        "MuktiReleasesJson".to_owned()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        MuktiReleasesJsonRepr::json_schema(gen)
+    }
+}
+
+impl TryFrom<MuktiReleasesJsonRepr> for MuktiReleasesJson {
+    type Error = DigestDecodeError;
+
+    fn try_from(repr: MuktiReleasesJsonRepr) -> Result<Self, Self::Error> {
+        let mut projects = repr.projects;
+        for (name, project) in &mut projects {
+            project.resolve_urls(name);
+        }
+        if repr.digest_encoding == DigestEncoding::Base64 {
+            for_each_digest(&mut projects, |digest| {
+                let bytes = BASE64_STANDARD
+                    .decode(&digest.0)
+                    .map_err(|err| DigestDecodeError::new(&digest.0, err))?;
+                digest.0 = hex::encode(bytes);
+                Ok(())
+            })?;
+        }
+        Ok(Self {
+            schema_version: repr.schema_version,
+            digest_encoding: repr.digest_encoding,
+            projects,
+            extra: repr.extra,
+        })
+    }
+}
+
+impl MuktiReleasesJson {
+    /// The lowest schema version that readers must understand to read this file correctly, given
+    /// the features it uses.
+    ///
+    /// Files are always written with at least this version, so that older readers reject them
+    /// rather than misinterpreting them.
+    pub fn required_schema_version(&self) -> u32 {
+        if self
+            .projects
+            .values()
+            .any(|project| project.base_url.is_some() || project.url_template.is_some())
+        {
+            2
+        } else if self.digest_encoding == DigestEncoding::Base64 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl From<MuktiReleasesJson> for MuktiReleasesJsonRepr {
+    fn from(json: MuktiReleasesJson) -> Self {
+        let schema_version = json.schema_version.max(json.required_schema_version());
+        let mut projects = json.projects;
+        for (name, project) in &mut projects {
+            project.relativize_urls(name);
+        }
+        if json.digest_encoding == DigestEncoding::Base64 {
+            for_each_digest(&mut projects, |digest| {
+                // Digests that aren't valid hex are left as-is.
+                if let Ok(bytes) = hex::decode(&digest.0) {
+                    digest.0 = BASE64_STANDARD.encode(bytes);
+                }
+                Ok::<_, std::convert::Infallible>(())
+            })
+            .unwrap_or_else(|never| match never {});
+        }
+        Self {
+            schema_version,
+            digest_encoding: json.digest_encoding,
+            projects,
+            extra: json.extra,
+        }
+    }
+}
+
+fn for_each_digest<E>(
+    projects: &mut BTreeMap<String, MuktiProject>,
+    mut f: impl FnMut(&mut Digest) -> Result<(), E>,
+) -> Result<(), E> {
+    for project in projects.values_mut() {
+        for (_, location) in project.locations_mut() {
+            for digest in location.checksums.values_mut() {
+                f(digest)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The encoding used for digests in a serialized releases.json.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum DigestEncoding {
+    /// Lowercase hex, e.g. `9f86d0...`.
+    Hex,
+
+    /// Standard base64 with padding, which is around 33% smaller than hex.
+    Base64,
+}
+
+impl Default for DigestEncoding {
+    fn default() -> Self {
+        Self::Hex
+    }
+}
+
+impl DigestEncoding {
+    pub(crate) fn is_hex(&self) -> bool {
+        *self == Self::Hex
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MuktiProject {
+    /// Descriptive information about the project, for generators that need it.
+    #[serde(default, skip_serializing_if = "ProjectInfo::is_empty")]
+    pub info: ProjectInfo,
+
+    /// The URL that relative artifact URLs are relative to.
+    ///
+    /// In memory, artifact URLs are always absolute: artifact URLs under this one are written out
+    /// relative to it, and read back in as absolute URLs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+
+    /// A template for artifact URLs, like
+    /// `https://example.com/{version}/{name}-{version}-{target}.{format}`.
+    ///
+    /// `{name}` is replaced with the project name, and `{version}`, `{target}` and `{format}` with
+    /// the location's. Locations whose URL matches the template are written out without one, and
+    /// read back in with the template expanded, so a location's URL overrides the template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+
     /// The latest version range (key in the releases field) without any pre-releases.
     pub latest: Option<VersionRange>,
 
+    /// If true, `latest` (and the `latest` field of the range it points to) was set manually, and
+    /// should not be advanced automatically when new versions are added.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub latest_pinned: bool,
+
     /// Map of version range (major or minor version) to release data about it
     #[serde(serialize_with = "serialize_reverse")]
     pub ranges: BTreeMap<VersionRange, ReleaseRangeData>,
+
+    /// Map of release channel names (e.g. "stable" or "beta") to the version each channel
+    /// currently points to.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, Version>,
+
+    /// Map of alias names (e.g. "linux") to the target and format they point to, published
+    /// alongside the target and format paths for each version.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, ProjectAlias>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 impl MuktiProject {
@@ -33,6 +255,67 @@ impl MuktiProject {
             .flat_map(|range| range.versions.iter().rev())
     }
 
+    /// Return versions that are available, as defined by [`ReleaseStatus::is_available`], ordered
+    /// by most recent version first.
+    ///
+    /// Includes pre-release versions.
+    pub fn active_versions(&self) -> impl Iterator<Item = (&Version, &ReleaseVersionData)> {
+        self.all_versions()
+            .filter(|(_, version_data)| version_data.status.is_available())
+    }
+
+    /// Return pre-release versions, ordered by most recent version first.
+    ///
+    /// Includes yanked versions.
+    pub fn prereleases(&self) -> impl Iterator<Item = (&Version, &ReleaseVersionData)> {
+        self.all_versions()
+            .filter(|(version, _)| !version.pre.is_empty())
+    }
+
+    /// Return versions with at least one artifact for `target`, ordered by most recent version
+    /// first.
+    ///
+    /// Includes pre-release and yanked versions.
+    pub fn versions_for_target<'a>(
+        &'a self,
+        target: &'a str,
+    ) -> impl Iterator<Item = (&'a Version, &'a ReleaseVersionData)> + 'a {
+        self.all_versions().filter(move |(_, version_data)| {
+            version_data
+                .locations
+                .iter()
+                .any(|location| location.target == target)
+        })
+    }
+
+    /// Return every location in every version, along with the version, ordered by most recent
+    /// version first.
+    pub fn locations(&self) -> impl Iterator<Item = (&Version, &ReleaseLocation)> {
+        self.all_versions().flat_map(|(version, version_data)| {
+            version_data
+                .locations
+                .iter()
+                .map(move |location| (version, location))
+        })
+    }
+
+    /// Return every location in every version mutably, along with the version, ordered by most
+    /// recent version first.
+    pub fn locations_mut(&mut self) -> impl Iterator<Item = (&Version, &mut ReleaseLocation)> {
+        self.ranges.values_mut().rev().flat_map(|range| {
+            range
+                .versions
+                .iter_mut()
+                .rev()
+                .flat_map(|(version, version_data)| {
+                    version_data
+                        .locations
+                        .iter_mut()
+                        .map(move |location| (version, location))
+                })
+        })
+    }
+
     /// Retrieve data for this exact version if found.
     ///
     /// Can include yanked or pre-release versions.
@@ -42,17 +325,313 @@ impl MuktiProject {
             .find(|&(v2, _)| eq_ignoring_build_metadata(version, v2))
     }
 
+    /// Retrieve data for the latest version: the latest version in the [`Self::latest`] range.
+    pub fn latest_version_data(&self) -> Option<(&Version, &ReleaseVersionData)> {
+        let range_data = self.ranges.get(&self.latest?)?;
+        self.get_version_data(&range_data.latest)
+    }
+
+    /// Returns the artifact users download for `target` and `format` in the latest version.
+    ///
+    /// See [`ReleaseVersionData::location`].
+    pub fn latest_location(&self, target: &str, format: &str) -> Option<&ReleaseLocation> {
+        let (_, version_data) = self.latest_version_data()?;
+        version_data.location(target, format)
+    }
+
+    /// Returns the artifact users download for `target` and `format` in this exact version.
+    ///
+    /// See [`ReleaseVersionData::location`].
+    pub fn location_for(
+        &self,
+        version: &Version,
+        target: &str,
+        format: &str,
+    ) -> Option<&ReleaseLocation> {
+        let (_, version_data) = self.get_version_data(version)?;
+        version_data.location(target, format)
+    }
+
+    /// Returns the artifact an alias in [`Self::aliases`] points to in the latest version.
+    ///
+    /// See [`Self::location_for_alias`].
+    pub fn latest_location_for_alias(&self, alias: &str) -> Option<&ReleaseLocation> {
+        let (version, version_data) = self.latest_version_data()?;
+        find_alias_location(self.aliases.get(alias)?, version, version_data)
+    }
+
+    /// Returns the artifact an alias in [`Self::aliases`] points to in this exact version.
+    ///
+    /// Returns `None` if the alias doesn't apply to the version. If the alias's target or format
+    /// is `*`, the first matching location is returned.
+    pub fn location_for_alias(&self, version: &Version, alias: &str) -> Option<&ReleaseLocation> {
+        let (version, version_data) = self.get_version_data(version)?;
+        find_alias_location(self.aliases.get(alias)?, version, version_data)
+    }
+
+    /// Retrieve data for the version a release channel points to, if the channel exists.
+    pub fn get_channel_data(&self, channel: &str) -> Option<(&Version, &ReleaseVersionData)> {
+        let version = self.channels.get(channel)?;
+        self.get_version_data(version)
+    }
+
+    /// Iterate over the channels that redirects are generated for, along with the version each one
+    /// points to.
+    ///
+    /// Channels named `latest`, or named like a version or version range, are skipped since their
+    /// paths would clash with the ones generated for those. So are channels pointing to versions
+    /// that don't exist.
+    pub fn redirect_channels(
+        &self,
+    ) -> impl Iterator<Item = (&str, &Version, &ReleaseVersionData)> + '_ {
+        self.channels.keys().filter_map(move |channel| {
+            if channel == "latest"
+                || channel.parse::<VersionRange>().is_ok()
+                || channel.parse::<Version>().is_ok()
+            {
+                return None;
+            }
+            let (version, version_data) = self.get_channel_data(channel)?;
+            Some((channel.as_str(), version, version_data))
+        })
+    }
+
+    /// Resolve a possibly relative artifact URL against [`Self::base_url`].
+    ///
+    /// Absolute URLs, and all URLs if there's no base URL, are returned unchanged.
+    pub fn resolve_url<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        match &self.base_url {
+            Some(base_url) => resolve_url(base_url, url),
+            None => Cow::Borrowed(url),
+        }
+    }
+
+    /// Returns `url` relative to [`Self::base_url`] if it's under it, or else unchanged.
+    pub fn relative_url<'a>(&self, url: &'a str) -> &'a str {
+        match &self.base_url {
+            Some(base_url) => relative_url(base_url, url),
+            None => url,
+        }
+    }
+
+    /// Returns the URL [`Self::url_template`] expands to for a location, resolved against
+    /// [`Self::base_url`], if there's a template.
+    ///
+    /// `name` is the project's name.
+    pub fn template_url(
+        &self,
+        name: &str,
+        version: &Version,
+        location: &ReleaseLocation,
+    ) -> Option<String> {
+        let url = expand_url_template(
+            self.url_template.as_deref()?,
+            name,
+            version,
+            &location.target,
+            &location.format,
+        );
+        Some(self.resolve_url(&url).into_owned())
+    }
+
+    fn resolve_urls(&mut self, name: &str) {
+        if let Some(url_template) = self.url_template.clone() {
+            for (version, location) in self.locations_mut() {
+                if location.url.is_empty() {
+                    location.url = expand_url_template(
+                        &url_template,
+                        name,
+                        version,
+                        &location.target,
+                        &location.format,
+                    );
+                }
+            }
+        }
+        if let Some(base_url) = self.base_url.clone() {
+            self.for_each_url(|url| {
+                if let Cow::Owned(resolved) = resolve_url(&base_url, url) {
+                    *url = resolved;
+                }
+            });
+        }
+    }
+
+    fn relativize_urls(&mut self, name: &str) {
+        if let Some(url_template) = self.url_template.clone() {
+            let base_url = self.base_url.clone();
+            for (version, location) in self.locations_mut() {
+                let url = expand_url_template(
+                    &url_template,
+                    name,
+                    version,
+                    &location.target,
+                    &location.format,
+                );
+                let url = match &base_url {
+                    Some(base_url) => resolve_url(base_url, &url).into_owned(),
+                    None => url,
+                };
+                if location.url == url {
+                    location.url.clear();
+                }
+            }
+        }
+        if let Some(base_url) = self.base_url.clone() {
+            self.for_each_url(|url| *url = relative_url(&base_url, url).to_owned());
+        }
+    }
+
+    fn for_each_url(&mut self, mut f: impl FnMut(&mut String)) {
+        for (_, location) in self.locations_mut() {
+            f(&mut location.url);
+            location.mirror_urls.iter_mut().for_each(&mut f);
+            for signature in &mut location.signatures {
+                signature.url.iter_mut().for_each(&mut f);
+            }
+            for attestation in &mut location.attestations {
+                f(&mut attestation.url);
+            }
+        }
+    }
+
+    /// Returns the license that applies to a version: its own, or else the project's.
+    pub fn license_for<'a>(&'a self, version_data: &'a ReleaseVersionData) -> Option<&'a str> {
+        version_data
+            .license
+            .as_deref()
+            .or(self.info.license.as_deref())
+    }
+
     /// Retrieve the latest version that matches this `VersionReq`.
     ///
-    /// This will match the latest non-pre-release, non-yanked version.
+    /// This will match the latest non-pre-release version that's available, as defined by
+    /// [`ReleaseStatus::is_available`].
     pub fn get_latest_matching(&self, req: &VersionReq) -> Option<(&Version, &ReleaseVersionData)> {
         self.all_versions().find(|&(version, version_data)| {
-            version_data.status == ReleaseStatus::Active && req.matches(version)
+            version_data.status.is_available() && req.matches(version)
+        })
+    }
+
+    /// Resolve a version requirement to the version an installer should pick.
+    ///
+    /// This is the latest version matching `req` that's available, as with
+    /// [`Self::get_latest_matching`]. Pre-releases only match if `req` mentions a pre-release of
+    /// the same major, minor and patch version, following the usual semver rules.
+    ///
+    /// Yanked versions are skipped, unless `req` pins that exact version (e.g. `=0.5.2`): someone
+    /// asking for a yanked version by name gets it, but it's never picked for them otherwise.
+    pub fn resolve_req(&self, req: &VersionReq) -> Option<(&Version, &ReleaseVersionData)> {
+        self.get_latest_matching(req).or_else(|| {
+            let version = exact_version(req)?;
+            self.get_version_data(&version)
         })
     }
 }
 
+fn find_alias_location<'a>(
+    alias: &ProjectAlias,
+    version: &Version,
+    version_data: &'a ReleaseVersionData,
+) -> Option<&'a ReleaseLocation> {
+    if !alias
+        .version_req
+        .as_ref()
+        .map_or(true, |req| req.matches(version))
+    {
+        return None;
+    }
+    version_data.redirect_locations().find(|location| {
+        (alias.target == "*" || alias.target == location.target)
+            && (alias.format == "*" || alias.format == location.format.as_str())
+    })
+}
+
+/// Returns the version `req` pins, if it's a single `=` comparator with all components.
+fn exact_version(req: &VersionReq) -> Option<Version> {
+    match req.comparators.as_slice() {
+        [Comparator {
+            op: Op::Exact,
+            major,
+            minor: Some(minor),
+            patch: Some(patch),
+            pre,
+        }] => Some(Version {
+            major: *major,
+            minor: *minor,
+            patch: *patch,
+            pre: pre.clone(),
+            build: BuildMetadata::EMPTY,
+        }),
+        _ => None,
+    }
+}
+
+/// Descriptive information about a project, stored in [`MuktiProject::info`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProjectInfo {
+    /// A human-readable name for the project, if it differs from the key it's stored under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// A one-line description of the project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The URL of the project's homepage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+
+    /// The URL of the project's source repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+
+    /// The project's license, as an SPDX license expression, e.g. "MIT OR Apache-2.0".
+    ///
+    /// Individual versions can override this with [`ReleaseVersionData::license`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ProjectInfo {
+    /// Returns true if no information is set.
+    pub fn is_empty(&self) -> bool {
+        self.display_name.is_none()
+            && self.description.is_none()
+            && self.homepage.is_none()
+            && self.repository.is_none()
+            && self.license.is_none()
+            && self.extra.is_empty()
+    }
+}
+
+/// The target and format an alias stored in [`MuktiProject::aliases`] points to.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProjectAlias {
+    /// The target the alias points to, or `*` for any target.
+    pub target: String,
+
+    /// The format the alias points to, or `*` for any format.
+    pub format: String,
+
+    /// If set, the alias is only published for versions matching this requirement.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub version_req: Option<VersionReq>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ReleaseRangeData {
     /// The latest version within this range (can be a prerelease)
     pub latest: Version,
@@ -60,81 +639,1020 @@ pub struct ReleaseRangeData {
     /// True if this version range only has prereleases.
     pub is_prerelease: bool,
 
+    /// When this version range stops being supported, as a date in `YYYY-MM-DD` format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eol_date: Option<String>,
+
     /// All known versions
     #[serde(serialize_with = "serialize_reverse")]
     pub versions: BTreeMap<Version, ReleaseVersionData>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(from = "ReleaseVersionDataRepr", into = "ReleaseVersionDataRepr")]
+pub struct ReleaseVersionData {
+    /// Canonical URL for this release
+    pub release_url: String,
+
+    /// The status of a release
+    pub status: ReleaseStatus,
+
+    /// Release locations
+    pub locations: Vec<ReleaseLocation>,
+
+    /// When this version was released, in RFC 3339 format.
+    pub release_date: Option<String>,
+
+    /// Release notes for this version, typically in Markdown.
+    pub notes: Option<String>,
+
+    /// A link to the changelog entry for this version.
+    pub changelog_url: Option<String>,
+
+    /// The git commit this version was built from, as a full SHA.
+    pub commit: Option<String>,
+
+    /// The license for this version as an SPDX license expression, if it differs from the
+    /// project's.
+    pub license: Option<String>,
+
+    /// Custom domain-specific information stored about this release.
+    pub metadata: serde_json::Value,
+
+    /// See [`MuktiReleasesJson::extra`].
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReleaseVersionData {
+    /// Returns the locations users download to install this version, skipping checksum files,
+    /// signatures and other auxiliary artifacts.
+    pub fn installable_locations(&self) -> impl Iterator<Item = &ReleaseLocation> + '_ {
+        self.locations
+            .iter()
+            .filter(|location| location.kind.is_installable())
+    }
+
+    /// Returns the locations that get redirects: installable locations, along with
+    /// [`SOURCE_TARGET`] artifacts of any kind.
+    pub fn redirect_locations(&self) -> impl Iterator<Item = &ReleaseLocation> + '_ {
+        self.locations
+            .iter()
+            .filter(|location| location.kind.is_installable() || location.is_target_independent())
+    }
+
+    /// Returns the artifact users download for `target` and `format`.
+    ///
+    /// Only locations that get redirects are considered, as returned by
+    /// [`Self::redirect_locations`]. Formats are compared ignoring a leading dot.
+    pub fn location(&self, target: &str, format: &str) -> Option<&ReleaseLocation> {
+        let format = Format::from(format).normalized();
+        self.redirect_locations()
+            .find(|location| location.target == target && location.format.normalized() == format)
+    }
+
+    /// Returns the debug symbols for this version, one per target.
+    ///
+    /// If a target has debug symbols in more than one format, the first one listed is returned.
+    pub fn debug_symbols(&self) -> impl Iterator<Item = &ReleaseLocation> + '_ {
+        let mut seen = BTreeSet::new();
+        self.locations.iter().filter(move |location| {
+            location.kind == ArtifactKind::DebugSymbols && seen.insert(&location.target)
+        })
+    }
+
+    /// Returns the value stored under `key` in [`Self::metadata`], if there is one.
+    ///
+    /// Keys let several tools store their own data for a version without clashing, e.g.
+    /// `metadata["nextest"]` for nextest.
+    pub fn typed_metadata<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, TypedMetadataError> {
+        let value = match &self.metadata {
+            serde_json::Value::Null => return Ok(None),
+            serde_json::Value::Object(map) => match map.get(key) {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+            other => {
+                return Err(TypedMetadataError::NotAnObject {
+                    kind: json_kind(other),
+                })
+            }
+        };
+        T::deserialize(value)
+            .map(Some)
+            .map_err(|error| TypedMetadataError::Convert {
+                key: key.to_owned(),
+                error,
+            })
+    }
+
+    /// Stores `value` under `key` in [`Self::metadata`], replacing anything already there.
+    ///
+    /// Other keys are left unchanged. If there's no metadata yet, it's created as an object.
+    pub fn set_typed_metadata<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), TypedMetadataError> {
+        let value = serde_json::to_value(value).map_err(|error| TypedMetadataError::Convert {
+            key: key.to_owned(),
+            error,
+        })?;
+        if self.metadata.is_null() {
+            self.metadata = serde_json::Value::Object(serde_json::Map::new());
+        }
+        match &mut self.metadata {
+            serde_json::Value::Object(map) => {
+                map.insert(key.to_owned(), value);
+                Ok(())
+            }
+            other => Err(TypedMetadataError::NotAnObject {
+                kind: json_kind(other),
+            }),
+        }
+    }
+}
+
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// The serialized form of [`ReleaseVersionData`].
+///
+/// The yank reason and date are stored next to `status`, which stays a plain string so that
+/// readers without support for them still see the release as yanked.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schemars",
+    schemars(description = "Release data for a single version.")
+)]
+struct ReleaseVersionDataRepr {
+    release_url: String,
+    status: ReleaseStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    yank_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    yank_date: Option<String>,
+    locations: Vec<ReleaseLocation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    release_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    changelog_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(default)]
+    metadata: serde_json::Value,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ReleaseVersionData {
+    fn schema_name() -> String {
+        "ReleaseVersionData".to_owned()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        ReleaseVersionDataRepr::json_schema(gen)
+    }
+}
+
+impl From<ReleaseVersionDataRepr> for ReleaseVersionData {
+    fn from(repr: ReleaseVersionDataRepr) -> Self {
+        let status = match repr.status {
+            ReleaseStatus::Yanked { .. } => ReleaseStatus::Yanked {
+                reason: repr.yank_reason,
+                date: repr.yank_date,
+            },
+            status => status,
+        };
+        Self {
+            release_url: repr.release_url,
+            status,
+            locations: repr.locations,
+            release_date: repr.release_date,
+            notes: repr.notes,
+            changelog_url: repr.changelog_url,
+            commit: repr.commit,
+            license: repr.license,
+            metadata: repr.metadata,
+            extra: repr.extra,
+        }
+    }
+}
+
+impl From<ReleaseVersionData> for ReleaseVersionDataRepr {
+    fn from(data: ReleaseVersionData) -> Self {
+        let (yank_reason, yank_date) = match &data.status {
+            ReleaseStatus::Yanked { reason, date } => (reason.clone(), date.clone()),
+            _ => (None, None),
+        };
+        Self {
+            release_url: data.release_url,
+            status: data.status,
+            yank_reason,
+            yank_date,
+            locations: data.locations,
+            release_date: data.release_date,
+            notes: data.notes,
+            changelog_url: data.changelog_url,
+            commit: data.commit,
+            license: data.license,
+            metadata: data.metadata,
+            extra: data.extra,
+        }
+    }
+}
+
+/// The status of a release.
+///
+/// New statuses may be added in the future. Statuses this version of the crate doesn't know about
+/// are read as [`ReleaseStatus::Unknown`], and written back out unchanged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ReleaseStatus {
+    /// This release is active.
+    Active,
+
+    /// This release was yanked.
+    Yanked {
+        /// Why the release was yanked.
+        reason: Option<String>,
+
+        /// When the release was yanked, in RFC 3339 format.
+        date: Option<String>,
+    },
+
+    /// This release is still available, but users should move to a newer one.
+    Deprecated,
+
+    /// This release is still available, but no longer supported.
+    EndOfLife,
+
+    /// A status not known to this version of the crate.
+    Unknown(String),
+}
+
+impl ReleaseStatus {
+    /// Returns the status as it's serialized, e.g. `end-of-life`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Active => "active",
+            Self::Yanked { .. } => "yanked",
+            Self::Deprecated => "deprecated",
+            Self::EndOfLife => "end-of-life",
+            Self::Unknown(status) => status,
+        }
+    }
+
+    /// Returns true if the release should be offered for download.
+    ///
+    /// This is true for active, deprecated and end-of-life releases. Unknown statuses are treated
+    /// as unavailable, since they may have been introduced to withdraw releases.
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Active | Self::Deprecated | Self::EndOfLife)
+    }
+
+    /// Returns true if the release was yanked.
+    pub fn is_yanked(&self) -> bool {
+        matches!(self, Self::Yanked { .. })
+    }
+}
+
+impl fmt::Display for ReleaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ReleaseStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(ReleaseStatusDeVisitor)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ReleaseStatus {
+    fn schema_name() -> String {
+        "ReleaseStatus".to_owned()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        open_string_schema(
+            "The status of a release.",
+            &["active", "yanked", "deprecated", "end-of-life"],
+        )
+    }
+}
+
+struct ReleaseStatusDeVisitor;
+
+impl<'de> Visitor<'de> for ReleaseStatusDeVisitor {
+    type Value = ReleaseStatus;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a release status string")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(match s {
+            "active" => ReleaseStatus::Active,
+            "yanked" => ReleaseStatus::Yanked {
+                reason: None,
+                date: None,
+            },
+            "deprecated" => ReleaseStatus::Deprecated,
+            "end-of-life" => ReleaseStatus::EndOfLife,
+            other => ReleaseStatus::Unknown(other.to_owned()),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReleaseLocation {
+    /// The target string
+    pub target: String,
+
+    /// The archive format (e.g. "tar.gz" or "zip")
+    pub format: Format,
+
+    /// What kind of artifact this is. Only archives and installers are offered for download.
+    #[serde(default, skip_serializing_if = "ArtifactKind::is_archive")]
+    pub kind: ArtifactKind,
+
+    /// The URL the target can be downloaded at
+    ///
+    /// Omitted on disk if it matches the project's [`MuktiProject::url_template`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+
+    /// Alternate URLs the target can be downloaded from, in order of preference, if `url` is
+    /// unavailable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirror_urls: Vec<String>,
+
+    /// The checksums for the target as a map of algorithm to checksum. This is
+    /// left open-ended to allow for new checksum algorithms to be added in the
+    /// future.
+    #[serde(default)]
+    pub checksums: BTreeMap<DigestAlgorithm, Digest>,
+
+    /// The size of the file in bytes, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Signatures published for the file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<SignatureEntry>,
+
+    /// Attestations about the file, such as SLSA build provenance.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attestations: Vec<AttestationEntry>,
+
+    /// The oldest glibc the artifact runs against, e.g. "2.17", for Linux GNU targets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_glibc: Option<String>,
+
+    /// The oldest macOS version the artifact runs on, e.g. "10.12".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_macos: Option<String>,
+
+    /// The oldest Windows version the artifact runs on, e.g. "10" or "10.0.17763".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_windows: Option<String>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ReleaseLocation {
+    /// Returns true if this artifact isn't specific to a platform, i.e. its target is
+    /// [`SOURCE_TARGET`].
+    pub fn is_target_independent(&self) -> bool {
+        self.target == SOURCE_TARGET
+    }
+}
+
+/// A signature for a release location, either published at a URL or stored inline.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SignatureEntry {
+    /// The signature scheme, e.g. "minisign", "gpg" or "cosign".
+    pub scheme: String,
+
+    /// An identifier for the key that made the signature, in the scheme's usual format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    /// The URL the signature can be downloaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// The signature itself, for schemes with short signatures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The kind of artifact a release location points to.
+///
+/// New kinds may be added in the future. Kinds this version of the crate doesn't know about are
+/// read as [`ArtifactKind::Other`], and written back out unchanged.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum ArtifactKind {
+    /// An archive containing the project's binaries. This is the default.
+    Archive,
+
+    /// An installer or package, e.g. a `.deb`, `.rpm` or `.msi`.
+    Installer,
+
+    /// A file listing checksums for other artifacts.
+    ChecksumFile,
+
+    /// A detached signature for another artifact.
+    Signature,
+
+    /// A software bill of materials.
+    Sbom,
+
+    /// Debug symbols for the binaries in another artifact.
+    DebugSymbols,
+
+    /// A kind not known to this version of the crate.
+    Other(String),
+}
+
+impl ArtifactKind {
+    /// Returns the kind as it's serialized, e.g. `debug-symbols`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Archive => "archive",
+            Self::Installer => "installer",
+            Self::ChecksumFile => "checksum-file",
+            Self::Signature => "signature",
+            Self::Sbom => "sbom",
+            Self::DebugSymbols => "debug-symbols",
+            Self::Other(kind) => kind,
+        }
+    }
+
+    /// Returns true if this is an archive.
+    pub fn is_archive(&self) -> bool {
+        matches!(self, Self::Archive)
+    }
+
+    /// Returns true if the artifact is something users download to install the project, i.e. an
+    /// archive or an installer.
+    pub fn is_installable(&self) -> bool {
+        matches!(self, Self::Archive | Self::Installer)
+    }
+}
+
+impl Default for ArtifactKind {
+    fn default() -> Self {
+        Self::Archive
+    }
+}
+
+impl From<&str> for ArtifactKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "archive" => Self::Archive,
+            "installer" => Self::Installer,
+            "checksum-file" => Self::ChecksumFile,
+            "signature" => Self::Signature,
+            "sbom" => Self::Sbom,
+            "debug-symbols" => Self::DebugSymbols,
+            other => Self::Other(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ArtifactKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ArtifactKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(ArtifactKindDeVisitor)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ArtifactKind {
+    fn schema_name() -> String {
+        "ArtifactKind".to_owned()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        open_string_schema(
+            "The kind of artifact a location points to.",
+            &[
+                "archive",
+                "installer",
+                "checksum-file",
+                "signature",
+                "sbom",
+                "debug-symbols",
+            ],
+        )
+    }
+}
+
+struct ArtifactKindDeVisitor;
+
+impl<'de> Visitor<'de> for ArtifactKindDeVisitor {
+    type Value = ArtifactKind;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an artifact kind string")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(ArtifactKind::from(s))
+    }
+}
+
+/// The file format of a release location.
+///
+/// Formats are serialized as the file extension without a leading dot, e.g. `tar.gz`. Formats this
+/// version of the crate doesn't know about, including known formats written with a leading dot,
+/// are read as [`Format::Other`], and written back out unchanged.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Format {
+    /// A gzip-compressed tarball.
+    TarGz,
+
+    /// An xz-compressed tarball.
+    TarXz,
+
+    /// A zstd-compressed tarball.
+    TarZst,
+
+    /// A zip archive.
+    Zip,
+
+    /// A Windows Installer package.
+    Msi,
+
+    /// A macOS disk image.
+    Dmg,
+
+    /// A Debian package.
+    Deb,
+
+    /// An RPM package.
+    Rpm,
+
+    /// A format not known to this version of the crate.
+    Other(String),
+}
+
+impl Format {
+    /// Returns the format as it's serialized, e.g. `tar.gz`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::TarXz => "tar.xz",
+            Self::TarZst => "tar.zst",
+            Self::Zip => "zip",
+            Self::Msi => "msi",
+            Self::Dmg => "dmg",
+            Self::Deb => "deb",
+            Self::Rpm => "rpm",
+            Self::Other(format) => format,
+        }
+    }
+
+    /// Returns this format with any leading dot removed, so that e.g. `.deb` is read as
+    /// [`Format::Deb`].
+    pub fn normalized(&self) -> Self {
+        match self {
+            Self::Other(format) => Self::from(format.trim_start_matches('.')),
+            known => known.clone(),
+        }
+    }
+
+    /// Returns true if this is a compressed tarball.
+    pub fn is_tarball(&self) -> bool {
+        matches!(self, Self::TarGz | Self::TarXz | Self::TarZst)
+    }
+}
+
+impl From<&str> for Format {
+    fn from(s: &str) -> Self {
+        match s {
+            "tar.gz" => Self::TarGz,
+            "tar.xz" => Self::TarXz,
+            "tar.zst" => Self::TarZst,
+            "zip" => Self::Zip,
+            "msi" => Self::Msi,
+            "dmg" => Self::Dmg,
+            "deb" => Self::Deb,
+            "rpm" => Self::Rpm,
+            other => Self::Other(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Format {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(FormatDeVisitor)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Format {
+    fn schema_name() -> String {
+        "Format".to_owned()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        open_string_schema(
+            "The format of an artifact.",
+            &[
+                "tar.gz", "tar.xz", "tar.zst", "zip", "msi", "dmg", "deb", "rpm",
+            ],
+        )
+    }
+}
+
+struct FormatDeVisitor;
+
+impl<'de> Visitor<'de> for FormatDeVisitor {
+    type Value = Format;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a format string")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Format::from(s))
+    }
+}
+
+/// An in-toto attestation published for a release location.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AttestationEntry {
+    /// The attestation's predicate type, e.g. "https://slsa.dev/provenance/v1".
+    pub predicate_type: String,
+
+    /// The URL the attestation can be downloaded from. This may be a DSSE envelope, a Sigstore
+    /// bundle, or a JSON Lines file containing several of them.
+    pub url: String,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// A checksum algorithm.
+///
+/// New algorithms may be added in the future. Algorithms this version of the crate doesn't know
+/// about are read as [`DigestAlgorithm::Other`], and written back out unchanged. Algorithms are
+/// compared and ordered by name, so checksum maps are serialized in the same order as before.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// The SHA-256 checksum algorithm.
+    Sha256,
+
+    /// The BLAKE2b-512 checksum algorithm.
+    Blake2b,
+
+    /// An algorithm not known to this version of the crate, e.g. `sha3-256`.
+    Other(String),
+}
+
+impl DigestAlgorithm {
+    /// Returns the name of this algorithm as a string.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake2b => "blake2b",
+            Self::Other(algorithm) => algorithm,
+        }
+    }
+
+    /// Returns the number of hex digits in digests for this algorithm, if known.
+    pub fn hex_len(&self) -> Option<usize> {
+        match self {
+            Self::Sha256 => Some(64),
+            Self::Blake2b => Some(128),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl From<&str> for DigestAlgorithm {
+    fn from(s: &str) -> Self {
+        match s {
+            "sha256" => Self::Sha256,
+            "blake2b" => Self::Blake2b,
+            other => Self::Other(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for DigestAlgorithm {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for DigestAlgorithm {}
+
+impl PartialOrd for DigestAlgorithm {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DigestAlgorithm {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for DigestAlgorithm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl Serialize for DigestAlgorithm {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DigestAlgorithm {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(DigestAlgorithmDeVisitor)
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ReleaseVersionData {
-    /// Canonical URL for this release
-    pub release_url: String,
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for DigestAlgorithm {
+    fn schema_name() -> String {
+        "DigestAlgorithm".to_owned()
+    }
 
-    /// The status of a release
-    pub status: ReleaseStatus,
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        open_string_schema("A digest algorithm.", &["sha256", "blake2b"])
+    }
+}
 
-    /// Release locations
-    pub locations: Vec<ReleaseLocation>,
+struct DigestAlgorithmDeVisitor;
 
-    /// Custom domain-specific information stored about this release.
-    #[serde(default)]
-    pub metadata: serde_json::Value,
-}
+impl<'de> Visitor<'de> for DigestAlgorithmDeVisitor {
+    type Value = DigestAlgorithm;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
-#[serde(rename_all = "kebab-case")]
-pub enum ReleaseStatus {
-    /// This release is active.
-    Active,
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a checksum algorithm string")
+    }
 
-    /// This release was yanked.
-    Yanked,
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(DigestAlgorithm::from(s))
+    }
 }
 
+/// A digest, typically encoded as a hex string.
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ReleaseLocation {
-    /// The target string
-    pub target: String,
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Digest(pub String);
 
-    /// The archive format (e.g. ".tar.gz" or ".zip")
-    pub format: String,
+impl Digest {
+    /// Parse a hex-encoded digest for `algorithm`, checking that it only contains hex digits and,
+    /// for algorithms this crate knows about, that it has the right length.
+    ///
+    /// The digest is stored lowercased.
+    pub fn parse(algorithm: &DigestAlgorithm, input: &str) -> Result<Self, DigestParseError> {
+        if input.is_empty() || !input.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DigestParseError::InvalidHex {
+                algorithm: algorithm.clone(),
+                input: input.to_owned(),
+            });
+        }
+        if let Some(expected) = algorithm.hex_len() {
+            if input.len() != expected {
+                return Err(DigestParseError::InvalidLength {
+                    algorithm: algorithm.clone(),
+                    input: input.to_owned(),
+                    actual: input.len(),
+                    expected,
+                });
+            }
+        }
+        Ok(Self(input.to_ascii_lowercase()))
+    }
+}
 
-    /// The URL the target can be downloaded at
-    pub url: String,
+/// A digest along with its algorithm, written as `ALGORITHM:HEX`, e.g. `sha256:9f86d0...`.
+#[derive(Clone, Debug)]
+pub struct PrefixedDigest {
+    /// The algorithm the digest was computed with.
+    pub algorithm: DigestAlgorithm,
 
-    /// The checksums for the target as a map of algorithm to checksum. This is
-    /// left open-ended to allow for new checksum algorithms to be added in the
-    /// future.
-    #[serde(default)]
-    pub checksums: BTreeMap<DigestAlgorithm, Digest>,
+    /// The hex-encoded digest.
+    pub digest: Digest,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
-#[serde(transparent)]
-pub struct DigestAlgorithm(Cow<'static, str>);
+impl fmt::Display for PrefixedDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.digest.0)
+    }
+}
 
-impl DigestAlgorithm {
-    /// The SHA-256 checksum algorithm.
-    pub const SHA256: Self = Self(Cow::Borrowed("sha256"));
+impl FromStr for PrefixedDigest {
+    type Err = DigestParseError;
 
-    /// The BLAKE2b-512 checksum algorithm.
-    pub const BLAKE2B: Self = Self(Cow::Borrowed("blake2b"));
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (algorithm, hex) = match input.split_once(':') {
+            Some((algorithm, hex)) if !algorithm.is_empty() => (algorithm, hex),
+            _ => {
+                return Err(DigestParseError::MissingAlgorithm {
+                    input: input.to_owned(),
+                })
+            }
+        };
+        let algorithm = DigestAlgorithm::from(algorithm);
+        let digest = Digest::parse(&algorithm, hex)?;
+        Ok(Self { algorithm, digest })
+    }
+}
+
+/// Expand the placeholders in a [`MuktiProject::url_template`].
+///
+/// `{name}` is replaced with `name`, and `{version}`, `{target}` and `{format}` with the others.
+/// Other text in braces is left as-is.
+pub fn expand_url_template(
+    template: &str,
+    name: &str,
+    version: &Version,
+    target: &str,
+    format: &Format,
+) -> String {
+    template
+        .replace("{name}", name)
+        .replace("{version}", &version.to_string())
+        .replace("{target}", target)
+        .replace("{format}", format.as_str())
+}
 
-    pub const fn new_static(algorithm: &'static str) -> Self {
-        Self(Cow::Borrowed(algorithm))
+fn resolve_url<'a>(base_url: &str, url: &'a str) -> Cow<'a, str> {
+    // Anything with a scheme, like https: or file:, is already absolute.
+    let is_absolute = url.split_once(':').map_or(false, |(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if is_absolute {
+        Cow::Borrowed(url)
+    } else {
+        Cow::Owned(format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        ))
     }
+}
+
+fn relative_url<'a>(base_url: &str, url: &'a str) -> &'a str {
+    url.strip_prefix(base_url.trim_end_matches('/'))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .filter(|relative| !relative.is_empty())
+        .unwrap_or(url)
+}
+
+/// A schema for strings that may hold values this crate doesn't know about, listing the ones it
+/// does as examples.
+#[cfg(feature = "schemars")]
+fn open_string_schema(description: &str, known: &[&str]) -> schemars::schema::Schema {
+    use schemars::schema::{InstanceType, Metadata, SchemaObject};
 
-    pub fn new(algorithm: String) -> Self {
-        Self(Cow::Owned(algorithm))
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_owned()),
+            examples: known.iter().map(|value| (*value).into()).collect(),
+            ..Default::default()
+        })),
+        ..Default::default()
     }
+    .into()
 }
 
-/// A digest, typically encoded as a hex string.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(transparent)]
-pub struct Digest(pub String);
+#[inline]
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[inline]
+pub(crate) fn is_false(value: &bool) -> bool {
+    !*value
+}
 
-fn serialize_reverse<S, K, V>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_reverse<S, K, V>(
+    map: &BTreeMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
     K: Serialize,
@@ -187,20 +1705,43 @@ impl VersionRangeKind {
     }
 }
 
+/// Displays the range in its canonical form, e.g. `1` or `0.9`.
+///
+/// With the alternate flag (`{:#}`), major and minor ranges are displayed as `1.x` or `0.9.x`
+/// instead. Patch ranges only contain one version, so they're displayed the same either way.
 impl fmt::Display for VersionRange {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let suffix = if f.alternate() { ".x" } else { "" };
         match self {
-            Self::Major(major) => write!(f, "{}", major),
-            Self::Minor(minor) => write!(f, "0.{}", minor),
+            Self::Major(major) => write!(f, "{}{}", major, suffix),
+            Self::Minor(minor) => write!(f, "0.{}{}", minor, suffix),
             Self::Patch(patch) => write!(f, "0.0.{}", patch),
         }
     }
 }
 
+/// Parses a range in its canonical form, or with a `.x` suffix, e.g. `1`, `1.x`, `0.9` or
+/// `0.9.x`.
+///
+/// Patch ranges like `0.0.3` have no `.x` form, and `0.x` isn't a range since 0.y versions aren't
+/// compatible with each other.
 impl FromStr for VersionRange {
     type Err = VersionRangeParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.strip_suffix(".x") {
+            // Anything else fails to parse with the `x` in place of a number.
+            Some(range) if range != "0" && !range.starts_with("0.0") => {
+                Self::parse_canonical(range)
+            }
+            _ => Self::parse_canonical(input),
+        }
+    }
+}
+
+impl VersionRange {
+    /// Parses a range in its canonical form only, as written in the release JSON.
+    fn parse_canonical(input: &str) -> Result<Self, VersionRangeParseError> {
         if let Some(patch_str) = input.strip_prefix("0.0.") {
             parse_component(patch_str, VersionRangeKind::Patch).map(Self::Patch)
         } else if let Some(minor_str) = input.strip_prefix("0.") {
@@ -234,6 +1775,31 @@ impl<'de> Deserialize<'de> for VersionRange {
     }
 }
 
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for VersionRange {
+    fn schema_name() -> String {
+        "VersionRange".to_owned()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject, StringValidation};
+
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some("A version range, e.g. `1` or `0.9`.".to_owned()),
+                ..Default::default()
+            })),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(r"^[0-9]+(\.[0-9]+){0,2}$".to_owned()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 struct VersionRangeDeVisitor;
 
 impl<'de> Visitor<'de> for VersionRangeDeVisitor {
@@ -250,7 +1816,8 @@ impl<'de> Visitor<'de> for VersionRangeDeVisitor {
     where
         E: serde::de::Error,
     {
-        s.parse().map_err(|err| E::custom(err))
+        // Only the canonical form is accepted, so that each range has a single key.
+        VersionRange::parse_canonical(s).map_err(|err| E::custom(err))
     }
 }
 
@@ -345,6 +1912,595 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_req() {
+        let json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = &json.projects["mukti"];
+        let resolve = |req: &str| {
+            let (version, _) = project.resolve_req(&VersionReq::parse(req).unwrap())?;
+            Some(version.clone())
+        };
+
+        assert_eq!(resolve("*"), Some(version("0.5.3")), "latest available");
+        assert_eq!(resolve("0.6"), None, "pre-releases aren't picked");
+        assert_eq!(
+            resolve(">=0.6.0-alpha.1"),
+            Some(version("0.6.0-alpha.1")),
+            "pre-release mentioned in the requirement"
+        );
+        assert_eq!(
+            resolve("^0.5,<0.5.3"),
+            Some(version("0.5.1")),
+            "0.5.2 is yanked so 0.5.1 is picked"
+        );
+        assert_eq!(
+            resolve("=0.5.2"),
+            Some(version("0.5.2")),
+            "yanked version pinned exactly"
+        );
+        assert_eq!(resolve("=0.5"), Some(version("0.5.3")), "=0.5 isn't a pin");
+        assert_eq!(resolve("=0.4.0"), None, "missing version");
+    }
+
+    #[test]
+    fn test_location_helpers() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        project.aliases.insert(
+            "windows".to_owned(),
+            ProjectAlias {
+                target: "x86_64-pc-windows-msvc".to_owned(),
+                format: "zip".to_owned(),
+                version_req: Some(VersionReq::parse(">=0.5.2").unwrap()),
+                extra: BTreeMap::new(),
+            },
+        );
+        let project = &json.projects["mukti"];
+        let url =
+            |location: Option<&ReleaseLocation>| location.map(|location| location.url.clone());
+
+        assert_eq!(
+            url(project.latest_location("x86_64-unknown-linux-gnu", "tar.gz")),
+            url(project.location_for(&version("0.5.3"), "x86_64-unknown-linux-gnu", ".tar.gz")),
+            "latest is 0.5.3, and a leading dot is ignored"
+        );
+        assert!(
+            project
+                .location_for(&version("0.5.3"), "aarch64-apple-darwin", "tar.gz")
+                .is_none(),
+            "missing target"
+        );
+
+        let windows = project.latest_location_for_alias("windows").unwrap();
+        assert_eq!(windows.target, "x86_64-pc-windows-msvc");
+        assert_eq!(windows.format, Format::Zip);
+        assert!(
+            project
+                .location_for_alias(&version("0.5.2"), "windows")
+                .is_some(),
+            "alias applies to 0.5.2"
+        );
+        assert!(
+            project
+                .location_for_alias(&version("0.5.1"), "windows")
+                .is_none(),
+            "alias doesn't apply to 0.5.1"
+        );
+        assert!(project.latest_location_for_alias("linux").is_none());
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_schema() {
+        let schema = schemars::schema_for!(MuktiReleasesJson);
+        let schema = serde_json::to_value(&schema).unwrap();
+        assert_eq!(schema["title"], "MuktiReleasesJson");
+        assert_eq!(schema["required"], serde_json::json!(["projects"]));
+        for name in [
+            "MuktiProject",
+            "ReleaseVersionData",
+            "ReleaseLocation",
+            "VersionRange",
+        ] {
+            assert!(
+                schema["definitions"].get(name).is_some(),
+                "{name} is defined"
+            );
+        }
+        assert_eq!(
+            schema["definitions"]["ReleaseVersionData"]["properties"]["status"]["$ref"],
+            "#/definitions/ReleaseStatus",
+            "the serialized form is used"
+        );
+    }
+
+    #[test]
+    fn test_iteration_helpers() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        // Drop the Linux artifact from 0.5.1.
+        project
+            .ranges
+            .get_mut(&VersionRange::Minor(5))
+            .unwrap()
+            .versions
+            .get_mut(&version("0.5.1"))
+            .unwrap()
+            .locations
+            .retain(|location| location.target != "x86_64-unknown-linux-gnu");
+        let project = &json.projects["mukti"];
+        let versions = |iter: &mut dyn Iterator<Item = (&Version, &ReleaseVersionData)>| {
+            iter.map(|(version, _)| version.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            versions(&mut project.active_versions()),
+            ["0.6.0-alpha.1", "0.5.3", "0.5.1"],
+            "yanked 0.5.2 is skipped"
+        );
+        assert_eq!(versions(&mut project.prereleases()), ["0.6.0-alpha.1"]);
+        assert_eq!(
+            versions(&mut project.versions_for_target("x86_64-unknown-linux-gnu")),
+            ["0.6.0-alpha.1", "0.5.3", "0.5.2"],
+        );
+        assert_eq!(
+            versions(&mut project.versions_for_target("aarch64-apple-darwin")),
+            Vec::<String>::new(),
+        );
+        assert_eq!(project.locations().count(), 11);
+    }
+
+    #[test]
+    fn test_digest_encoding_round_trip() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        json.digest_encoding = DigestEncoding::Base64;
+
+        let serialized = serde_json::to_string(&json).unwrap();
+        assert!(
+            serialized.contains(r#""digest_encoding":"base64""#),
+            "encoding is recorded"
+        );
+        assert!(
+            !serialized.contains(&hex_sha256(&json)),
+            "digests are not serialized as hex"
+        );
+
+        let json2: MuktiReleasesJson = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(json2.digest_encoding, DigestEncoding::Base64);
+        assert_eq!(
+            hex_sha256(&json2),
+            hex_sha256(&json),
+            "digests are hex in memory after round trip"
+        );
+    }
+
+    #[test]
+    fn test_release_status_round_trip() {
+        for (input, expected) in [
+            ("active", ReleaseStatus::Active),
+            ("end-of-life", ReleaseStatus::EndOfLife),
+            ("archived", ReleaseStatus::Unknown("archived".to_owned())),
+        ] {
+            let json = format!("\"{}\"", input);
+            let status: ReleaseStatus = serde_json::from_str(&json).unwrap();
+            assert_eq!(status, expected, "{} is parsed correctly", input);
+            assert_eq!(
+                serde_json::to_string(&status).unwrap(),
+                json,
+                "{} is serialized unchanged",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_artifact_kind() {
+        let json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let location = &json.projects["mukti"]
+            .all_versions()
+            .next()
+            .unwrap()
+            .1
+            .locations[0];
+        assert_eq!(
+            location.kind,
+            ArtifactKind::Archive,
+            "missing kind is an archive"
+        );
+        assert!(
+            !serde_json::to_string(location)
+                .unwrap()
+                .contains("\"kind\""),
+            "archive kind isn't serialized"
+        );
+
+        for (input, expected) in [
+            ("debug-symbols", ArtifactKind::DebugSymbols),
+            ("provenance", ArtifactKind::Other("provenance".to_owned())),
+        ] {
+            let json = format!("\"{}\"", input);
+            let kind: ArtifactKind = serde_json::from_str(&json).unwrap();
+            assert_eq!(kind, expected, "{} is parsed correctly", input);
+            assert!(!kind.is_installable(), "{} isn't installable", input);
+            assert_eq!(serde_json::to_string(&kind).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        for (input, expected, normalized) in [
+            ("tar.gz", Format::TarGz, Format::TarGz),
+            ("zip", Format::Zip, Format::Zip),
+            (".deb", Format::Other(".deb".to_owned()), Format::Deb),
+            (
+                "tar.bz2",
+                Format::Other("tar.bz2".to_owned()),
+                Format::Other("tar.bz2".to_owned()),
+            ),
+        ] {
+            let json = format!("\"{}\"", input);
+            let format: Format = serde_json::from_str(&json).unwrap();
+            assert_eq!(format, expected, "{} is parsed correctly", input);
+            assert_eq!(format.normalized(), normalized, "{} is normalized", input);
+            assert_eq!(
+                serde_json::to_string(&format).unwrap(),
+                json,
+                "{} is written back out unchanged",
+                input
+            );
+        }
+        assert!(Format::TarZst.is_tarball());
+        assert!(!Format::Zip.is_tarball());
+    }
+
+    #[test]
+    fn test_digest_algorithm_round_trip() {
+        let json = r#"{"sha3-256":"aa","sha256":"bb","blake2b":"cc"}"#;
+        let checksums: BTreeMap<DigestAlgorithm, Digest> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            checksums.keys().collect::<Vec<_>>(),
+            [
+                &DigestAlgorithm::Blake2b,
+                &DigestAlgorithm::Sha256,
+                &DigestAlgorithm::Other("sha3-256".to_owned()),
+            ],
+            "algorithms are parsed and ordered by name"
+        );
+        assert_eq!(
+            serde_json::to_string(&checksums).unwrap(),
+            r#"{"blake2b":"cc","sha256":"bb","sha3-256":"aa"}"#,
+            "unknown algorithms are written back out"
+        );
+        assert_eq!(
+            DigestAlgorithm::Other("sha256".to_owned()),
+            DigestAlgorithm::Sha256,
+            "algorithms with the same name are equal"
+        );
+    }
+
+    #[test]
+    fn test_digest_parse() {
+        let sha256 = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        let digest: PrefixedDigest = format!("sha256:{}", sha256).parse().unwrap();
+        assert_eq!(digest.algorithm, DigestAlgorithm::Sha256);
+        assert_eq!(
+            digest.to_string(),
+            format!("sha256:{}", sha256.to_ascii_lowercase()),
+            "digest is lowercased"
+        );
+
+        let digest: PrefixedDigest = "sha3-256:abcd".parse().unwrap();
+        assert_eq!(
+            digest.algorithm,
+            DigestAlgorithm::Other("sha3-256".to_owned()),
+            "any length is accepted for unknown algorithms"
+        );
+
+        for input in ["sha256:abcd", "sha256:", "blake2b:xyz", ":abcd", "abcd"] {
+            assert!(
+                input.parse::<PrefixedDigest>().is_err(),
+                "{} is rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_yank_reason_round_trip() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        let range_data = project.ranges.get_mut(&VersionRange::Minor(5)).unwrap();
+        let version_data = range_data.versions.get_mut(&version("0.5.2")).unwrap();
+        assert_eq!(
+            version_data.status,
+            ReleaseStatus::Yanked {
+                reason: None,
+                date: None
+            },
+            "plain yanked status is read without a reason"
+        );
+        version_data.status = ReleaseStatus::Yanked {
+            reason: Some("broken".to_owned()),
+            date: Some("2024-01-01T00:00:00Z".to_owned()),
+        };
+
+        let serialized = serde_json::to_string(&json).unwrap();
+        assert!(
+            serialized.contains(r#""status":"yanked","yank_reason":"broken""#),
+            "status stays a string, with the reason alongside it"
+        );
+        let json2: MuktiReleasesJson = serde_json::from_str(&serialized).unwrap();
+        let (_, version_data) = json2.projects["mukti"]
+            .get_version_data(&version("0.5.2"))
+            .unwrap();
+        assert_eq!(
+            version_data.status,
+            ReleaseStatus::Yanked {
+                reason: Some("broken".to_owned()),
+                date: Some("2024-01-01T00:00:00Z".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let mut value = serde_json::to_value(&json).unwrap();
+        let future = || serde_json::json!({ "added_in": "a future version" });
+
+        value["future_field"] = future();
+        let project = &mut value["projects"]["mukti"];
+        project["future_field"] = future();
+        project["info"] = serde_json::json!({ "description": "d", "future_field": future() });
+        project["aliases"] = serde_json::json!({
+            "linux": {
+                "target": "x86_64-unknown-linux-gnu",
+                "format": "tar.gz",
+                "future_field": future(),
+            },
+        });
+        let range = &mut project["ranges"]["0.5"];
+        range["future_field"] = future();
+        let version = &mut range["versions"]["0.5.3"];
+        version["future_field"] = future();
+        let location = &mut version["locations"][0];
+        location["future_field"] = future();
+        location["signatures"] = serde_json::json!([
+            {
+                "scheme": "minisign",
+                "url": "https://example.com/a.minisig",
+                "future_field": future(),
+            },
+        ]);
+        location["attestations"] = serde_json::json!([
+            {
+                "predicate_type": "https://slsa.dev/provenance/v1",
+                "url": "https://example.com/a.intoto.jsonl",
+                "future_field": future(),
+            },
+        ]);
+
+        let json: MuktiReleasesJson = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            json.extra["future_field"],
+            future(),
+            "top-level field is kept"
+        );
+        assert!(
+            !json.projects["mukti"].info.is_empty(),
+            "info with unknown fields isn't empty"
+        );
+        assert_eq!(
+            serde_json::to_value(&json).unwrap(),
+            value,
+            "unknown fields are written back out unchanged"
+        );
+    }
+
+    #[test]
+    fn test_base_url_round_trip() {
+        const BASE_URL: &str = "https://github.com/nextest-rs/mukti/releases/";
+        let location_urls = |json: &MuktiReleasesJson| -> Vec<String> {
+            json.projects["mukti"]
+                .all_versions()
+                .flat_map(|(_, version_data)| &version_data.locations)
+                .map(|location| location.url.clone())
+                .collect()
+        };
+
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let urls = location_urls(&json);
+        json.projects.get_mut("mukti").unwrap().base_url = Some(BASE_URL.to_owned());
+
+        let value = serde_json::to_value(&json).unwrap();
+        assert_eq!(
+            value["schema_version"], 2,
+            "files with a base URL are written with a schema old readers reject"
+        );
+        let serialized_url = &value["projects"]["mukti"]["ranges"]["0.5"]["versions"]["0.5.3"]
+            ["locations"][0]["url"];
+        assert_eq!(
+            serialized_url,
+            "download/mukti-bin-0.5.3/mukti-bin-0.5.3-x86_64-unknown-linux-gnu.tar.gz",
+            "URLs under the base URL are written out relative to it"
+        );
+
+        let json2: MuktiReleasesJson = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            location_urls(&json2),
+            urls,
+            "URLs are absolute in memory after round trip"
+        );
+
+        let project = &json2.projects["mukti"];
+        assert_eq!(
+            project.resolve_url("a/b.tar.gz"),
+            format!("{BASE_URL}a/b.tar.gz")
+        );
+        assert_eq!(
+            project.resolve_url("/a/b.tar.gz"),
+            format!("{BASE_URL}a/b.tar.gz")
+        );
+        assert_eq!(
+            project.resolve_url("https://example.com/b.tar.gz"),
+            "https://example.com/b.tar.gz",
+            "absolute URLs are unchanged"
+        );
+        assert_eq!(
+            project.relative_url("https://example.com/b.tar.gz"),
+            "https://example.com/b.tar.gz",
+            "URLs outside the base URL stay absolute"
+        );
+    }
+
+    #[test]
+    fn test_url_template_round_trip() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        project.url_template = Some("{name}-{version}-{target}.{format}".to_owned());
+        assert_eq!(
+            serde_json::to_value(&json).unwrap()["schema_version"],
+            2,
+            "files with a URL template are written with a schema old readers reject"
+        );
+
+        let project = json.projects.get_mut("mukti").unwrap();
+        project.base_url = Some("https://github.com/nextest-rs/mukti/releases/download".to_owned());
+        project.url_template =
+            Some("{name}-bin-{version}/{name}-bin-{version}-{target}.{format}".to_owned());
+        let locations = |json: &MuktiReleasesJson| -> Vec<String> {
+            json.projects["mukti"]
+                .all_versions()
+                .flat_map(|(_, version_data)| &version_data.locations)
+                .map(|location| location.url.clone())
+                .collect()
+        };
+        let urls = locations(&json);
+
+        let value = serde_json::to_value(&json).unwrap();
+        let serialized_locations =
+            &value["projects"]["mukti"]["ranges"]["0.5"]["versions"]["0.5.3"]["locations"];
+        assert_eq!(
+            serialized_locations[0].get("url"),
+            None,
+            "URL matching the template is omitted"
+        );
+        assert_eq!(
+            serialized_locations[1]["url"], "mukti-bin-0.5.3/",
+            "URL not matching the template is kept"
+        );
+
+        let json2: MuktiReleasesJson = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            locations(&json2),
+            urls,
+            "template is expanded after round trip"
+        );
+    }
+
+    #[test]
+    fn test_typed_metadata() {
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct NextestMetadata {
+            min_rust: String,
+        }
+
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        let version_data = project
+            .ranges
+            .get_mut(&VersionRange::Minor(5))
+            .unwrap()
+            .versions
+            .get_mut(&version("0.5.3"))
+            .unwrap();
+        assert_eq!(
+            version_data
+                .typed_metadata::<NextestMetadata>("nextest")
+                .unwrap(),
+            None,
+            "no metadata yet"
+        );
+
+        let nextest = NextestMetadata {
+            min_rust: "1.70".to_owned(),
+        };
+        version_data
+            .set_typed_metadata("nextest", &nextest)
+            .unwrap();
+        version_data.set_typed_metadata("other", &42).unwrap();
+        assert_eq!(
+            version_data.metadata,
+            serde_json::json!({ "nextest": { "min_rust": "1.70" }, "other": 42 }),
+        );
+        assert_eq!(
+            version_data.typed_metadata("nextest").unwrap(),
+            Some(nextest),
+            "metadata round trips"
+        );
+        assert!(
+            matches!(
+                version_data.typed_metadata::<NextestMetadata>("other"),
+                Err(TypedMetadataError::Convert { .. }),
+            ),
+            "wrong type fails to convert"
+        );
+
+        version_data.metadata = serde_json::json!([1, 2]);
+        assert!(
+            matches!(
+                version_data.set_typed_metadata("nextest", &1),
+                Err(TypedMetadataError::NotAnObject { kind: "an array" }),
+            ),
+            "can't add keys to an array"
+        );
+    }
+
+    #[test]
+    fn test_version_range_x() {
+        for (input, range, canonical, alternate) in [
+            ("1", VersionRange::Major(1), "1", "1.x"),
+            ("1.x", VersionRange::Major(1), "1", "1.x"),
+            ("0.9", VersionRange::Minor(9), "0.9", "0.9.x"),
+            ("0.9.x", VersionRange::Minor(9), "0.9", "0.9.x"),
+            ("0.0.3", VersionRange::Patch(3), "0.0.3", "0.0.3"),
+        ] {
+            let parsed: VersionRange = input.parse().unwrap();
+            assert_eq!(parsed, range, "{input} parses correctly");
+            assert_eq!(
+                parsed.to_string(),
+                canonical,
+                "{input} displays canonically"
+            );
+            assert_eq!(format!("{parsed:#}"), alternate, "{input} displays with .x");
+        }
+
+        assert_eq!(
+            serde_json::to_string(&VersionRange::Minor(9)).unwrap(),
+            r#""0.9""#,
+            "serialization is canonical"
+        );
+        for input in ["1.y", "x", "0.x", "0.0.x", "0.0.3.x", "1.x.x"] {
+            assert!(
+                input.parse::<VersionRange>().is_err(),
+                "{input} is rejected"
+            );
+        }
+        assert!(
+            serde_json::from_str::<VersionRange>(r#""1.x""#).is_err(),
+            "deserialization only accepts the canonical form"
+        );
+    }
+
+    fn hex_sha256(json: &MuktiReleasesJson) -> String {
+        let project = &json.projects["mukti"];
+        let (_, version_data) = project.get_version_data(&version("0.5.3")).unwrap();
+        version_data.locations[0].checksums[&DigestAlgorithm::Sha256]
+            .0
+            .clone()
+    }
+
     fn get_latest_matching_version<'a>(
         project: &'a MuktiProject,
         version_req_str: &str,
@@ -0,0 +1,376 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{MuktiProject, MuktiReleasesJson, ReleaseLocation, ReleaseVersionData};
+use semver::{Version, VersionReq};
+use std::collections::BTreeSet;
+
+/// Configuration for [`published_paths`], matching the options redirects are generated with.
+#[derive(Clone, Debug, Default)]
+pub struct PublishedPathsConfig {
+    /// The prefix all paths are served under, e.g. `/` or `/releases`.
+    pub prefix: String,
+
+    /// Aliases for specific targets and formats, in addition to those stored in each project.
+    /// These take precedence over stored aliases with the same name.
+    pub aliases: Vec<PathAlias>,
+
+    /// Whether friendly aliases are derived from target triples. See [`friendly_aliases`].
+    pub auto_aliases: bool,
+}
+
+/// An alias for a target and format, e.g. `linux` for `x86_64-unknown-linux-gnu` and `tar.gz`.
+///
+/// The target or format can be `*` to match any target or format. See [`PathAlias::name_for`] for
+/// how such aliases are named.
+#[derive(Clone, Debug)]
+pub struct PathAlias {
+    /// The alias.
+    pub alias: String,
+
+    /// The target the alias points to, or `*` for any target.
+    pub target: String,
+
+    /// The format the alias points to, or `*` for any format.
+    pub format: String,
+
+    /// If set, the alias is only published for this project.
+    pub project: Option<String>,
+
+    /// If set, the alias is only published for versions matching this requirement.
+    pub version_req: Option<VersionReq>,
+}
+
+impl PathAlias {
+    /// Whether this alias is published for `version` of `project`.
+    pub fn applies_to(&self, project: &str, version: &Version) -> bool {
+        self.applies_to_project(project) && self.applies_to_version(version)
+    }
+
+    /// Whether this alias is published for `project`.
+    pub fn applies_to_project(&self, project: &str) -> bool {
+        self.project.as_deref().map_or(true, |p| p == project)
+    }
+
+    /// Whether this alias is published for `version`, of any project it applies to.
+    pub fn applies_to_version(&self, version: &Version) -> bool {
+        self.version_req
+            .as_ref()
+            .map_or(true, |req| req.matches(version))
+    }
+
+    /// Whether this alias points to `location`.
+    pub fn matches_location(&self, location: &ReleaseLocation) -> bool {
+        (self.target == "*" || self.target == location.target)
+            && (self.format == "*" || self.format == location.format.as_str())
+    }
+
+    /// The name this alias is published under for `location`, which it must match.
+    ///
+    /// `{target}` and `{format}` in the alias are replaced with the location's target and format.
+    /// If the format is `*` and the alias doesn't contain `{format}`, `.FORMAT` is appended, so
+    /// that each format gets a path of its own.
+    pub fn name_for(&self, location: &ReleaseLocation) -> String {
+        let name = self
+            .alias
+            .replace("{target}", &location.target)
+            .replace("{format}", format_name(location));
+        if self.format == "*" && !self.alias.contains("{format}") {
+            format!("{}.{}", name, format_name(location))
+        } else {
+            name
+        }
+    }
+
+    /// Whether this alias is published as `name` in `version_data`.
+    ///
+    /// Aliases without wildcards claim their name even if they don't point to anything in this
+    /// version, so they always take precedence over friendly aliases.
+    pub fn is_named(&self, name: &str, version_data: &ReleaseVersionData) -> bool {
+        if self.target == "*" || self.format == "*" {
+            version_data
+                .redirect_locations()
+                .any(|location| self.matches_location(location) && self.name_for(location) == name)
+        } else {
+            self.alias == name
+        }
+    }
+}
+
+/// Returns the aliases published for `project`: those in `aliases` that apply to it, followed by
+/// those stored in [`MuktiProject::aliases`] that aren't overridden by one in `aliases` with the
+/// same name.
+pub fn project_aliases(
+    aliases: &[PathAlias],
+    project_name: &str,
+    project: &MuktiProject,
+) -> Vec<PathAlias> {
+    let passed_in = aliases
+        .iter()
+        .filter(|alias| alias.applies_to_project(project_name));
+    let stored = project
+        .aliases
+        .iter()
+        .filter(|(name, _)| {
+            !aliases
+                .iter()
+                .any(|alias| alias.alias == **name && alias.applies_to_project(project_name))
+        })
+        .map(|(name, alias)| PathAlias {
+            alias: name.clone(),
+            target: alias.target.clone(),
+            format: alias.format.clone(),
+            project: Some(project_name.to_owned()),
+            version_req: alias.version_req.clone(),
+        });
+    passed_in.cloned().chain(stored).collect()
+}
+
+/// Returns every path that redirects are generated for.
+///
+/// This covers the release page, each target and format, and each alias (including those stored
+/// in [`MuktiProject::aliases`](crate::MuktiProject::aliases)), for `latest`, every
+/// non-prerelease version range, and every individual version.
+pub fn published_paths(
+    release_json: &MuktiReleasesJson,
+    config: &PublishedPathsConfig,
+) -> BTreeSet<String> {
+    let prefix = config.prefix.trim_end_matches('/');
+    let mut paths = BTreeSet::new();
+
+    for (project_name, project) in &release_json.projects {
+        let aliases = project_aliases(&config.aliases, project_name, project);
+
+        let mut add_paths = |path_version: &str, version: &Version, version_data| {
+            add_version_paths(
+                &mut paths,
+                prefix,
+                path_version,
+                (project_name, version, version_data),
+                &aliases,
+                config.auto_aliases,
+            );
+        };
+
+        if let Some(range) = &project.latest {
+            if let Some(range_data) = project.ranges.get(range) {
+                if let Some(version_data) = range_data.versions.get(&range_data.latest) {
+                    add_paths("latest", &range_data.latest, version_data);
+                }
+            }
+        }
+
+        for (channel, version, version_data) in project.redirect_channels() {
+            add_paths(channel, version, version_data);
+        }
+
+        for (range, range_data) in &project.ranges {
+            if !range_data.is_prerelease {
+                if let Some(version_data) = range_data.versions.get(&range_data.latest) {
+                    add_paths(&range.to_string(), &range_data.latest, version_data);
+                }
+            }
+            for (version, version_data) in &range_data.versions {
+                add_paths(&version.to_string(), version, version_data);
+            }
+        }
+    }
+
+    paths
+}
+
+fn add_version_paths(
+    paths: &mut BTreeSet<String>,
+    prefix: &str,
+    path_version: &str,
+    (project_name, version, version_data): (&str, &Version, &ReleaseVersionData),
+    aliases: &[PathAlias],
+    auto_aliases: bool,
+) {
+    paths.insert(format!("{}/{}/release", prefix, path_version));
+
+    let aliases: Vec<_> = aliases
+        .iter()
+        .filter(|alias| alias.applies_to(project_name, version))
+        .collect();
+    for location in version_data.redirect_locations() {
+        paths.insert(format!(
+            "{}/{}/{}.{}",
+            prefix, path_version, location.target, location.format
+        ));
+        for alias in aliases
+            .iter()
+            .filter(|alias| alias.matches_location(location))
+        {
+            paths.insert(format!(
+                "{}/{}/{}",
+                prefix,
+                path_version,
+                alias.name_for(location)
+            ));
+        }
+    }
+    for location in version_data.debug_symbols() {
+        paths.insert(format!(
+            "{}/{}/{}-debug-symbols",
+            prefix, path_version, location.target
+        ));
+    }
+
+    if auto_aliases {
+        // Explicit aliases take precedence over friendly aliases with the same name.
+        for (alias, _) in friendly_aliases(version_data) {
+            if !aliases
+                .iter()
+                .any(|explicit| explicit.is_named(&alias, version_data))
+            {
+                paths.insert(format!("{}/{}/{}", prefix, path_version, alias));
+            }
+        }
+    }
+}
+
+/// Friendly names, along with the targets they can refer to in order of preference.
+///
+/// Targets that can run on more than one platform (e.g. universal macOS binaries) are listed as
+/// fallbacks for each of them.
+static FRIENDLY_ALIAS_TABLE: &[(&str, &[&str])] = &[
+    (
+        "linux-x64",
+        &["x86_64-unknown-linux-gnu", "x86_64-unknown-linux-musl"],
+    ),
+    (
+        "linux-x86",
+        &["i686-unknown-linux-gnu", "i686-unknown-linux-musl"],
+    ),
+    (
+        "linux-arm64",
+        &["aarch64-unknown-linux-gnu", "aarch64-unknown-linux-musl"],
+    ),
+    (
+        "linux-armv7",
+        &[
+            "armv7-unknown-linux-gnueabihf",
+            "armv7-unknown-linux-musleabihf",
+        ],
+    ),
+    ("linux-riscv64", &["riscv64gc-unknown-linux-gnu"]),
+    ("linux-ppc64le", &["powerpc64le-unknown-linux-gnu"]),
+    ("linux-s390x", &["s390x-unknown-linux-gnu"]),
+    (
+        "macos-x64",
+        &["x86_64-apple-darwin", "universal-apple-darwin"],
+    ),
+    (
+        "macos-arm64",
+        &["aarch64-apple-darwin", "universal-apple-darwin"],
+    ),
+    ("macos-universal", &["universal-apple-darwin"]),
+    (
+        "windows-x64",
+        &["x86_64-pc-windows-msvc", "x86_64-pc-windows-gnu"],
+    ),
+    (
+        "windows-x86",
+        &["i686-pc-windows-msvc", "i686-pc-windows-gnu"],
+    ),
+    ("windows-arm64", &["aarch64-pc-windows-msvc"]),
+    ("freebsd-x64", &["x86_64-unknown-freebsd"]),
+    ("illumos-x64", &["x86_64-unknown-illumos"]),
+];
+
+/// Derive friendly aliases like `linux-x64` from the targets present in `version_data`.
+///
+/// For each friendly name, `{name}.{format}` is returned for every format of the preferred
+/// target, and a bare `{name}` points to its preferred format: zip on Windows, and tar.gz
+/// elsewhere.
+pub fn friendly_aliases(version_data: &ReleaseVersionData) -> Vec<(String, &ReleaseLocation)> {
+    let mut aliases = Vec::new();
+
+    for (name, candidates) in FRIENDLY_ALIAS_TABLE {
+        let target = candidates.iter().find(|target| {
+            version_data
+                .installable_locations()
+                .any(|location| location.target == **target)
+        });
+        let target = match target {
+            Some(target) => target,
+            None => continue,
+        };
+        let locations: Vec<_> = version_data
+            .installable_locations()
+            .filter(|location| location.target == *target)
+            .collect();
+
+        let preferred_format = if name.starts_with("windows-") {
+            "zip"
+        } else {
+            "tar.gz"
+        };
+        let preferred = locations
+            .iter()
+            .find(|location| format_name(location) == preferred_format)
+            .unwrap_or(&locations[0]);
+        aliases.push((name.to_string(), *preferred));
+
+        for location in locations {
+            aliases.push((format!("{}.{}", name, format_name(location)), location));
+        }
+    }
+
+    aliases
+}
+
+/// Formats may be recorded with or without a leading dot.
+fn format_name(location: &ReleaseLocation) -> &str {
+    location.format.as_str().trim_start_matches('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FIXTURE_JSON: &str = include_str!("../../fixtures/mukti-releases.json");
+
+    #[test]
+    fn test_published_paths() {
+        let json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let config = PublishedPathsConfig {
+            prefix: "/mukti/".to_owned(),
+            aliases: vec![
+                PathAlias {
+                    alias: "linux".to_owned(),
+                    target: "x86_64-unknown-linux-gnu".to_owned(),
+                    format: "tar.gz".to_owned(),
+                    project: None,
+                    version_req: Some(VersionReq::parse(">=0.5.2").unwrap()),
+                },
+                PathAlias {
+                    alias: "win".to_owned(),
+                    target: "x86_64-pc-windows-msvc".to_owned(),
+                    format: "*".to_owned(),
+                    project: None,
+                    version_req: None,
+                },
+            ],
+            auto_aliases: true,
+        };
+        let paths = published_paths(&json, &config);
+
+        for path in [
+            "/mukti/latest/release",
+            "/mukti/latest/linux",
+            "/mukti/0.5/x86_64-pc-windows-msvc.zip",
+            "/mukti/0.5.3/linux",
+            "/mukti/0.5.3/windows-x64",
+            "/mukti/0.5.3/win.zip",
+            "/mukti/0.5.3/win.tar.gz",
+            "/mukti/0.6.0-alpha.1/release",
+        ] {
+            assert!(paths.contains(path), "{path} is published");
+        }
+        for path in ["/mukti/0.5.1/linux", "/mukti/0.6/release"] {
+            assert!(!paths.contains(path), "{path} is not published");
+        }
+    }
+}
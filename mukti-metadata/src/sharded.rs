@@ -0,0 +1,453 @@
+// Copyright (c) The mukti Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    DigestEncoding, MuktiProject, MuktiReleasesJson, ProjectAlias, ProjectInfo,
+    ReadReleasesJsonError, ReleaseRangeData, ShardAssembleError, ToShardedError, VersionRange,
+    CURRENT_SCHEMA_VERSION,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+/// The index file of a sharded releases.json.
+///
+/// A sharded layout consists of this index, plus one file per version range. Each shard is itself
+/// a [`MuktiReleasesJson`] containing a single project with a single range, so clients that only
+/// care about one range can download just that file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShardedIndex {
+    /// Always [`Layout::Sharded`].
+    pub layout: Layout,
+
+    /// The schema version of the index and its shards.
+    #[serde(default)]
+    pub schema_version: u32,
+
+    /// How digests are encoded in the shard files.
+    #[serde(default, skip_serializing_if = "DigestEncoding::is_hex")]
+    pub digest_encoding: DigestEncoding,
+
+    /// The projects that are part of this index.
+    pub projects: BTreeMap<String, ShardedProject>,
+
+    /// See [`MuktiReleasesJson::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// The layout of a releases.json file.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum Layout {
+    /// A single file containing all data.
+    Single,
+
+    /// An index pointing to one file per version range.
+    Sharded,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::Single
+    }
+}
+
+/// A project within a [`ShardedIndex`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ShardedProject {
+    /// See [`MuktiProject::info`].
+    #[serde(default, skip_serializing_if = "ProjectInfo::is_empty")]
+    pub info: ProjectInfo,
+
+    /// See [`MuktiProject::base_url`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+
+    /// See [`MuktiProject::url_template`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+
+    /// The latest version range without any pre-releases.
+    pub latest: Option<VersionRange>,
+
+    /// See [`MuktiProject::latest_pinned`].
+    #[serde(default, skip_serializing_if = "crate::models::is_false")]
+    pub latest_pinned: bool,
+
+    /// Map of version range to the shard containing its versions.
+    #[serde(serialize_with = "crate::models::serialize_reverse")]
+    pub ranges: BTreeMap<VersionRange, RangeShard>,
+
+    /// See [`MuktiProject::channels`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, Version>,
+
+    /// See [`MuktiProject::aliases`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, ProjectAlias>,
+
+    /// See [`MuktiProject::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// A reference to the shard for a version range.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RangeShard {
+    /// The path to the shard, relative to the index.
+    pub path: String,
+
+    /// The latest version within this range (can be a prerelease).
+    pub latest: Version,
+
+    /// True if this version range only has prereleases.
+    pub is_prerelease: bool,
+
+    /// See [`ReleaseRangeData::eol_date`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eol_date: Option<String>,
+
+    /// See [`ReleaseRangeData::extra`].
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl MuktiReleasesJson {
+    /// Split this releases.json into an index and one shard per version range.
+    ///
+    /// Shards are returned as a list of paths relative to the index, and their contents. Each
+    /// project's shards are placed in a directory named after the project, so this fails if a
+    /// project name isn't usable as a directory name, such as `..` or a name containing a slash.
+    pub fn to_sharded(
+        &self,
+    ) -> Result<(ShardedIndex, Vec<(String, MuktiReleasesJson)>), ToShardedError> {
+        if let Some(name) = self.projects.keys().find(|name| !is_path_component(name)) {
+            return Err(ToShardedError::InvalidProjectName { name: name.clone() });
+        }
+
+        let mut shards = Vec::new();
+        let projects = self
+            .projects
+            .iter()
+            .map(|(name, project)| {
+                let ranges = project
+                    .ranges
+                    .iter()
+                    .map(|(range, range_data)| {
+                        let path = format!("{}/{}.json", name, range);
+                        let shard_project = MuktiProject {
+                            info: ProjectInfo::default(),
+                            base_url: project.base_url.clone(),
+                            url_template: project.url_template.clone(),
+                            latest: Some(*range),
+                            latest_pinned: false,
+                            ranges: std::iter::once((*range, range_data.clone())).collect(),
+                            channels: BTreeMap::new(),
+                            aliases: BTreeMap::new(),
+                            extra: BTreeMap::new(),
+                        };
+                        let shard = MuktiReleasesJson {
+                            schema_version: self.schema_version,
+                            digest_encoding: self.digest_encoding,
+                            projects: std::iter::once((name.clone(), shard_project)).collect(),
+                            extra: BTreeMap::new(),
+                        };
+                        shards.push((path.clone(), shard));
+
+                        let range_shard = RangeShard {
+                            path,
+                            latest: range_data.latest.clone(),
+                            is_prerelease: range_data.is_prerelease,
+                            eol_date: range_data.eol_date.clone(),
+                            // Unknown fields are kept with the rest of the range's data, in the
+                            // shard.
+                            extra: BTreeMap::new(),
+                        };
+                        (*range, range_shard)
+                    })
+                    .collect();
+                let sharded_project = ShardedProject {
+                    info: project.info.clone(),
+                    base_url: project.base_url.clone(),
+                    url_template: project.url_template.clone(),
+                    latest: project.latest,
+                    latest_pinned: project.latest_pinned,
+                    ranges,
+                    channels: project.channels.clone(),
+                    aliases: project.aliases.clone(),
+                    extra: project.extra.clone(),
+                };
+                (name.clone(), sharded_project)
+            })
+            .collect();
+
+        let index = ShardedIndex {
+            layout: Layout::Sharded,
+            schema_version: self.schema_version.max(self.required_schema_version()),
+            digest_encoding: self.digest_encoding,
+            projects,
+            extra: self.extra.clone(),
+        };
+        Ok((index, shards))
+    }
+}
+
+impl ShardedIndex {
+    /// Reassemble a full releases.json from this index, using `load_shard` to read each shard by
+    /// its path relative to the index.
+    ///
+    /// Shard paths that are absolute or contain `..` are rejected before `load_shard` is called,
+    /// so an index can't refer to files outside its own directory.
+    pub fn assemble<E>(
+        self,
+        mut load_shard: impl FnMut(&str) -> Result<MuktiReleasesJson, E>,
+    ) -> Result<MuktiReleasesJson, ShardAssembleError<E>> {
+        let mut projects = BTreeMap::new();
+        for (name, sharded_project) in self.projects {
+            let mut ranges = BTreeMap::new();
+            for (range, range_shard) in sharded_project.ranges {
+                if !is_relative_path(&range_shard.path) {
+                    return Err(ShardAssembleError::InvalidPath {
+                        path: range_shard.path,
+                    });
+                }
+                let mut shard =
+                    load_shard(&range_shard.path).map_err(|error| ShardAssembleError::Load {
+                        path: range_shard.path.clone(),
+                        error,
+                    })?;
+                let shard_range_data = shard
+                    .projects
+                    .remove(&name)
+                    .and_then(|mut project| project.ranges.remove(&range))
+                    .ok_or_else(|| ShardAssembleError::MissingRange {
+                        path: range_shard.path.clone(),
+                        project: name.clone(),
+                        range,
+                    })?;
+                let range_data = ReleaseRangeData {
+                    latest: range_shard.latest,
+                    is_prerelease: range_shard.is_prerelease,
+                    eol_date: range_shard.eol_date,
+                    versions: shard_range_data.versions,
+                    extra: shard_range_data.extra,
+                };
+                ranges.insert(range, range_data);
+            }
+
+            let project = MuktiProject {
+                info: sharded_project.info,
+                base_url: sharded_project.base_url,
+                url_template: sharded_project.url_template,
+                latest: sharded_project.latest,
+                latest_pinned: sharded_project.latest_pinned,
+                ranges,
+                channels: sharded_project.channels,
+                aliases: sharded_project.aliases,
+                extra: sharded_project.extra,
+            };
+            projects.insert(name, project);
+        }
+
+        Ok(MuktiReleasesJson {
+            schema_version: self.schema_version,
+            digest_encoding: self.digest_encoding,
+            projects,
+            extra: self.extra,
+        })
+    }
+}
+
+/// Used to inspect a file without fully parsing it.
+#[derive(Deserialize)]
+struct FileProbe {
+    #[serde(default)]
+    layout: Layout,
+
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// Returns the layout of the releases.json contained in `json`.
+pub fn detect_layout(json: &str) -> Result<Layout, serde_json::Error> {
+    let probe: FileProbe = serde_json::from_str(json)?;
+    Ok(probe.layout)
+}
+
+/// Returns the schema version of the releases.json contained in `json`.
+///
+/// Unlike deserializing the file, this works even if the schema is newer than this crate
+/// understands.
+pub fn detect_schema_version(json: &str) -> Result<u32, serde_json::Error> {
+    let probe: FileProbe = serde_json::from_str(json)?;
+    Ok(probe.schema_version)
+}
+
+/// Read a releases.json file from disk, transparently reassembling it if it is a sharded index.
+///
+/// Files with a schema version newer than [`CURRENT_SCHEMA_VERSION`] are rejected.
+pub fn read_releases_json(path: &Path) -> Result<MuktiReleasesJson, ReadReleasesJsonError> {
+    let json = read_to_string(path)?;
+    let probe: FileProbe =
+        serde_json::from_str(&json).map_err(|error| ReadReleasesJsonError::Parse {
+            path: path.to_owned(),
+            error,
+        })?;
+    if probe.schema_version > CURRENT_SCHEMA_VERSION {
+        return Err(ReadReleasesJsonError::UnsupportedSchema {
+            path: path.to_owned(),
+            schema_version: probe.schema_version,
+        });
+    }
+    match probe.layout {
+        Layout::Single => parse_file(path, &json),
+        Layout::Sharded => {
+            let index: ShardedIndex = parse_file(path, &json)?;
+            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+            index
+                .assemble(|shard_path| {
+                    let shard_path = dir.join(shard_path);
+                    let json = read_to_string(&shard_path)?;
+                    parse_file(&shard_path, &json)
+                })
+                .map_err(|error| match error {
+                    ShardAssembleError::Load { error, .. } => error,
+                    ShardAssembleError::MissingRange {
+                        path: shard_path,
+                        project,
+                        range,
+                    } => ReadReleasesJsonError::MissingRange {
+                        path: dir.join(shard_path),
+                        project,
+                        range,
+                    },
+                    ShardAssembleError::InvalidPath { path: shard_path } => {
+                        ReadReleasesJsonError::InvalidShardPath {
+                            path: path.to_owned(),
+                            shard_path,
+                        }
+                    }
+                })
+        }
+    }
+}
+
+/// Returns true if `name` can be used as a single file or directory name.
+fn is_path_component(name: &str) -> bool {
+    !name.contains(['/', '\\']) && is_relative_path(name)
+}
+
+/// Returns true if `path` is relative and doesn't refer to anything outside the directory it's
+/// relative to.
+///
+/// Backslashes are rejected too, since they're separators on Windows.
+fn is_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains('\\')
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn read_to_string(path: &Path) -> Result<String, ReadReleasesJsonError> {
+    std::fs::read_to_string(path).map_err(|error| ReadReleasesJsonError::Read {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+fn parse_file<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    json: &str,
+) -> Result<T, ReadReleasesJsonError> {
+    serde_json::from_str(json).map_err(|error| ReadReleasesJsonError::Parse {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FIXTURE_JSON: &str = include_str!("../../fixtures/mukti-releases.json");
+
+    #[test]
+    fn test_sharded_round_trip() {
+        let mut json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        let project = json.projects.get_mut("mukti").unwrap();
+        project.info.description = Some("Release metadata manager".to_owned());
+        let future = || ("future_field".to_owned(), "a future field".into());
+        project.extra.extend([future()]);
+        let range_data = project.ranges.values_mut().next().unwrap();
+        range_data.eol_date = Some("2024-01-01".to_owned());
+        range_data.extra.extend([future()]);
+        json.extra.extend([future()]);
+        let (index, shards) = json.to_sharded().unwrap();
+
+        let index_json = serde_json::to_string(&index).unwrap();
+        assert_eq!(detect_layout(&index_json).unwrap(), Layout::Sharded);
+        assert_eq!(detect_layout(FIXTURE_JSON).unwrap(), Layout::Single);
+
+        let shards: BTreeMap<_, _> = shards
+            .into_iter()
+            .map(|(path, shard)| (path, serde_json::to_string(&shard).unwrap()))
+            .collect();
+        let index: ShardedIndex = serde_json::from_str(&index_json).unwrap();
+        let assembled = index
+            .assemble(|path| serde_json::from_str(&shards[path]))
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&assembled).unwrap(),
+            serde_json::to_value(&json).unwrap(),
+            "assembled releases JSON matches the original"
+        );
+    }
+
+    #[test]
+    fn test_shard_paths() {
+        let json: MuktiReleasesJson = serde_json::from_str(FIXTURE_JSON).unwrap();
+        for name in ["..", ".", "", "a/b", "/abs", "a\\b"] {
+            let mut json = json.clone();
+            let project = json.projects.remove("mukti").unwrap();
+            json.projects.insert(name.to_owned(), project);
+            assert!(
+                matches!(
+                    json.to_sharded(),
+                    Err(ToShardedError::InvalidProjectName { name: n }) if n == name
+                ),
+                "project name {name:?} is rejected"
+            );
+        }
+
+        let (index, _) = json.to_sharded().unwrap();
+        for path in [
+            "../mukti/0.5.json",
+            "mukti/../../0.5.json",
+            "/etc/passwd",
+            "a\\..\\b",
+            "",
+        ] {
+            let mut index = index.clone();
+            for range_shard in index.projects.get_mut("mukti").unwrap().ranges.values_mut() {
+                range_shard.path = path.to_owned();
+            }
+            let result = index.assemble(|_| -> Result<MuktiReleasesJson, ()> {
+                panic!("shard {path:?} isn't loaded")
+            });
+            assert!(
+                matches!(result, Err(ShardAssembleError::InvalidPath { path: p }) if p == path),
+                "shard path {path:?} is rejected"
+            );
+        }
+    }
+}